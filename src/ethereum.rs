@@ -1,25 +1,32 @@
 //! Module contains implementation for instantiating `namespace`s with a little
 //! help from macros.
 
+pub mod address;
 #[macro_use]
 mod api;
+pub mod bytes;
 pub mod encoding;
 pub mod models;
 mod provider;
+pub mod revert;
 pub mod serialization;
 
 pub use self::api::{Api, ApiError};
-use self::encoding::{Data, Quantity};
+use self::encoding::{Data, Decode, Quantity, U256};
 use self::models::*;
 pub use self::provider::Provider;
-use self::serialization::MaybeSyncing;
+pub use self::revert::Revert;
+use self::serialization::{MaybeSyncing, SyncingStatus};
+use crate::jsonrpc::{Duplex, Subscription};
+use crate::transport::DuplexTransport;
+use serde::de::DeserializeOwned;
 
 api! {
     module Web3 [
         eth => Eth,
     ] {
         web3_clientVersion as client_version() -> String;
-        web3_sha3 as sha3(bytes: impl AsRef<[u8]> [ Data<_> ]) -> Hash [ Data<_> ];
+        web3_sha3 as sha3(bytes: impl AsRef<[u8]> [ Data<_> ]) -> Hash;
     }
 
     module Net [] {
@@ -33,18 +40,273 @@ api! {
     ] {
         eth_protocolVersion as protocol_version() -> String;
         eth_syncing as syncing() -> Option<Syncing> [ MaybeSyncing ];
-        eth_coinbase as coinbase() -> Address [ Data<_> ];
+        eth_coinbase as coinbase() -> Address;
         eth_mining as mining() -> bool;
         eth_hashrate as hashrate() -> usize [ Quantity<_> ];
         eth_gasPrice as gas_price() -> u128 [ Quantity<_> ];
+        eth_getBalance as get_balance(address: Address, block: BlockId) -> U256 [ Quantity<_> ];
+        eth_getTransactionCount as get_transaction_count(
+            address: Address,
+            block: BlockId,
+        ) -> u64 [ Quantity<_> ];
+        eth_getCode as get_code(address: Address, block: BlockId) -> Bytes;
+        eth_getStorageAt as get_storage_at(
+            address: Address,
+            slot: U256 [ Quantity<_> ],
+            block: BlockId,
+        ) -> Hash;
     }
 }
 
+impl<T> Eth<'_, Duplex<T>>
+where
+    T: DuplexTransport,
+{
+    /// Subscribes to new block headers. This includes the headers of blocks
+    /// that become part of the canonical chain because of a reorganization.
+    pub async fn subscribe_new_heads(
+        &self,
+    ) -> Result<Subscription<'_, T, BlockHeader>, ApiError<Duplex<T>>> {
+        self.0
+            .subscribe_with("eth", ("newHeads",), decode::<_, BlockHeader>)
+            .await
+    }
+
+    /// Subscribes to logs matching the specified filter that are included in
+    /// new blocks.
+    pub async fn subscribe_logs(
+        &self,
+        filter: &LogFilter,
+    ) -> Result<Subscription<'_, T, Log>, ApiError<Duplex<T>>> {
+        self.0
+            .subscribe_with("eth", ("logs", filter), decode::<_, Log>)
+            .await
+    }
+
+    /// Subscribes to the hashes of transactions that are added to the pending
+    /// state of the node.
+    pub async fn subscribe_new_pending_transactions(
+        &self,
+    ) -> Result<Subscription<'_, T, Hash>, ApiError<Duplex<T>>> {
+        self.0
+            .subscribe_with("eth", ("newPendingTransactions",), decode::<_, Hash>)
+            .await
+    }
+
+    /// Subscribes to changes in the sync status of the node.
+    pub async fn subscribe_syncing(
+        &self,
+    ) -> Result<Subscription<'_, T, Option<Syncing>>, ApiError<Duplex<T>>> {
+        self.0
+            .subscribe_with("eth", ("syncing",), decode::<_, SyncingStatus>)
+            .await
+    }
+}
+
+/// Decodes a subscription notification result through a proxy type.
+fn decode<R, E>(notification: &str) -> Result<R, serde_json::Error>
+where
+    R: Decode<E>,
+    E: DeserializeOwned,
+{
+    Ok(R::decode(serde_json::from_str(notification)?))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::ethereum::bytes::FixedBytes;
+    #[cfg(feature = "mock")]
+    use crate::transport::MockTransport;
     use serde_json::json;
 
+    #[cfg(feature = "mock")]
+    #[test]
+    fn concurrent_calls() {
+        let mut transport = MockTransport::default();
+        transport
+            .expect_call("web3_clientVersion", json!([]), Ok(json!("test")))
+            .expect_call("net_version", json!([]), Ok(json!("42")));
+        let web3 = Web3(Provider::new(transport));
+
+        let (client_version, net_version) = futures::executor::block_on(futures::future::join(
+            web3.client_version(),
+            web3.eth().net().version(),
+        ));
+
+        assert_eq!(client_version.unwrap(), "test");
+        assert_eq!(net_version.unwrap(), "42");
+    }
+
+    #[test]
+    fn batched_calls() {
+        use crate::jsonrpc::Client;
+        use crate::transport::Transport;
+        use serde_json::Value;
+        use std::convert::Infallible;
+        use std::future::{self, Ready};
+
+        /// A transport that responds to batches with each method name as the
+        /// result, in reverse order.
+        struct MethodNames;
+
+        impl Transport for MethodNames {
+            type Error = Infallible;
+            type Call<'a> = Ready<Result<Vec<u8>, Infallible>>;
+
+            fn call<'a>(&'a self, request: &'a [u8]) -> Self::Call<'a> {
+                let requests = serde_json::from_slice::<Vec<Value>>(request).unwrap();
+                let responses = requests
+                    .iter()
+                    .rev()
+                    .map(|request| {
+                        json!({
+                            "jsonrpc": "2.0",
+                            "result": request["method"],
+                            "id": request["id"],
+                        })
+                    })
+                    .collect::<Vec<_>>();
+                future::ready(Ok(serde_json::to_vec(&responses).unwrap()))
+            }
+        }
+
+        let client = Client::new(MethodNames);
+        let batch = client.batch();
+        let web3 = Web3(Provider::from(&batch));
+
+        let client_version = web3.client_version();
+        let eth = web3.eth();
+        let net = eth.net();
+        let net_version = net.version();
+        let protocol_version = eth.protocol_version();
+
+        futures::executor::block_on(async {
+            batch.send().await.unwrap();
+            assert_eq!(client_version.await.unwrap(), "web3_clientVersion");
+            assert_eq!(net_version.await.unwrap(), "net_version");
+            assert_eq!(protocol_version.await.unwrap(), "eth_protocolVersion");
+        });
+    }
+
+    #[test]
+    fn subscriptions() {
+        use crate::jsonrpc::Client;
+        use futures::StreamExt;
+        use serde_json::Value;
+        use std::convert::Infallible;
+        use std::future::{self, Ready};
+        use std::sync::Mutex;
+        use std::task::{Context, Poll};
+
+        /// A duplex transport that responds to subscriptions with a single
+        /// notification, recording the subscription parameters.
+        #[derive(Default)]
+        struct Node(Mutex<(Vec<Value>, Vec<Value>)>);
+
+        impl DuplexTransport for Node {
+            type Error = Infallible;
+            type Send<'a> = Ready<Result<(), Infallible>>;
+
+            fn send(&self, message: Vec<u8>) -> Self::Send<'_> {
+                let request = serde_json::from_slice::<Value>(&message).unwrap();
+                let params = request["params"].clone();
+                let result = match params[0].as_str().unwrap() {
+                    "newPendingTransactions" => json!(format!("0x{}", "42".repeat(32))),
+                    "syncing" => json!({
+                        "syncing": true,
+                        "status": {
+                            "startingBlock": "0x0",
+                            "currentBlock": "0x1",
+                            "highestBlock": "0x2",
+                        },
+                    }),
+                    "logs" => json!({
+                        "address": format!("0x{}", "01".repeat(20)),
+                        "topics": [format!("0x{}", "02".repeat(32))],
+                        "data": "0x",
+                        "blockHash": format!("0x{}", "03".repeat(32)),
+                        "blockNumber": "0x2a",
+                        "transactionHash": format!("0x{}", "04".repeat(32)),
+                        "transactionIndex": "0x0",
+                        "logIndex": "0x1",
+                        "removed": false,
+                    }),
+                    _ => Value::Null,
+                };
+
+                let mut state = self.0.lock().unwrap();
+                let (subscriptions, inbox) = &mut *state;
+                subscriptions.push(params);
+                inbox.push(json!({
+                    "jsonrpc": "2.0",
+                    "result": "0x1",
+                    "id": request["id"],
+                }));
+                inbox.push(json!({
+                    "jsonrpc": "2.0",
+                    "method": "eth_subscription",
+                    "params": { "subscription": "0x1", "result": result },
+                }));
+                future::ready(Ok(()))
+            }
+
+            fn poll_receive(&self, _: &mut Context) -> Poll<Result<Vec<u8>, Infallible>> {
+                let message = self.0.lock().unwrap().1.remove(0);
+                Poll::Ready(Ok(serde_json::to_vec(&message).unwrap()))
+            }
+        }
+
+        let eth = Eth(Provider::from(Client::duplex(Node::default())));
+
+        futures::executor::block_on(async {
+            let mut transactions = eth.subscribe_new_pending_transactions().await.unwrap();
+            assert_eq!(transactions.next().await.unwrap().unwrap(), [0x42; 32]);
+
+            let mut syncing = eth.subscribe_syncing().await.unwrap();
+            assert_eq!(
+                syncing.next().await.unwrap().unwrap(),
+                Some(Syncing {
+                    starting_block: 0,
+                    current_block: 1,
+                    highest_block: 2,
+                }),
+            );
+
+            let filter = LogFilter {
+                address: vec![Address([1; 20])],
+                topics: vec![None, Some(vec![FixedBytes([2; 32])])],
+            };
+            let mut logs = eth.subscribe_logs(&filter).await.unwrap();
+            assert_eq!(
+                logs.next().await.unwrap().unwrap(),
+                Log {
+                    address: Address([1; 20]),
+                    topics: vec![FixedBytes([2; 32])],
+                    data: Bytes::new(),
+                    block_hash: FixedBytes([3; 32]),
+                    block_number: 42,
+                    transaction_hash: FixedBytes([4; 32]),
+                    transaction_index: 0,
+                    log_index: 1,
+                    removed: false,
+                },
+            );
+        });
+
+        let subscriptions = &eth.0.transport().transport().0.lock().unwrap().0;
+        assert_eq!(
+            subscriptions[2],
+            json!([
+                "logs",
+                {
+                    "address": [format!("0x{}", "01".repeat(20))],
+                    "topics": [null, [format!("0x{}", "02".repeat(32))]],
+                },
+            ]),
+        );
+    }
+
     api_test! {
         web3_clientVersion as Web3::client_version {
             (): json!([]) => json!("test"), == "test";
@@ -82,5 +344,33 @@ mod tests {
                 });
             (): json!([]) => json!(false), == None;
         }
+        eth_getBalance as Eth::get_balance {
+            (Address([1; 20]), BlockId::Latest):
+                json!([format!("0x{}", "01".repeat(20)), "latest"])
+                => json!("0x2a"),
+                == U256::from(42_u64);
+        }
+        eth_getTransactionCount as Eth::get_transaction_count {
+            (Address([1; 20]), BlockId::Number(42)):
+                json!([format!("0x{}", "01".repeat(20)), "0x2a"])
+                => json!("0x1"),
+                == 1;
+        }
+        eth_getCode as Eth::get_code {
+            (Address([1; 20]), BlockId::Finalized):
+                json!([format!("0x{}", "01".repeat(20)), "finalized"])
+                => json!("0x0402"),
+                == Bytes(vec![4, 2]);
+        }
+        eth_getStorageAt as Eth::get_storage_at {
+            (Address([1; 20]), U256::from(1_u64), BlockId::from(FixedBytes([2; 32]))):
+                json!([
+                    format!("0x{}", "01".repeat(20)),
+                    "0x1",
+                    { "blockHash": format!("0x{}", "02".repeat(32)) },
+                ])
+                => json!(format!("0x{}", "03".repeat(32))),
+                == [3; 32];
+        }
     }
 }
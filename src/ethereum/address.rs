@@ -0,0 +1,293 @@
+//! Module containing the Ethereum address type, with support for EIP-55 mixed
+//! case checksums and the EIP-1191 chain specific variant.
+
+use hex::FromHex;
+use serde::de::{self, Deserialize, Deserializer};
+use serde::ser::{Serialize, Serializer};
+use std::fmt::{self, Debug, Display, Formatter, LowerHex, UpperHex};
+use std::str::FromStr;
+use thiserror::Error;
+use tiny_keccak::{Hasher, Keccak};
+
+/// A 20-byte Ethereum address.
+///
+/// Addresses are formatted with an EIP-55 checksum, and serialized as
+/// lowercase hex strings. When parsing, all lowercase and all uppercase
+/// addresses are accepted as is, while mixed case addresses must have a valid
+/// checksum.
+#[derive(Clone, Copy, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct Address(pub [u8; 20]);
+
+impl Address {
+    /// The zero address.
+    pub const ZERO: Self = Address([0; 20]);
+
+    /// Creates an address from its bytes.
+    pub const fn new(bytes: [u8; 20]) -> Self {
+        Address(bytes)
+    }
+
+    /// Returns the bytes of the address.
+    pub const fn to_bytes(self) -> [u8; 20] {
+        self.0
+    }
+
+    /// Returns the address as a mixed case checksummed hex string. The
+    /// checksum is chain specific as specified by EIP-1191 if a chain ID is
+    /// specified, and the EIP-55 checksum otherwise.
+    ///
+    /// Note that EIP-1191 checksums are only used by a few chains, such as RSK,
+    /// and most wallets and explorers expect EIP-55 checksums.
+    pub fn to_checksum(&self, chain_id: Option<u64>) -> String {
+        let hex = hex::encode(self.0);
+        let hash = checksum_hash(&hex, chain_id);
+
+        let mut checksum = String::with_capacity(42);
+        checksum.push_str("0x");
+        for (i, c) in hex.chars().enumerate() {
+            if nibble(&hash, i) >= 8 {
+                checksum.push(c.to_ascii_uppercase());
+            } else {
+                checksum.push(c);
+            }
+        }
+        checksum
+    }
+
+    /// Parses a `0x` prefixed hex address, validating the chain specific
+    /// EIP-1191 checksum for the specified chain if the address is mixed case,
+    /// or the EIP-55 checksum if no chain ID is specified.
+    pub fn parse_checksum(s: &str, chain_id: Option<u64>) -> Result<Self, ParseAddressError> {
+        let hex = s
+            .strip_prefix("0x")
+            .ok_or(ParseAddressError::MissingPrefix)?;
+        if hex.len() != 40 {
+            return Err(ParseAddressError::InvalidLength);
+        }
+        let address =
+            Address(<[u8; 20]>::from_hex(hex).map_err(|_| ParseAddressError::InvalidHex)?);
+
+        let lowercase = !hex.bytes().any(|c| c.is_ascii_uppercase());
+        let uppercase = !hex.bytes().any(|c| c.is_ascii_lowercase());
+        if !lowercase && !uppercase && address.to_checksum(chain_id) != s {
+            return Err(ParseAddressError::InvalidChecksum);
+        }
+
+        Ok(address)
+    }
+}
+
+/// Returns the hash used for computing the checksum of a lowercase hex
+/// address.
+fn checksum_hash(hex: &str, chain_id: Option<u64>) -> [u8; 32] {
+    let mut hasher = Keccak::v256();
+    if let Some(chain_id) = chain_id {
+        hasher.update(format!("{}0x", chain_id).as_bytes());
+    }
+    hasher.update(hex.as_bytes());
+
+    let mut hash = [0; 32];
+    hasher.finalize(&mut hash);
+    hash
+}
+
+/// Returns the nibble at the specified index of a hash.
+fn nibble(hash: &[u8; 32], index: usize) -> u8 {
+    let byte = hash[index / 2];
+    if index.is_multiple_of(2) {
+        byte >> 4
+    } else {
+        byte & 0xf
+    }
+}
+
+impl From<[u8; 20]> for Address {
+    fn from(bytes: [u8; 20]) -> Self {
+        Address(bytes)
+    }
+}
+
+impl From<Address> for [u8; 20] {
+    fn from(address: Address) -> Self {
+        address.0
+    }
+}
+
+impl AsRef<[u8]> for Address {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+/// Decodes an address from hex without a `0x` prefix, for use with the
+/// [`Data`](super::encoding::Data) type wrapper. Note that this does not
+/// validate checksums.
+impl FromHex for Address {
+    type Error = hex::FromHexError;
+
+    fn from_hex<T: AsRef<[u8]>>(hex: T) -> Result<Self, Self::Error> {
+        <[u8; 20]>::from_hex(hex).map(Address)
+    }
+}
+
+/// Parses a `0x` prefixed hex address, validating the EIP-55 checksum if the
+/// address is mixed case.
+impl FromStr for Address {
+    type Err = ParseAddressError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Address::parse_checksum(s, None)
+    }
+}
+
+/// Formats the address with an EIP-55 checksum.
+impl Display for Address {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        f.pad(&self.to_checksum(None))
+    }
+}
+
+impl Debug for Address {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        f.write_str(&self.to_checksum(None))
+    }
+}
+
+impl LowerHex for Address {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        if f.alternate() {
+            f.write_str("0x")?;
+        }
+        f.write_str(&hex::encode(self.0))
+    }
+}
+
+impl UpperHex for Address {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        if f.alternate() {
+            f.write_str("0x")?;
+        }
+        f.write_str(&hex::encode_upper(self.0))
+    }
+}
+
+impl Serialize for Address {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(&format!("{:#x}", self))
+    }
+}
+
+impl<'de> Deserialize<'de> for Address {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(de::Error::custom)
+    }
+}
+
+/// An error parsing an address.
+#[derive(Clone, Copy, Debug, Eq, Error, PartialEq)]
+pub enum ParseAddressError {
+    /// The address is missing the `0x` prefix.
+    #[error("missing '0x' prefix")]
+    MissingPrefix,
+    /// The address does not have 40 hex digits.
+    #[error("invalid address length")]
+    InvalidLength,
+    /// The address contains invalid hex digits.
+    #[error("invalid hex digit in address")]
+    InvalidHex,
+    /// The mixed case address has an invalid checksum.
+    #[error("invalid address checksum")]
+    InvalidChecksum,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn address(hex: &str) -> Address {
+        Address(<[u8; 20]>::from_hex(hex.to_lowercase()).unwrap())
+    }
+
+    #[test]
+    fn eip55_checksums() {
+        for checksum in [
+            "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed",
+            "0xfB6916095ca1df60bB79Ce92cE3Ea74c37c5d359",
+            "0xdbF03B407c01E7cD3CBea99509d93f8DDDC8C6FB",
+            "0xD1220A0cf47c7B9Be7A2E6BA89F429762e7b9aDb",
+        ] {
+            let address = address(&checksum[2..]);
+            assert_eq!(address.to_string(), checksum);
+            assert_eq!(checksum.parse::<Address>().unwrap(), address);
+        }
+    }
+
+    #[test]
+    fn eip1191_checksums() {
+        for (chain_id, checksum) in [
+            (30, "0x5aaEB6053f3e94c9b9a09f33669435E7ef1bEAeD"),
+            (30, "0xFb6916095cA1Df60bb79ce92cE3EA74c37c5d359"),
+            (31, "0x5aAeb6053F3e94c9b9A09F33669435E7EF1BEaEd"),
+            (31, "0xFb6916095CA1dF60bb79CE92ce3Ea74C37c5D359"),
+        ] {
+            let address = address(&checksum[2..]);
+            assert_eq!(address.to_checksum(Some(chain_id)), checksum);
+            assert_eq!(
+                Address::parse_checksum(checksum, Some(chain_id)).unwrap(),
+                address,
+            );
+            assert_eq!(
+                checksum.parse::<Address>(),
+                Err(ParseAddressError::InvalidChecksum),
+            );
+        }
+    }
+
+    #[test]
+    fn parses_addresses() {
+        let lower = "0x5aaeb6053f3e94c9b9a09f33669435e7ef1beaed";
+        let address = lower.parse::<Address>().unwrap();
+        assert_eq!(lower.to_uppercase().replace('X', "x").parse(), Ok(address));
+        assert_eq!(
+            "0x5AAeb6053F3E94C9b9A09f33669435E7Ef1BeAed".parse::<Address>(),
+            Err(ParseAddressError::InvalidChecksum),
+        );
+        assert_eq!(
+            lower[2..].parse::<Address>(),
+            Err(ParseAddressError::MissingPrefix),
+        );
+        assert_eq!(
+            lower[..41].parse::<Address>(),
+            Err(ParseAddressError::InvalidLength),
+        );
+        assert_eq!(
+            lower.replace('a', "g").parse::<Address>(),
+            Err(ParseAddressError::InvalidHex),
+        );
+    }
+
+    #[test]
+    fn serialization() {
+        let address = address("5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed");
+        let json = json!("0x5aaeb6053f3e94c9b9a09f33669435e7ef1beaed");
+        assert_eq!(serde_json::to_value(address).unwrap(), json);
+        assert_eq!(serde_json::from_value::<Address>(json).unwrap(), address);
+        assert_eq!(
+            serde_json::from_value::<Address>(json!("0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed"))
+                .unwrap(),
+            address,
+        );
+        assert!(serde_json::from_value::<Address>(json!(
+            "0x5AAeb6053F3E94C9b9A09f33669435E7Ef1BeAed"
+        ))
+        .is_err());
+    }
+}
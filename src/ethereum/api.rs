@@ -6,7 +6,7 @@ use crate::transport::Transport;
 
 /// Trait with methods shared amongst APIs, allowing for easy extension.
 pub trait Api<T> {
-    fn provider(&mut self) -> Provider<T>;
+    fn provider(&self) -> Provider<'_, T>;
 }
 
 /// Type Definition for API errors.
@@ -35,32 +35,35 @@ macro_rules! api {
             T: $crate::transport::Transport,
         {$(
             $(#[$subns_attr])*
-            pub fn $subns(&mut self) -> $subns_type<T> {
+            pub fn $subns(&self) -> $subns_type<'_, T> {
                 $subns_type(self.provider())
             }
         )* $(
             $(#[$method_attr])*
-            pub async fn $method(&mut self, $(
+            pub fn $method(&self, $(
                 $param: $param_type,
-            )*) -> Result<$result_type, $crate::ethereum::ApiError<T>> {
+            )*) -> impl ::std::future::Future<
+                Output = Result<$result_type, $crate::ethereum::ApiError<T>>,
+            > + '_ {
                 let params = __api!(params: ($(
                     __api!(ser: $param ; $param_type $([ $param_serde ])*),
                 )*));
 
-                let result = <$result_type as $crate::ethereum::encoding::Decode<
-                    __api!(de: $result_type $([ $result_serde ])*),
-                >>::decode(
-                    self.provider()
-                        .call(stringify!($method_name), params)
-                        .await?
+                let call = self.0.call::<_, __api!(de: $result_type $([ $result_serde ])*)>(
+                    stringify!($method_name),
+                    params,
                 );
 
-                Ok(result)
+                async move {
+                    Ok(<$result_type as $crate::ethereum::encoding::Decode<
+                        __api!(de: $result_type $([ $result_serde ])*),
+                    >>::decode(call.await?))
+                }
             }
         )*}
 
         impl<T> $crate::ethereum::Api<T> for $ns<'_, T> {
-            fn provider(&mut self) -> $crate::ethereum::Provider<T> {
+            fn provider(&self) -> $crate::ethereum::Provider<'_, T> {
                 self.0.shared()
             }
         }
@@ -112,7 +115,7 @@ macro_rules! api_test {
             )*
 
             let provider = Provider::new(transport);
-            let mut api = $ns(provider);
+            let api = $ns(provider);
 
             futures::executor::block_on(async move {$(
                 let result = api.$call($($param),*).await.unwrap();
@@ -0,0 +1,465 @@
+//! Module containing byte array types, with a fixed-size type used for hashes,
+//! bloom filters and other fixed-size data, and a growable type used for
+//! variable-length data such as calldata and code.
+
+use super::address::Address;
+use hex::FromHex;
+use serde::de::{self, Deserialize, Deserializer, Visitor};
+use serde::ser::{Serialize, Serializer};
+use std::array::TryFromSliceError;
+use std::convert::TryFrom;
+use std::fmt::{self, Debug, Display, Formatter, LowerHex, UpperHex};
+use std::iter::FromIterator;
+use std::ops::{Deref, DerefMut};
+use std::str::FromStr;
+use thiserror::Error;
+
+/// A fixed-size byte array that is formatted, parsed and serialized as a `0x`
+/// prefixed hex string.
+#[derive(Clone, Copy, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct FixedBytes<const N: usize>(pub [u8; N]);
+
+impl<const N: usize> FixedBytes<N> {
+    /// The byte array with all bytes set to zero.
+    pub const ZERO: Self = FixedBytes([0; N]);
+
+    /// Creates a byte array from its bytes.
+    pub const fn new(bytes: [u8; N]) -> Self {
+        FixedBytes(bytes)
+    }
+
+    /// Returns the bytes of the byte array.
+    pub const fn to_bytes(self) -> [u8; N] {
+        self.0
+    }
+}
+
+impl<const N: usize> Default for FixedBytes<N> {
+    fn default() -> Self {
+        Self::ZERO
+    }
+}
+
+impl<const N: usize> Deref for FixedBytes<N> {
+    type Target = [u8; N];
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<const N: usize> DerefMut for FixedBytes<N> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+impl<const N: usize> AsRef<[u8]> for FixedBytes<N> {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl<const N: usize> AsMut<[u8]> for FixedBytes<N> {
+    fn as_mut(&mut self) -> &mut [u8] {
+        &mut self.0
+    }
+}
+
+impl<const N: usize> From<[u8; N]> for FixedBytes<N> {
+    fn from(bytes: [u8; N]) -> Self {
+        FixedBytes(bytes)
+    }
+}
+
+impl<const N: usize> From<FixedBytes<N>> for [u8; N] {
+    fn from(bytes: FixedBytes<N>) -> Self {
+        bytes.0
+    }
+}
+
+impl<const N: usize> TryFrom<&[u8]> for FixedBytes<N> {
+    type Error = TryFromSliceError;
+
+    fn try_from(bytes: &[u8]) -> Result<Self, Self::Error> {
+        <[u8; N]>::try_from(bytes).map(FixedBytes)
+    }
+}
+
+impl From<Address> for FixedBytes<20> {
+    fn from(address: Address) -> Self {
+        FixedBytes(address.0)
+    }
+}
+
+impl From<FixedBytes<20>> for Address {
+    fn from(bytes: FixedBytes<20>) -> Self {
+        Address(bytes.0)
+    }
+}
+
+impl<const N: usize> PartialEq<[u8; N]> for FixedBytes<N> {
+    fn eq(&self, other: &[u8; N]) -> bool {
+        self.0 == *other
+    }
+}
+
+/// Decodes a byte array from hex without a `0x` prefix, for use with the
+/// [`Data`](super::encoding::Data) type wrapper.
+impl<const N: usize> FromHex for FixedBytes<N> {
+    type Error = hex::FromHexError;
+
+    fn from_hex<T: AsRef<[u8]>>(hex: T) -> Result<Self, Self::Error> {
+        let mut bytes = [0; N];
+        hex::decode_to_slice(hex, &mut bytes)?;
+        Ok(FixedBytes(bytes))
+    }
+}
+
+/// Parses a `0x` prefixed hex string with exactly two digits per byte.
+impl<const N: usize> FromStr for FixedBytes<N> {
+    type Err = ParseBytesError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let hex = s.strip_prefix("0x").ok_or(ParseBytesError::MissingPrefix)?;
+        if hex.len() != N * 2 {
+            return Err(ParseBytesError::InvalidLength);
+        }
+        Self::from_hex(hex).map_err(|_| ParseBytesError::InvalidHex)
+    }
+}
+
+impl<const N: usize> Display for FixedBytes<N> {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "{:#x}", self)
+    }
+}
+
+impl<const N: usize> Debug for FixedBytes<N> {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "{:#x}", self)
+    }
+}
+
+impl<const N: usize> LowerHex for FixedBytes<N> {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        if f.alternate() {
+            f.write_str("0x")?;
+        }
+        f.write_str(&hex::encode(self.0))
+    }
+}
+
+impl<const N: usize> UpperHex for FixedBytes<N> {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        if f.alternate() {
+            f.write_str("0x")?;
+        }
+        f.write_str(&hex::encode_upper(self.0))
+    }
+}
+
+impl<const N: usize> Serialize for FixedBytes<N> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+impl<'de, const N: usize> Deserialize<'de> for FixedBytes<N> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct FixedBytesVisitor<const N: usize>;
+
+        impl<const N: usize> Visitor<'_> for FixedBytesVisitor<N> {
+            type Value = FixedBytes<N>;
+
+            fn expecting(&self, formatter: &mut Formatter) -> fmt::Result {
+                write!(formatter, "a '0x' prefixed hex string with {} bytes", N)
+            }
+
+            fn visit_str<E>(self, s: &str) -> Result<Self::Value, E>
+            where
+                E: de::Error,
+            {
+                s.parse().map_err(de::Error::custom)
+            }
+        }
+
+        deserializer.deserialize_str(FixedBytesVisitor)
+    }
+}
+
+/// A growable byte array that is formatted, parsed and serialized as a `0x`
+/// prefixed hex string.
+#[derive(Clone, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct Bytes(pub Vec<u8>);
+
+impl Bytes {
+    /// Creates a new empty byte array.
+    pub const fn new() -> Self {
+        Bytes(Vec::new())
+    }
+
+    /// Returns the bytes of the byte array.
+    pub fn into_vec(self) -> Vec<u8> {
+        self.0
+    }
+}
+
+impl Deref for Bytes {
+    type Target = Vec<u8>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl DerefMut for Bytes {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+impl AsRef<[u8]> for Bytes {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl AsMut<[u8]> for Bytes {
+    fn as_mut(&mut self) -> &mut [u8] {
+        &mut self.0
+    }
+}
+
+impl From<Vec<u8>> for Bytes {
+    fn from(bytes: Vec<u8>) -> Self {
+        Bytes(bytes)
+    }
+}
+
+impl From<&[u8]> for Bytes {
+    fn from(bytes: &[u8]) -> Self {
+        Bytes(bytes.to_vec())
+    }
+}
+
+impl<const N: usize> From<[u8; N]> for Bytes {
+    fn from(bytes: [u8; N]) -> Self {
+        Bytes(bytes.to_vec())
+    }
+}
+
+impl<const N: usize> From<FixedBytes<N>> for Bytes {
+    fn from(bytes: FixedBytes<N>) -> Self {
+        Bytes(bytes.0.to_vec())
+    }
+}
+
+impl From<Bytes> for Vec<u8> {
+    fn from(bytes: Bytes) -> Self {
+        bytes.0
+    }
+}
+
+impl FromIterator<u8> for Bytes {
+    fn from_iter<I: IntoIterator<Item = u8>>(iter: I) -> Self {
+        Bytes(iter.into_iter().collect())
+    }
+}
+
+impl Extend<u8> for Bytes {
+    fn extend<I: IntoIterator<Item = u8>>(&mut self, iter: I) {
+        self.0.extend(iter)
+    }
+}
+
+impl PartialEq<[u8]> for Bytes {
+    fn eq(&self, other: &[u8]) -> bool {
+        self.0 == other
+    }
+}
+
+/// Decodes a byte array from hex without a `0x` prefix, for use with the
+/// [`Data`](super::encoding::Data) type wrapper.
+impl FromHex for Bytes {
+    type Error = hex::FromHexError;
+
+    fn from_hex<T: AsRef<[u8]>>(hex: T) -> Result<Self, Self::Error> {
+        Vec::from_hex(hex).map(Bytes)
+    }
+}
+
+/// Parses a `0x` prefixed hex string with two digits per byte.
+impl FromStr for Bytes {
+    type Err = ParseBytesError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let hex = s.strip_prefix("0x").ok_or(ParseBytesError::MissingPrefix)?;
+        if hex.len() % 2 != 0 {
+            return Err(ParseBytesError::InvalidLength);
+        }
+        Self::from_hex(hex).map_err(|_| ParseBytesError::InvalidHex)
+    }
+}
+
+impl Display for Bytes {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "{:#x}", self)
+    }
+}
+
+impl Debug for Bytes {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "{:#x}", self)
+    }
+}
+
+impl LowerHex for Bytes {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        if f.alternate() {
+            f.write_str("0x")?;
+        }
+        f.write_str(&hex::encode(&self.0))
+    }
+}
+
+impl UpperHex for Bytes {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        if f.alternate() {
+            f.write_str("0x")?;
+        }
+        f.write_str(&hex::encode_upper(&self.0))
+    }
+}
+
+impl Serialize for Bytes {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+impl<'de> Deserialize<'de> for Bytes {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct BytesVisitor;
+
+        impl Visitor<'_> for BytesVisitor {
+            type Value = Bytes;
+
+            fn expecting(&self, formatter: &mut Formatter) -> fmt::Result {
+                formatter.write_str("a '0x' prefixed hex string with two digits per byte")
+            }
+
+            fn visit_str<E>(self, s: &str) -> Result<Self::Value, E>
+            where
+                E: de::Error,
+            {
+                s.parse().map_err(de::Error::custom)
+            }
+        }
+
+        deserializer.deserialize_str(BytesVisitor)
+    }
+}
+
+/// An error parsing a byte array.
+#[derive(Clone, Copy, Debug, Eq, Error, PartialEq)]
+pub enum ParseBytesError {
+    /// The string is missing the `0x` prefix.
+    #[error("missing '0x' prefix")]
+    MissingPrefix,
+    /// The string does not have two hex digits per byte, or does not have
+    /// the length of the fixed-size byte array.
+    #[error("invalid byte array length")]
+    InvalidLength,
+    /// The string contains invalid hex digits.
+    #[error("invalid hex digit in byte array")]
+    InvalidHex,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn formats_and_parses() {
+        let bytes = FixedBytes([0xab, 0x01, 0x00, 0xff]);
+        assert_eq!(bytes.to_string(), "0xab0100ff");
+        assert_eq!(format!("{:?}", bytes), "0xab0100ff");
+        assert_eq!(format!("{:X}", bytes), "AB0100FF");
+        assert_eq!("0xab0100ff".parse(), Ok(bytes));
+        assert_eq!("0xAB0100FF".parse(), Ok(bytes));
+
+        assert_eq!(
+            "ab0100ff".parse::<FixedBytes<4>>(),
+            Err(ParseBytesError::MissingPrefix),
+        );
+        assert_eq!(
+            "0xab0100".parse::<FixedBytes<4>>(),
+            Err(ParseBytesError::InvalidLength),
+        );
+        assert_eq!(
+            "0xab0100fg".parse::<FixedBytes<4>>(),
+            Err(ParseBytesError::InvalidHex),
+        );
+    }
+
+    #[test]
+    fn conversions() {
+        let bytes = FixedBytes::<4>::try_from(&[1, 2, 3, 4][..]).unwrap();
+        assert_eq!(bytes, [1, 2, 3, 4]);
+        assert_eq!(<[u8; 4]>::from(bytes), [1, 2, 3, 4]);
+        assert_eq!(&bytes[1..], [2, 3, 4]);
+        assert!(FixedBytes::<4>::try_from(&[1, 2, 3][..]).is_err());
+
+        let address = Address([1; 20]);
+        assert_eq!(Address::from(FixedBytes::from(address)), address);
+    }
+
+    #[test]
+    fn serialization() {
+        let bloom = FixedBytes([0x42; 256]);
+        let json = json!(format!("0x{}", "42".repeat(256)));
+        assert_eq!(serde_json::to_value(bloom).unwrap(), json);
+        assert_eq!(
+            serde_json::from_value::<FixedBytes<256>>(json).unwrap(),
+            bloom
+        );
+        assert!(serde_json::from_value::<FixedBytes<32>>(json!("0x42")).is_err());
+    }
+
+    #[test]
+    fn growable_bytes() {
+        let mut bytes = "0x".parse::<Bytes>().unwrap();
+        assert!(bytes.is_empty());
+        bytes.extend([0xde, 0xad]);
+        bytes.push(0xbe);
+        assert_eq!(bytes.to_string(), "0xdeadbe");
+        assert_eq!("0xdeadbe".parse(), Ok(bytes.clone()));
+        assert_eq!(
+            "0xdeadb".parse::<Bytes>(),
+            Err(ParseBytesError::InvalidLength)
+        );
+        assert_eq!(
+            "deadbe".parse::<Bytes>(),
+            Err(ParseBytesError::MissingPrefix)
+        );
+
+        let json = json!("0xdeadbe");
+        assert_eq!(serde_json::to_value(&bytes).unwrap(), json);
+        assert_eq!(serde_json::from_value::<Bytes>(json).unwrap(), bytes);
+        assert_eq!(Bytes::from(FixedBytes([1, 2])), Bytes(vec![1, 2]));
+    }
+}
@@ -1,12 +1,15 @@
 //! This module provides Ethereum encoding for Rust primitives used for JSON RPC
 //! calls.
 
+mod int;
+
+pub use self::int::{ParseIntError, TryFromIntError, I256, U256};
 use hex::{FromHex, ToHex};
 use serde::de::{self, Deserialize, Deserializer, Visitor};
 use serde::ser::{Serialize, Serializer};
 use std::fmt::{self, LowerHex};
 use std::marker::PhantomData;
-use std::num::ParseIntError;
+use std::num;
 
 /// A trait for decoding a JSON RPC result into an API result. This is used for
 /// using proxy types for doing deserialization (such as
@@ -90,10 +93,57 @@ where
 
 /// Module for `#[serde(with = ...)]` to perform serialization with the
 /// [`ethrs::encoding::Data`] type wrapper.
-pub mod data {}
+pub mod data {
+    use super::*;
+
+    pub fn serialize<T, S>(value: &T, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        T: AsRef<[u8]>,
+        S: Serializer,
+    {
+        Data(value.as_ref()).serialize(serializer)
+    }
+
+    pub fn deserialize<'de, T, D>(deserializer: D) -> Result<T, D::Error>
+    where
+        T: FromHex,
+        T::Error: fmt::Display,
+        D: Deserializer<'de>,
+    {
+        Ok(Data::<T>::deserialize(deserializer)?.0)
+    }
+
+    /// Module for `#[serde(with = ...)]` to perform serialization of optional
+    /// values with the [`ethrs::encoding::Data`] type wrapper, where `None` is
+    /// serialized as `null`. Use `#[serde(default)]` to also accept missing
+    /// fields.
+    pub mod option {
+        use super::*;
+
+        pub fn serialize<T, S>(value: &Option<T>, serializer: S) -> Result<S::Ok, S::Error>
+        where
+            T: AsRef<[u8]>,
+            S: Serializer,
+        {
+            value
+                .as_ref()
+                .map(|value| Data(value.as_ref()))
+                .serialize(serializer)
+        }
 
-/// A type wrapper around primitive integer quantities that get serialized as
-/// hex strings.
+        pub fn deserialize<'de, T, D>(deserializer: D) -> Result<Option<T>, D::Error>
+        where
+            T: FromHex,
+            T::Error: fmt::Display,
+            D: Deserializer<'de>,
+        {
+            Ok(Option::<Data<T>>::deserialize(deserializer)?.map(|data| data.0))
+        }
+    }
+}
+
+/// A type wrapper around integer quantities, either primitive integers or
+/// [`U256`] and [`I256`], that get serialized as hex strings.
 pub struct Quantity<T>(pub T);
 
 impl<T> From<T> for Quantity<T> {
@@ -144,10 +194,9 @@ where
             where
                 E: de::Error,
             {
-                let s = if s.starts_with("0x") {
-                    &s[2..]
-                } else {
-                    return Err(de::Error::custom("missing '0x' prefix"));
+                let s = match s.strip_prefix("0x") {
+                    Some(s) => s,
+                    None => return Err(de::Error::custom("missing '0x' prefix")),
                 };
                 if s != "0" && s.starts_with('0') {
                     return Err(de::Error::custom("hex number contains leading 0s"));
@@ -183,6 +232,31 @@ pub mod quantity {
     {
         Ok(Quantity::<T>::deserialize(deserializer)?.0)
     }
+
+    /// Module for `#[serde(with = ...)]` to perform serialization of optional
+    /// values with the [`ethrs::encoding::Quantity`] type wrapper, where `None`
+    /// is serialized as `null`. Use `#[serde(default)]` to also accept missing
+    /// fields.
+    pub mod option {
+        use super::*;
+
+        pub fn serialize<T, S>(value: &Option<T>, serializer: S) -> Result<S::Ok, S::Error>
+        where
+            T: LowerHex,
+            S: Serializer,
+        {
+            value.as_ref().map(Quantity).serialize(serializer)
+        }
+
+        pub fn deserialize<'de, T, D>(deserializer: D) -> Result<Option<T>, D::Error>
+        where
+            T: FromStrRadix,
+            T::Error: fmt::Display,
+            D: Deserializer<'de>,
+        {
+            Ok(Option::<Quantity<T>>::deserialize(deserializer)?.map(|quantity| quantity.0))
+        }
+    }
 }
 
 /// A trait for reading integers from a string in the specified base.
@@ -208,7 +282,7 @@ pub trait FromStrRadix: Sized {
 macro_rules! impl_from_str_radix {
     ($($int:ty),* $(,)?) => {$(
         impl FromStrRadix for $int {
-            type Error = ParseIntError;
+            type Error = num::ParseIntError;
 
             fn from_str_radix(s: &str, radix: u32) -> Result<Self, Self::Error> {
                 <$int>::from_str_radix(s, radix)
@@ -221,3 +295,44 @@ impl_from_str_radix! {
     isize, i8, i16, i32, i64, i128,
     usize, u8, u16, u32, u64, u128,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ethereum::bytes::Bytes;
+    use serde::{Deserialize, Serialize};
+    use serde_json::json;
+
+    #[derive(Debug, Deserialize, PartialEq, Serialize)]
+    struct Optional {
+        #[serde(default, with = "data::option")]
+        data: Option<Bytes>,
+        #[serde(default, with = "quantity::option")]
+        quantity: Option<U256>,
+    }
+
+    #[test]
+    fn optional_fields() {
+        let value = Optional {
+            data: Some(Bytes(vec![4, 2])),
+            quantity: Some(U256::from(42_u64)),
+        };
+        let json = json!({ "data": "0x0402", "quantity": "0x2a" });
+        assert_eq!(serde_json::to_value(&value).unwrap(), json);
+        assert_eq!(serde_json::from_value::<Optional>(json).unwrap(), value);
+
+        let value = Optional {
+            data: None,
+            quantity: None,
+        };
+        let json = json!({ "data": null, "quantity": null });
+        assert_eq!(serde_json::to_value(&value).unwrap(), json);
+        assert_eq!(serde_json::from_value::<Optional>(json).unwrap(), value);
+        assert_eq!(
+            serde_json::from_value::<Optional>(json!({})).unwrap(),
+            value
+        );
+
+        assert!(serde_json::from_value::<Optional>(json!({ "quantity": "0x02a" })).is_err());
+    }
+}
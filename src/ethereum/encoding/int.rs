@@ -0,0 +1,1224 @@
+//! Module containing 256-bit unsigned and signed integer types, used for
+//! quantities such as balances and token amounts, and for storage words.
+
+use super::FromStrRadix;
+use std::cmp::Ordering;
+use std::convert::{TryFrom, TryInto};
+use std::fmt::{self, Debug, Display, Formatter, LowerHex, UpperHex};
+use std::ops::{
+    Add, AddAssign, BitAnd, BitAndAssign, BitOr, BitOrAssign, BitXor, BitXorAssign, Div, DivAssign,
+    Mul, MulAssign, Neg, Not, Rem, RemAssign, Shl, ShlAssign, Shr, ShrAssign, Sub, SubAssign,
+};
+use std::str::FromStr;
+use thiserror::Error;
+
+/// A 256-bit unsigned integer.
+///
+/// Arithmetic operators panic on overflow and division by zero, use the
+/// `checked_*`, `overflowing_*`, `wrapping_*` and `saturating_*` methods for
+/// explicit overflow handling.
+#[derive(Clone, Copy, Default, Eq, Hash, PartialEq)]
+pub struct U256([u64; 4]);
+
+/// A 256-bit signed integer in two's complement representation.
+///
+/// Arithmetic operators panic on overflow and division by zero, use the
+/// `checked_*`, `overflowing_*`, `wrapping_*` and `saturating_*` methods for
+/// explicit overflow handling.
+#[derive(Clone, Copy, Default, Eq, Hash, PartialEq)]
+pub struct I256(U256);
+
+impl U256 {
+    /// The smallest value, 0.
+    pub const ZERO: Self = U256([0; 4]);
+    /// The value 1.
+    pub const ONE: Self = U256([1, 0, 0, 0]);
+    /// The largest value, 2²⁵⁶ - 1.
+    pub const MAX: Self = U256([u64::MAX; 4]);
+    /// The size of the integer in bits.
+    pub const BITS: u32 = 256;
+
+    /// Creates an integer from its 64-bit limbs, in little endian order.
+    pub const fn from_limbs(limbs: [u64; 4]) -> Self {
+        U256(limbs)
+    }
+
+    /// Returns the 64-bit limbs of the integer, in little endian order.
+    pub const fn into_limbs(self) -> [u64; 4] {
+        self.0
+    }
+
+    /// Creates an integer from its big endian byte representation.
+    pub fn from_be_bytes(bytes: [u8; 32]) -> Self {
+        let mut limbs = [0; 4];
+        for (limb, chunk) in limbs.iter_mut().rev().zip(bytes.chunks_exact(8)) {
+            *limb = u64::from_be_bytes(chunk.try_into().expect("8 byte chunk"));
+        }
+        U256(limbs)
+    }
+
+    /// Returns the big endian byte representation of the integer.
+    pub fn to_be_bytes(self) -> [u8; 32] {
+        let mut bytes = [0; 32];
+        for (chunk, limb) in bytes.chunks_exact_mut(8).zip(self.0.iter().rev()) {
+            chunk.copy_from_slice(&limb.to_be_bytes());
+        }
+        bytes
+    }
+
+    /// Creates an integer from its little endian byte representation.
+    pub fn from_le_bytes(mut bytes: [u8; 32]) -> Self {
+        bytes.reverse();
+        Self::from_be_bytes(bytes)
+    }
+
+    /// Returns the little endian byte representation of the integer.
+    pub fn to_le_bytes(self) -> [u8; 32] {
+        let mut bytes = self.to_be_bytes();
+        bytes.reverse();
+        bytes
+    }
+
+    /// Returns `true` if the integer is zero.
+    pub fn is_zero(self) -> bool {
+        self == Self::ZERO
+    }
+
+    /// Returns the number of leading zero bits.
+    pub fn leading_zeros(self) -> u32 {
+        let mut zeros = 0;
+        for limb in self.0.iter().rev() {
+            zeros += limb.leading_zeros();
+            if *limb != 0 {
+                break;
+            }
+        }
+        zeros
+    }
+
+    /// Returns the number of bits needed to represent the integer.
+    pub fn bits(self) -> u32 {
+        Self::BITS - self.leading_zeros()
+    }
+
+    /// Returns whether the bit at the specified position is set.
+    fn bit(self, index: u32) -> bool {
+        (self.0[(index / 64) as usize] >> (index % 64)) & 1 == 1
+    }
+
+    /// Adds, returning the wrapped result and whether it overflowed.
+    pub fn overflowing_add(self, rhs: Self) -> (Self, bool) {
+        let mut result = [0; 4];
+        let mut carry = false;
+        for (i, limb) in result.iter_mut().enumerate() {
+            let (sum, c0) = self.0[i].overflowing_add(rhs.0[i]);
+            let (sum, c1) = sum.overflowing_add(carry as u64);
+            *limb = sum;
+            carry = c0 || c1;
+        }
+        (U256(result), carry)
+    }
+
+    /// Subtracts, returning the wrapped result and whether it overflowed.
+    pub fn overflowing_sub(self, rhs: Self) -> (Self, bool) {
+        let mut result = [0; 4];
+        let mut borrow = false;
+        for (i, limb) in result.iter_mut().enumerate() {
+            let (diff, b0) = self.0[i].overflowing_sub(rhs.0[i]);
+            let (diff, b1) = diff.overflowing_sub(borrow as u64);
+            *limb = diff;
+            borrow = b0 || b1;
+        }
+        (U256(result), borrow)
+    }
+
+    /// Multiplies, returning the wrapped result and whether it overflowed.
+    pub fn overflowing_mul(self, rhs: Self) -> (Self, bool) {
+        let mut result = [0; 8];
+        for i in 0..4 {
+            let mut carry = 0;
+            for j in 0..4 {
+                let product = u128::from(self.0[i]) * u128::from(rhs.0[j])
+                    + u128::from(result[i + j])
+                    + carry;
+                result[i + j] = product as u64;
+                carry = product >> 64;
+            }
+            result[i + 4] = carry as u64;
+        }
+
+        let overflow = result[4..].iter().any(|limb| *limb != 0);
+        (U256([result[0], result[1], result[2], result[3]]), overflow)
+    }
+
+    /// Checked addition, returning `None` on overflow.
+    pub fn checked_add(self, rhs: Self) -> Option<Self> {
+        checked(self.overflowing_add(rhs))
+    }
+
+    /// Checked subtraction, returning `None` on overflow.
+    pub fn checked_sub(self, rhs: Self) -> Option<Self> {
+        checked(self.overflowing_sub(rhs))
+    }
+
+    /// Checked multiplication, returning `None` on overflow.
+    pub fn checked_mul(self, rhs: Self) -> Option<Self> {
+        checked(self.overflowing_mul(rhs))
+    }
+
+    /// Checked division, returning `None` if `rhs` is zero.
+    pub fn checked_div(self, rhs: Self) -> Option<Self> {
+        Some(self.checked_div_rem(rhs)?.0)
+    }
+
+    /// Checked remainder, returning `None` if `rhs` is zero.
+    pub fn checked_rem(self, rhs: Self) -> Option<Self> {
+        Some(self.checked_div_rem(rhs)?.1)
+    }
+
+    /// Checked exponentiation, returning `None` on overflow.
+    pub fn checked_pow(self, mut exp: u32) -> Option<Self> {
+        let mut base = self;
+        let mut result = Self::ONE;
+        while exp > 0 {
+            if exp & 1 == 1 {
+                result = result.checked_mul(base)?;
+            }
+            exp >>= 1;
+            if exp > 0 {
+                base = base.checked_mul(base)?;
+            }
+        }
+        Some(result)
+    }
+
+    /// Wrapping addition, wrapping around at 2²⁵⁶.
+    pub fn wrapping_add(self, rhs: Self) -> Self {
+        self.overflowing_add(rhs).0
+    }
+
+    /// Wrapping subtraction, wrapping around at 2²⁵⁶.
+    pub fn wrapping_sub(self, rhs: Self) -> Self {
+        self.overflowing_sub(rhs).0
+    }
+
+    /// Wrapping multiplication, wrapping around at 2²⁵⁶.
+    pub fn wrapping_mul(self, rhs: Self) -> Self {
+        self.overflowing_mul(rhs).0
+    }
+
+    /// Wrapping negation, computing the two's complement of the integer.
+    pub fn wrapping_neg(self) -> Self {
+        Self::ZERO.wrapping_sub(self)
+    }
+
+    /// Saturating addition, returning [`U256::MAX`] on overflow.
+    pub fn saturating_add(self, rhs: Self) -> Self {
+        self.checked_add(rhs).unwrap_or(Self::MAX)
+    }
+
+    /// Saturating subtraction, returning zero on overflow.
+    pub fn saturating_sub(self, rhs: Self) -> Self {
+        self.checked_sub(rhs).unwrap_or(Self::ZERO)
+    }
+
+    /// Saturating multiplication, returning [`U256::MAX`] on overflow.
+    pub fn saturating_mul(self, rhs: Self) -> Self {
+        self.checked_mul(rhs).unwrap_or(Self::MAX)
+    }
+
+    /// Computes the quotient and remainder, returning `None` if `rhs` is zero.
+    pub fn checked_div_rem(self, rhs: Self) -> Option<(Self, Self)> {
+        if rhs.is_zero() {
+            return None;
+        }
+        if rhs.0[1..] == [0; 3] {
+            let (quotient, remainder) = self.div_rem_u64(rhs.0[0]);
+            return Some((quotient, U256::from(remainder)));
+        }
+
+        // NOTE: Binary long division, where the remainder may temporarily
+        // exceed 256 bits by one bit when shifting, in which case it is larger
+        // than the divisor.
+        let mut quotient = Self::ZERO;
+        let mut remainder = Self::ZERO;
+        for i in (0..self.bits()).rev() {
+            let carry = remainder.bit(255);
+            remainder <<= 1;
+            remainder.0[0] |= self.bit(i) as u64;
+            if carry || remainder >= rhs {
+                remainder = remainder.wrapping_sub(rhs);
+                quotient.0[(i / 64) as usize] |= 1 << (i % 64);
+            }
+        }
+        Some((quotient, remainder))
+    }
+
+    /// Divides by a 64-bit divisor, returning the quotient and remainder.
+    fn div_rem_u64(self, rhs: u64) -> (Self, u64) {
+        let mut quotient = [0; 4];
+        let mut remainder = 0_u128;
+        for i in (0..4).rev() {
+            let dividend = (remainder << 64) | u128::from(self.0[i]);
+            quotient[i] = (dividend / u128::from(rhs)) as u64;
+            remainder = dividend % u128::from(rhs);
+        }
+        (U256(quotient), remainder as u64)
+    }
+
+    /// Converts a string slice in a given base to an integer, with an optional
+    /// `+` sign. See [`FromStrRadix::from_str_radix`].
+    ///
+    /// # Panics
+    ///
+    /// This function panics if `radix` is not in the range from 2 to 36.
+    pub fn from_str_radix(s: &str, radix: u32) -> Result<Self, ParseIntError> {
+        parse_digits(s.strip_prefix('+').unwrap_or(s), radix)
+    }
+
+    /// Returns the integer as a `u128` if it fits.
+    fn to_u128(self) -> Option<u128> {
+        if self.0[2..] == [0; 2] {
+            Some(u128::from(self.0[0]) | (u128::from(self.0[1]) << 64))
+        } else {
+            None
+        }
+    }
+
+    /// Formats the integer in decimal, without any padding.
+    fn to_decimal(self) -> String {
+        const CHUNK: u64 = 10_000_000_000_000_000_000;
+
+        let mut chunks = Vec::new();
+        let mut value = self;
+        loop {
+            let (quotient, chunk) = value.div_rem_u64(CHUNK);
+            chunks.push(chunk);
+            value = quotient;
+            if value.is_zero() {
+                break;
+            }
+        }
+
+        let mut decimal = chunks.pop().expect("at least one chunk").to_string();
+        for chunk in chunks.iter().rev() {
+            decimal.push_str(&format!("{:019}", chunk));
+        }
+        decimal
+    }
+
+    /// Formats the integer in hexadecimal, without any padding.
+    fn to_hex(self, upper: bool) -> String {
+        let mut limbs = self.0.iter().rev().skip_while(|limb| **limb == 0);
+        let first = limbs.next().copied().unwrap_or_default();
+        let mut hex = if upper {
+            format!("{:X}", first)
+        } else {
+            format!("{:x}", first)
+        };
+        for limb in limbs {
+            if upper {
+                hex.push_str(&format!("{:016X}", limb));
+            } else {
+                hex.push_str(&format!("{:016x}", limb));
+            }
+        }
+        hex
+    }
+}
+
+impl I256 {
+    /// The value 0.
+    pub const ZERO: Self = I256(U256::ZERO);
+    /// The value 1.
+    pub const ONE: Self = I256(U256::ONE);
+    /// The value -1.
+    pub const MINUS_ONE: Self = I256(U256::MAX);
+    /// The smallest value, -2²⁵⁵.
+    pub const MIN: Self = I256(U256([0, 0, 0, 1 << 63]));
+    /// The largest value, 2²⁵⁵ - 1.
+    pub const MAX: Self = I256(U256([u64::MAX, u64::MAX, u64::MAX, u64::MAX >> 1]));
+    /// The size of the integer in bits.
+    pub const BITS: u32 = 256;
+
+    /// Creates an integer from its two's complement 64-bit limbs, in little
+    /// endian order.
+    pub const fn from_limbs(limbs: [u64; 4]) -> Self {
+        I256(U256(limbs))
+    }
+
+    /// Returns the two's complement 64-bit limbs of the integer, in little
+    /// endian order.
+    pub const fn into_limbs(self) -> [u64; 4] {
+        (self.0).0
+    }
+
+    /// Creates an integer from its two's complement representation.
+    pub const fn from_twos_complement(value: U256) -> Self {
+        I256(value)
+    }
+
+    /// Returns the two's complement representation of the integer.
+    pub const fn twos_complement(self) -> U256 {
+        self.0
+    }
+
+    /// Creates an integer from its big endian two's complement byte
+    /// representation.
+    pub fn from_be_bytes(bytes: [u8; 32]) -> Self {
+        I256(U256::from_be_bytes(bytes))
+    }
+
+    /// Returns the big endian two's complement byte representation of the
+    /// integer.
+    pub fn to_be_bytes(self) -> [u8; 32] {
+        self.0.to_be_bytes()
+    }
+
+    /// Creates an integer from its little endian two's complement byte
+    /// representation.
+    pub fn from_le_bytes(bytes: [u8; 32]) -> Self {
+        I256(U256::from_le_bytes(bytes))
+    }
+
+    /// Returns the little endian two's complement byte representation of the
+    /// integer.
+    pub fn to_le_bytes(self) -> [u8; 32] {
+        self.0.to_le_bytes()
+    }
+
+    /// Returns `true` if the integer is zero.
+    pub fn is_zero(self) -> bool {
+        self.0.is_zero()
+    }
+
+    /// Returns `true` if the integer is negative.
+    pub fn is_negative(self) -> bool {
+        self.0.bit(255)
+    }
+
+    /// Returns `true` if the integer is positive.
+    pub fn is_positive(self) -> bool {
+        !self.is_negative() && !self.is_zero()
+    }
+
+    /// Returns the absolute value of the integer as an unsigned integer. This
+    /// never overflows, as the absolute value of [`I256::MIN`] fits in a
+    /// [`U256`].
+    pub fn unsigned_abs(self) -> U256 {
+        if self.is_negative() {
+            self.0.wrapping_neg()
+        } else {
+            self.0
+        }
+    }
+
+    /// Creates an integer from a sign and an absolute value, returning `None`
+    /// if it doesn't fit.
+    fn from_sign_and_abs(negative: bool, abs: U256) -> Option<Self> {
+        if negative {
+            match abs.cmp(&Self::MIN.0) {
+                Ordering::Greater => None,
+                _ => Some(I256(abs.wrapping_neg())),
+            }
+        } else {
+            match abs.cmp(&Self::MAX.0) {
+                Ordering::Greater => None,
+                _ => Some(I256(abs)),
+            }
+        }
+    }
+
+    /// Adds, returning the wrapped result and whether it overflowed.
+    pub fn overflowing_add(self, rhs: Self) -> (Self, bool) {
+        let result = I256(self.0.wrapping_add(rhs.0));
+        let overflow =
+            self.is_negative() == rhs.is_negative() && result.is_negative() != self.is_negative();
+        (result, overflow)
+    }
+
+    /// Subtracts, returning the wrapped result and whether it overflowed.
+    pub fn overflowing_sub(self, rhs: Self) -> (Self, bool) {
+        let result = I256(self.0.wrapping_sub(rhs.0));
+        let overflow =
+            self.is_negative() != rhs.is_negative() && result.is_negative() != self.is_negative();
+        (result, overflow)
+    }
+
+    /// Multiplies, returning the wrapped result and whether it overflowed.
+    pub fn overflowing_mul(self, rhs: Self) -> (Self, bool) {
+        let result = I256(self.0.wrapping_mul(rhs.0));
+        (result, self.checked_mul(rhs).is_none())
+    }
+
+    /// Checked addition, returning `None` on overflow.
+    pub fn checked_add(self, rhs: Self) -> Option<Self> {
+        checked(self.overflowing_add(rhs))
+    }
+
+    /// Checked subtraction, returning `None` on overflow.
+    pub fn checked_sub(self, rhs: Self) -> Option<Self> {
+        checked(self.overflowing_sub(rhs))
+    }
+
+    /// Checked multiplication, returning `None` on overflow.
+    pub fn checked_mul(self, rhs: Self) -> Option<Self> {
+        let abs = self.unsigned_abs().checked_mul(rhs.unsigned_abs())?;
+        Self::from_sign_and_abs(self.is_negative() != rhs.is_negative(), abs)
+    }
+
+    /// Checked division, rounding towards zero. Returns `None` if `rhs` is
+    /// zero or if the division overflows.
+    pub fn checked_div(self, rhs: Self) -> Option<Self> {
+        let quotient = self.unsigned_abs().checked_div(rhs.unsigned_abs())?;
+        Self::from_sign_and_abs(self.is_negative() != rhs.is_negative(), quotient)
+    }
+
+    /// Checked remainder, with the sign of `self`. Returns `None` if `rhs` is
+    /// zero or if the division overflows.
+    pub fn checked_rem(self, rhs: Self) -> Option<Self> {
+        if self == Self::MIN && rhs == Self::MINUS_ONE {
+            return None;
+        }
+        let remainder = self.unsigned_abs().checked_rem(rhs.unsigned_abs())?;
+        Self::from_sign_and_abs(self.is_negative(), remainder)
+    }
+
+    /// Checked negation, returning `None` for [`I256::MIN`].
+    pub fn checked_neg(self) -> Option<Self> {
+        Self::ZERO.checked_sub(self)
+    }
+
+    /// Checked absolute value, returning `None` for [`I256::MIN`].
+    pub fn checked_abs(self) -> Option<Self> {
+        Self::from_sign_and_abs(false, self.unsigned_abs())
+    }
+
+    /// Checked exponentiation, returning `None` on overflow.
+    pub fn checked_pow(self, exp: u32) -> Option<Self> {
+        let abs = self.unsigned_abs().checked_pow(exp)?;
+        Self::from_sign_and_abs(self.is_negative() && exp % 2 == 1, abs)
+    }
+
+    /// Wrapping addition, wrapping around at the boundary of the type.
+    pub fn wrapping_add(self, rhs: Self) -> Self {
+        self.overflowing_add(rhs).0
+    }
+
+    /// Wrapping subtraction, wrapping around at the boundary of the type.
+    pub fn wrapping_sub(self, rhs: Self) -> Self {
+        self.overflowing_sub(rhs).0
+    }
+
+    /// Wrapping multiplication, wrapping around at the boundary of the type.
+    pub fn wrapping_mul(self, rhs: Self) -> Self {
+        self.overflowing_mul(rhs).0
+    }
+
+    /// Saturating addition, returning the numeric bound on overflow.
+    pub fn saturating_add(self, rhs: Self) -> Self {
+        self.checked_add(rhs).unwrap_or(if rhs.is_negative() {
+            Self::MIN
+        } else {
+            Self::MAX
+        })
+    }
+
+    /// Saturating subtraction, returning the numeric bound on overflow.
+    pub fn saturating_sub(self, rhs: Self) -> Self {
+        self.checked_sub(rhs).unwrap_or(if rhs.is_negative() {
+            Self::MAX
+        } else {
+            Self::MIN
+        })
+    }
+
+    /// Saturating multiplication, returning the numeric bound on overflow.
+    pub fn saturating_mul(self, rhs: Self) -> Self {
+        self.checked_mul(rhs)
+            .unwrap_or(if self.is_negative() != rhs.is_negative() {
+                Self::MIN
+            } else {
+                Self::MAX
+            })
+    }
+
+    /// Converts a string slice in a given base to an integer, with an optional
+    /// `+` or `-` sign. See [`FromStrRadix::from_str_radix`].
+    ///
+    /// # Panics
+    ///
+    /// This function panics if `radix` is not in the range from 2 to 36.
+    pub fn from_str_radix(s: &str, radix: u32) -> Result<Self, ParseIntError> {
+        let (negative, digits) = sign(s);
+        Self::parse(negative, digits, radix)
+    }
+
+    /// Parses an integer from its sign and digits in the specified radix.
+    fn parse(negative: bool, digits: &str, radix: u32) -> Result<Self, ParseIntError> {
+        let overflow = if negative {
+            ParseIntError::NegOverflow
+        } else {
+            ParseIntError::PosOverflow
+        };
+        let abs = parse_digits(digits, radix).map_err(|err| match err {
+            ParseIntError::PosOverflow => overflow,
+            err => err,
+        })?;
+        Self::from_sign_and_abs(negative, abs).ok_or(overflow)
+    }
+
+    /// Returns the integer as an `i128` if it fits.
+    fn to_i128(self) -> Option<i128> {
+        let extension = if self.is_negative() { u64::MAX } else { 0 };
+        let low = u128::from((self.0).0[0]) | (u128::from((self.0).0[1]) << 64);
+        let fits =
+            (self.0).0[2..] == [extension; 2] && (low as i128).is_negative() == self.is_negative();
+        if fits {
+            Some(low as i128)
+        } else {
+            None
+        }
+    }
+}
+
+/// Returns the result of an overflowing operation if it did not overflow.
+fn checked<T>((value, overflow): (T, bool)) -> Option<T> {
+    if overflow {
+        None
+    } else {
+        Some(value)
+    }
+}
+
+/// Splits the sign from a signed integer string.
+fn sign(s: &str) -> (bool, &str) {
+    match s.strip_prefix('-') {
+        Some(digits) => (true, digits),
+        None => (false, s.strip_prefix('+').unwrap_or(s)),
+    }
+}
+
+/// Parses unsigned digits in the specified radix.
+fn parse_digits(digits: &str, radix: u32) -> Result<U256, ParseIntError> {
+    assert!(
+        (2..=36).contains(&radix),
+        "from_str_radix_int: must lie in the range `[2, 36]` - found {}",
+        radix,
+    );
+    if digits.is_empty() {
+        return Err(ParseIntError::Empty);
+    }
+
+    let multiplier = U256::from(radix);
+    digits.chars().try_fold(U256::ZERO, |value, c| {
+        let digit = c.to_digit(radix).ok_or(ParseIntError::InvalidDigit)?;
+        value
+            .checked_mul(multiplier)
+            .and_then(|value| value.checked_add(U256::from(digit)))
+            .ok_or(ParseIntError::PosOverflow)
+    })
+}
+
+/// Splits an optional `0x` prefix from integer digits, returning the radix of
+/// the digits.
+fn radix(digits: &str) -> (u32, &str) {
+    match digits.strip_prefix("0x") {
+        Some(digits) => (16, digits),
+        None => (10, digits),
+    }
+}
+
+impl Ord for U256 {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.iter().rev().cmp(other.0.iter().rev())
+    }
+}
+
+impl PartialOrd for U256 {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for I256 {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self.is_negative(), other.is_negative()) {
+            (true, false) => Ordering::Less,
+            (false, true) => Ordering::Greater,
+            _ => self.0.cmp(&other.0),
+        }
+    }
+}
+
+impl PartialOrd for I256 {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Parses an integer in decimal, or in hexadecimal with a `0x` prefix.
+impl FromStr for U256 {
+    type Err = ParseIntError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (radix, digits) = radix(s);
+        parse_digits(digits, radix)
+    }
+}
+
+/// Parses an integer in decimal, or in hexadecimal with a `0x` prefix, with an
+/// optional `+` or `-` sign before the prefix.
+impl FromStr for I256 {
+    type Err = ParseIntError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (negative, digits) = sign(s);
+        let (radix, digits) = radix(digits);
+        Self::parse(negative, digits, radix)
+    }
+}
+
+impl Display for U256 {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        f.pad_integral(true, "", &self.to_decimal())
+    }
+}
+
+impl Display for I256 {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        f.pad_integral(!self.is_negative(), "", &self.unsigned_abs().to_decimal())
+    }
+}
+
+impl LowerHex for U256 {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        f.pad_integral(true, "0x", &self.to_hex(false))
+    }
+}
+
+impl UpperHex for U256 {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        f.pad_integral(true, "0x", &self.to_hex(true))
+    }
+}
+
+/// Formats the two's complement representation of the integer, like for
+/// primitive signed integers.
+impl LowerHex for I256 {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        LowerHex::fmt(&self.0, f)
+    }
+}
+
+/// Formats the two's complement representation of the integer, like for
+/// primitive signed integers.
+impl UpperHex for I256 {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        UpperHex::fmt(&self.0, f)
+    }
+}
+
+impl Neg for I256 {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        self.checked_neg().expect("attempt to negate with overflow")
+    }
+}
+
+impl Shl<u32> for U256 {
+    type Output = Self;
+
+    fn shl(self, rhs: u32) -> Self {
+        assert!(rhs < Self::BITS, "attempt to shift left with overflow");
+        let (limbs, bits) = ((rhs / 64) as usize, rhs % 64);
+        let mut result = [0; 4];
+        for (i, limb) in result.iter_mut().enumerate().skip(limbs) {
+            *limb = self.0[i - limbs] << bits;
+            if bits > 0 && i > limbs {
+                *limb |= self.0[i - limbs - 1] >> (64 - bits);
+            }
+        }
+        U256(result)
+    }
+}
+
+impl Shr<u32> for U256 {
+    type Output = Self;
+
+    fn shr(self, rhs: u32) -> Self {
+        assert!(rhs < Self::BITS, "attempt to shift right with overflow");
+        let (limbs, bits) = ((rhs / 64) as usize, rhs % 64);
+        let mut result = [0; 4];
+        for (i, limb) in result.iter_mut().enumerate().take(4 - limbs) {
+            *limb = self.0[i + limbs] >> bits;
+            if bits > 0 && i + limbs < 3 {
+                *limb |= self.0[i + limbs + 1] << (64 - bits);
+            }
+        }
+        U256(result)
+    }
+}
+
+impl Shl<u32> for I256 {
+    type Output = Self;
+
+    fn shl(self, rhs: u32) -> Self {
+        I256(self.0 << rhs)
+    }
+}
+
+/// Arithmetic shift right, preserving the sign of the integer.
+impl Shr<u32> for I256 {
+    type Output = Self;
+
+    fn shr(self, rhs: u32) -> Self {
+        if self.is_negative() {
+            I256(!(!self.0 >> rhs))
+        } else {
+            I256(self.0 >> rhs)
+        }
+    }
+}
+
+impl Not for U256 {
+    type Output = Self;
+
+    fn not(self) -> Self {
+        U256([!self.0[0], !self.0[1], !self.0[2], !self.0[3]])
+    }
+}
+
+impl Not for I256 {
+    type Output = Self;
+
+    fn not(self) -> Self {
+        I256(!self.0)
+    }
+}
+
+macro_rules! impl_ops {
+    ($($int:ident),*) => {$(
+        impl_ops!(@arith $int, Add, add, AddAssign, add_assign, checked_add, "attempt to add with overflow");
+        impl_ops!(@arith $int, Sub, sub, SubAssign, sub_assign, checked_sub, "attempt to subtract with overflow");
+        impl_ops!(@arith $int, Mul, mul, MulAssign, mul_assign, checked_mul, "attempt to multiply with overflow");
+        impl_ops!(@arith $int, Div, div, DivAssign, div_assign, checked_div, "attempt to divide by zero or with overflow");
+        impl_ops!(@arith $int, Rem, rem, RemAssign, rem_assign, checked_rem, "attempt to calculate the remainder with a divisor of zero or with overflow");
+        impl_ops!(@bit $int, BitAnd, bitand, BitAndAssign, bitand_assign, &);
+        impl_ops!(@bit $int, BitOr, bitor, BitOrAssign, bitor_assign, |);
+        impl_ops!(@bit $int, BitXor, bitxor, BitXorAssign, bitxor_assign, ^);
+
+        impl ShlAssign<u32> for $int {
+            fn shl_assign(&mut self, rhs: u32) {
+                *self = *self << rhs;
+            }
+        }
+
+        impl ShrAssign<u32> for $int {
+            fn shr_assign(&mut self, rhs: u32) {
+                *self = *self >> rhs;
+            }
+        }
+
+        impl Debug for $int {
+            fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+                Display::fmt(self, f)
+            }
+        }
+
+        impl FromStrRadix for $int {
+            type Error = ParseIntError;
+
+            fn from_str_radix(s: &str, radix: u32) -> Result<Self, Self::Error> {
+                <$int>::from_str_radix(s, radix)
+            }
+        }
+    )*};
+
+    (@arith $int:ident, $op:ident, $fn:ident, $assign:ident, $assign_fn:ident, $checked:ident, $msg:literal) => {
+        impl $op for $int {
+            type Output = Self;
+
+            fn $fn(self, rhs: Self) -> Self {
+                self.$checked(rhs).expect($msg)
+            }
+        }
+
+        impl $assign for $int {
+            fn $assign_fn(&mut self, rhs: Self) {
+                *self = $op::$fn(*self, rhs);
+            }
+        }
+    };
+
+    (@bit $int:ident, $op:ident, $fn:ident, $assign:ident, $assign_fn:ident, $tok:tt) => {
+        impl $op for $int {
+            type Output = Self;
+
+            fn $fn(self, rhs: Self) -> Self {
+                let (a, b) = (self.into_limbs(), rhs.into_limbs());
+                Self::from_limbs([a[0] $tok b[0], a[1] $tok b[1], a[2] $tok b[2], a[3] $tok b[3]])
+            }
+        }
+
+        impl $assign for $int {
+            fn $assign_fn(&mut self, rhs: Self) {
+                *self = $op::$fn(*self, rhs);
+            }
+        }
+    };
+}
+
+impl_ops!(U256, I256);
+
+/// An error converting between integer types.
+#[derive(Clone, Copy, Debug, Eq, Error, PartialEq)]
+#[error("out of range integral type conversion attempted")]
+pub struct TryFromIntError(());
+
+/// An error parsing a 256-bit integer.
+#[derive(Clone, Copy, Debug, Eq, Error, PartialEq)]
+pub enum ParseIntError {
+    /// The string is empty.
+    #[error("cannot parse integer from empty string")]
+    Empty,
+    /// The string contains an invalid digit.
+    #[error("invalid digit found in string")]
+    InvalidDigit,
+    /// The integer is too large to fit in the type.
+    #[error("number too large to fit in target type")]
+    PosOverflow,
+    /// The integer is too small to fit in the type.
+    #[error("number too small to fit in target type")]
+    NegOverflow,
+}
+
+impl From<u128> for U256 {
+    fn from(value: u128) -> Self {
+        U256([value as u64, (value >> 64) as u64, 0, 0])
+    }
+}
+
+impl From<i128> for I256 {
+    fn from(value: i128) -> Self {
+        let extension = if value < 0 { u64::MAX } else { 0 };
+        I256(U256([
+            value as u64,
+            (value >> 64) as u64,
+            extension,
+            extension,
+        ]))
+    }
+}
+
+impl TryFrom<U256> for I256 {
+    type Error = TryFromIntError;
+
+    fn try_from(value: U256) -> Result<Self, Self::Error> {
+        I256::from_sign_and_abs(false, value).ok_or(TryFromIntError(()))
+    }
+}
+
+impl TryFrom<I256> for U256 {
+    type Error = TryFromIntError;
+
+    fn try_from(value: I256) -> Result<Self, Self::Error> {
+        if value.is_negative() {
+            Err(TryFromIntError(()))
+        } else {
+            Ok(value.0)
+        }
+    }
+}
+
+macro_rules! impl_conversions {
+    (unsigned: $($uint:ty),*; signed: $($int:ty),*) => {
+        $(
+            impl From<$uint> for U256 {
+                fn from(value: $uint) -> Self {
+                    U256::from(value as u128)
+                }
+            }
+
+            impl From<$uint> for I256 {
+                fn from(value: $uint) -> Self {
+                    I256(U256::from(value))
+                }
+            }
+
+            impl TryFrom<U256> for $uint {
+                type Error = TryFromIntError;
+
+                fn try_from(value: U256) -> Result<Self, Self::Error> {
+                    value
+                        .to_u128()
+                        .and_then(|value| <$uint>::try_from(value).ok())
+                        .ok_or(TryFromIntError(()))
+                }
+            }
+
+            impl TryFrom<I256> for $uint {
+                type Error = TryFromIntError;
+
+                fn try_from(value: I256) -> Result<Self, Self::Error> {
+                    <$uint>::try_from(U256::try_from(value)?)
+                }
+            }
+        )*
+        $(
+            impl From<$int> for I256 {
+                fn from(value: $int) -> Self {
+                    I256::from(value as i128)
+                }
+            }
+
+            impl TryFrom<$int> for U256 {
+                type Error = TryFromIntError;
+
+                fn try_from(value: $int) -> Result<Self, Self::Error> {
+                    u128::try_from(value)
+                        .map(U256::from)
+                        .map_err(|_| TryFromIntError(()))
+                }
+            }
+
+            impl TryFrom<U256> for $int {
+                type Error = TryFromIntError;
+
+                fn try_from(value: U256) -> Result<Self, Self::Error> {
+                    <$int>::try_from(I256::try_from(value)?)
+                }
+            }
+
+            impl TryFrom<I256> for $int {
+                type Error = TryFromIntError;
+
+                fn try_from(value: I256) -> Result<Self, Self::Error> {
+                    value
+                        .to_i128()
+                        .and_then(|value| <$int>::try_from(value).ok())
+                        .ok_or(TryFromIntError(()))
+                }
+            }
+        )*
+    };
+}
+
+impl_conversions! {
+    unsigned: u8, u16, u32, u64, usize;
+    signed: i8, i16, i32, i64, isize
+}
+
+impl From<u128> for I256 {
+    fn from(value: u128) -> Self {
+        I256(U256::from(value))
+    }
+}
+
+impl TryFrom<U256> for u128 {
+    type Error = TryFromIntError;
+
+    fn try_from(value: U256) -> Result<Self, Self::Error> {
+        value.to_u128().ok_or(TryFromIntError(()))
+    }
+}
+
+impl TryFrom<I256> for u128 {
+    type Error = TryFromIntError;
+
+    fn try_from(value: I256) -> Result<Self, Self::Error> {
+        u128::try_from(U256::try_from(value)?)
+    }
+}
+
+impl TryFrom<i128> for U256 {
+    type Error = TryFromIntError;
+
+    fn try_from(value: i128) -> Result<Self, Self::Error> {
+        u128::try_from(value)
+            .map(U256::from)
+            .map_err(|_| TryFromIntError(()))
+    }
+}
+
+impl TryFrom<U256> for i128 {
+    type Error = TryFromIntError;
+
+    fn try_from(value: U256) -> Result<Self, Self::Error> {
+        i128::try_from(I256::try_from(value)?)
+    }
+}
+
+impl TryFrom<I256> for i128 {
+    type Error = TryFromIntError;
+
+    fn try_from(value: I256) -> Result<Self, Self::Error> {
+        value.to_i128().ok_or(TryFromIntError(()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ethereum::encoding::Quantity;
+    use serde_json::json;
+
+    #[test]
+    fn parses_and_formats() {
+        let max = "115792089237316195423570985008687907853269984665640564039457584007913129639935";
+        assert_eq!(max.parse::<U256>().unwrap(), U256::MAX);
+        assert_eq!(U256::MAX.to_string(), max);
+        assert_eq!(format!("{:#x}", U256::MAX), format!("0x{}", "f".repeat(64)),);
+        assert_eq!(format!("{:X}", U256::from(0xabc_u64)), "ABC");
+        assert_eq!(format!("{:>5}", U256::from(42_u64)), "   42");
+        assert_eq!(U256::ZERO.to_string(), "0");
+        assert_eq!(format!("{:#x}", U256::ZERO), "0x0");
+
+        let value = U256::from(1_u64) << 200;
+        assert_eq!(value.to_string().parse::<U256>().unwrap(), value);
+        assert_eq!(format!("{:#x}", value).parse::<U256>().unwrap(), value);
+        assert_eq!(
+            "1606938044258990275541962092341162602522202993782792835301376"
+                .parse::<U256>()
+                .unwrap(),
+            value,
+        );
+
+        assert_eq!("".parse::<U256>(), Err(ParseIntError::Empty));
+        assert_eq!("-1".parse::<U256>(), Err(ParseIntError::InvalidDigit));
+        assert_eq!("0xg".parse::<U256>(), Err(ParseIntError::InvalidDigit));
+        assert_eq!(
+            format!("0x1{}", "0".repeat(64)).parse::<U256>(),
+            Err(ParseIntError::PosOverflow),
+        );
+
+        assert_eq!("-42".parse::<I256>().unwrap(), I256::from(-42));
+        assert_eq!("-0x2a".parse::<I256>().unwrap(), I256::from(-42));
+        assert_eq!(I256::from(-42).to_string(), "-42");
+        assert_eq!(format!("{:+}", I256::from(42)), "+42");
+        assert_eq!(format!("{:x}", I256::MINUS_ONE), "f".repeat(64));
+        assert_eq!(I256::MIN.to_string().parse::<I256>().unwrap(), I256::MIN);
+        assert_eq!(I256::MAX.to_string().parse::<I256>().unwrap(), I256::MAX);
+        assert_eq!(
+            (U256::ONE << 255).to_string().parse::<I256>(),
+            Err(ParseIntError::PosOverflow),
+        );
+        assert_eq!(
+            format!("-{}", (U256::ONE << 255) + U256::ONE).parse::<I256>(),
+            Err(ParseIntError::NegOverflow),
+        );
+    }
+
+    #[test]
+    fn unsigned_arithmetic() {
+        let a = U256::from(u128::MAX);
+        assert_eq!(a + U256::ONE, U256::ONE << 128);
+        // NOTE: (2¹²⁸ - 1)² = 2²⁵⁶ - 2¹²⁹ + 1
+        assert_eq!(a * a, U256::MAX - (U256::ONE << 129) + U256::from(2_u64));
+        assert_eq!((a * a) / a, a);
+        assert_eq!((a * a + U256::from(5_u64)) % a, U256::from(5_u64));
+        assert_eq!(U256::MAX / U256::MAX, U256::ONE);
+        assert_eq!(
+            U256::MAX % (U256::ONE << 255),
+            (U256::ONE << 255) - U256::ONE
+        );
+        assert_eq!(
+            U256::from(10_u64).checked_pow(18),
+            Some(U256::from(10_u64.pow(18)))
+        );
+        assert_eq!(U256::from(2_u64).checked_pow(256), None);
+
+        assert_eq!(U256::MAX.checked_add(U256::ONE), None);
+        assert_eq!(U256::ZERO.checked_sub(U256::ONE), None);
+        assert_eq!(U256::MAX.checked_mul(U256::from(2_u64)), None);
+        assert_eq!(U256::ONE.checked_div(U256::ZERO), None);
+        assert_eq!(U256::MAX.wrapping_add(U256::ONE), U256::ZERO);
+        assert_eq!(U256::ZERO.saturating_sub(U256::ONE), U256::ZERO);
+        assert_eq!(U256::MAX.saturating_mul(U256::MAX), U256::MAX);
+
+        assert_eq!(U256::MAX >> 192, U256::from(u64::MAX));
+        assert_eq!((U256::MAX >> 1).leading_zeros(), 1);
+        assert_eq!(
+            U256::from(0xff_u64) & U256::from(0x0f_u64),
+            U256::from(0x0f_u64)
+        );
+        assert_eq!(!U256::ZERO, U256::MAX);
+    }
+
+    #[test]
+    fn signed_arithmetic() {
+        let (a, b) = (I256::from(-7), I256::from(2));
+        assert_eq!(a + b, I256::from(-5));
+        assert_eq!(a - b, I256::from(-9));
+        assert_eq!(a * b, I256::from(-14));
+        assert_eq!(a / b, I256::from(-3));
+        assert_eq!(a % b, I256::from(-1));
+        assert_eq!(-a, I256::from(7));
+        assert_eq!(a >> 1, I256::from(-4));
+        assert_eq!(a.checked_pow(3), Some(I256::from(-343)));
+        assert!(a < b && I256::MIN < a && b < I256::MAX);
+
+        assert_eq!(I256::MAX.checked_add(I256::ONE), None);
+        assert_eq!(I256::MIN.checked_sub(I256::ONE), None);
+        assert_eq!(I256::MIN.checked_neg(), None);
+        assert_eq!(I256::MIN.checked_div(I256::MINUS_ONE), None);
+        assert_eq!(I256::MIN.checked_rem(I256::MINUS_ONE), None);
+        assert_eq!(I256::MIN.checked_mul(I256::ONE), Some(I256::MIN));
+        assert_eq!(I256::MIN.unsigned_abs(), U256::ONE << 255);
+        assert_eq!(I256::MAX.saturating_add(I256::ONE), I256::MAX);
+        assert_eq!(I256::MIN.saturating_mul(I256::from(2)), I256::MIN);
+        assert_eq!(I256::MAX.wrapping_add(I256::ONE), I256::MIN);
+    }
+
+    #[test]
+    fn conversions() {
+        assert_eq!(u64::try_from(U256::from(42_u64)), Ok(42));
+        assert!(u64::try_from(U256::ONE << 64).is_err());
+        assert_eq!(u128::try_from(U256::from(u128::MAX)), Ok(u128::MAX));
+        assert!(U256::try_from(-1_i32).is_err());
+        assert_eq!(i8::try_from(I256::from(-128)), Ok(-128));
+        assert!(i8::try_from(I256::from(128)).is_err());
+        assert_eq!(i128::try_from(I256::from(i128::MIN)), Ok(i128::MIN));
+        assert!(i128::try_from(I256::from(u128::MAX)).is_err());
+        assert!(u8::try_from(I256::MINUS_ONE).is_err());
+        assert!(I256::try_from(U256::MAX).is_err());
+        assert!(U256::try_from(I256::MINUS_ONE).is_err());
+
+        let bytes = {
+            let mut bytes = [0; 32];
+            bytes[0] = 1;
+            bytes[31] = 2;
+            bytes
+        };
+        let value = U256::from_be_bytes(bytes);
+        assert_eq!(value, (U256::ONE << 248) + U256::from(2_u64));
+        assert_eq!(value.to_be_bytes(), bytes);
+        assert_eq!(U256::from_le_bytes(value.to_le_bytes()), value);
+        assert_eq!(I256::from(-1).to_be_bytes(), [0xff; 32]);
+    }
+
+    #[test]
+    fn quantity_serialization() {
+        let value = U256::from(10_u64).checked_pow(24).unwrap();
+        let json = json!("0xd3c21bcecceda1000000");
+        assert_eq!(serde_json::to_value(Quantity(value)).unwrap(), json);
+        assert_eq!(
+            serde_json::from_value::<Quantity<U256>>(json).unwrap().0,
+            value,
+        );
+        assert_eq!(
+            serde_json::from_value::<Quantity<U256>>(json!("0x0"))
+                .unwrap()
+                .0,
+            U256::ZERO,
+        );
+        assert!(serde_json::from_value::<Quantity<U256>>(json!("0x01")).is_err());
+        assert_eq!(
+            serde_json::from_value::<Quantity<I256>>(json!("0x2a"))
+                .unwrap()
+                .0,
+            I256::from(42),
+        );
+    }
+}
@@ -1,14 +1,20 @@
 //! Module containing all the data model definitions for JSON parameters and
 //! results used for RPC.
 
-use super::encoding::quantity;
+use super::bytes::FixedBytes;
+use super::encoding::{quantity, Quantity};
+use serde::de::{Deserializer, IntoDeserializer};
+use serde::ser::{SerializeStruct, Serializer};
 use serde::{Deserialize, Serialize};
 
+pub use super::address::Address;
+pub use super::bytes::Bytes;
+
 /// A 32-byte hash.
-pub type Hash = [u8; 32];
+pub type Hash = FixedBytes<32>;
 
-/// A 20-byte Ethereum address.
-pub type Address = [u8; 20];
+/// An 8-byte proof-of-work nonce.
+pub type Nonce = FixedBytes<8>;
 
 /// Sync status data.
 #[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
@@ -26,3 +32,277 @@ pub struct Syncing {
     #[serde(rename = "highestBlock", with = "quantity")]
     pub highest_block: u64,
 }
+
+/// A 256-byte bloom filter.
+pub type Bloom = FixedBytes<256>;
+
+/// A block header, as received from `newHeads` subscriptions.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct BlockHeader {
+    /// The block hash.
+    pub hash: Hash,
+
+    /// The hash of the parent block.
+    #[serde(rename = "parentHash")]
+    pub parent_hash: Hash,
+
+    /// The hash of the uncles (ommers) of the block.
+    #[serde(rename = "sha3Uncles")]
+    pub uncles_hash: Hash,
+
+    /// The address of the beneficiary of the block rewards.
+    pub miner: Address,
+
+    /// The root of the final state trie of the block.
+    #[serde(rename = "stateRoot")]
+    pub state_root: Hash,
+
+    /// The root of the transaction trie of the block.
+    #[serde(rename = "transactionsRoot")]
+    pub transactions_root: Hash,
+
+    /// The root of the receipts trie of the block.
+    #[serde(rename = "receiptsRoot")]
+    pub receipts_root: Hash,
+
+    /// The bloom filter for the logs of the block.
+    #[serde(rename = "logsBloom")]
+    pub logs_bloom: Bloom,
+
+    /// The difficulty of the block.
+    #[serde(with = "quantity")]
+    pub difficulty: u128,
+
+    /// The block number.
+    #[serde(with = "quantity")]
+    pub number: u64,
+
+    /// The maximum gas allowed in the block.
+    #[serde(rename = "gasLimit", with = "quantity")]
+    pub gas_limit: u64,
+
+    /// The total gas used by all transactions in the block.
+    #[serde(rename = "gasUsed", with = "quantity")]
+    pub gas_used: u64,
+
+    /// The unix timestamp for when the block was collated.
+    #[serde(with = "quantity")]
+    pub timestamp: u64,
+
+    /// The extra data field of the block.
+    #[serde(rename = "extraData")]
+    pub extra_data: Bytes,
+
+    /// The mix hash of the block.
+    #[serde(rename = "mixHash")]
+    pub mix_hash: Hash,
+
+    /// The proof-of-work nonce of the block.
+    pub nonce: Nonce,
+}
+
+/// A log emitted by a transaction.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct Log {
+    /// The address of the contract that emitted the log.
+    pub address: Address,
+
+    /// The indexed topics of the log.
+    pub topics: Vec<Hash>,
+
+    /// The non-indexed data of the log.
+    pub data: Bytes,
+
+    /// The hash of the block containing the log.
+    #[serde(rename = "blockHash")]
+    pub block_hash: Hash,
+
+    /// The number of the block containing the log.
+    #[serde(rename = "blockNumber", with = "quantity")]
+    pub block_number: u64,
+
+    /// The hash of the transaction that emitted the log.
+    #[serde(rename = "transactionHash")]
+    pub transaction_hash: Hash,
+
+    /// The index of the transaction in the block.
+    #[serde(rename = "transactionIndex", with = "quantity")]
+    pub transaction_index: u64,
+
+    /// The index of the log in the block.
+    #[serde(rename = "logIndex", with = "quantity")]
+    pub log_index: u64,
+
+    /// Whether the log was removed because of a chain reorganization.
+    #[serde(default)]
+    pub removed: bool,
+}
+
+/// A filter for matching logs.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize)]
+pub struct LogFilter {
+    /// The contract addresses to match logs from. An empty list matches logs
+    /// from any address.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub address: Vec<Address>,
+
+    /// The topics to match at each position. `None` matches any topic and
+    /// multiple topics in the same position match any one of them.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub topics: Vec<Option<Vec<Hash>>>,
+}
+
+/// A block identifier for methods that operate on the state at a specific
+/// block.
+///
+/// Blocks can be specified by number, by one of the block tags, or by hash
+/// using the EIP-1898 object form.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub enum BlockId {
+    /// A block number.
+    Number(u64),
+    /// The most recent block in the canonical chain.
+    #[default]
+    Latest,
+    /// The genesis block.
+    Earliest,
+    /// The pending state and transactions of the node.
+    Pending,
+    /// The most recent block that is safe from reorganizations under honest
+    /// majority and certain synchronicity assumptions.
+    Safe,
+    /// The most recent block that has been finalized.
+    Finalized,
+    /// A block hash, as specified by EIP-1898.
+    Hash {
+        /// The hash of the block.
+        hash: Hash,
+        /// Whether the node should return an error when the block is not in
+        /// the canonical chain.
+        require_canonical: bool,
+    },
+}
+
+impl From<u64> for BlockId {
+    fn from(number: u64) -> Self {
+        BlockId::Number(number)
+    }
+}
+
+impl From<Hash> for BlockId {
+    fn from(hash: Hash) -> Self {
+        BlockId::Hash {
+            hash,
+            require_canonical: false,
+        }
+    }
+}
+
+impl Serialize for BlockId {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match self {
+            BlockId::Number(number) => Quantity(number).serialize(serializer),
+            BlockId::Latest => serializer.serialize_str("latest"),
+            BlockId::Earliest => serializer.serialize_str("earliest"),
+            BlockId::Pending => serializer.serialize_str("pending"),
+            BlockId::Safe => serializer.serialize_str("safe"),
+            BlockId::Finalized => serializer.serialize_str("finalized"),
+            BlockId::Hash {
+                hash,
+                require_canonical,
+            } => {
+                let len = if *require_canonical { 2 } else { 1 };
+                let mut object = serializer.serialize_struct("BlockId", len)?;
+                object.serialize_field("blockHash", hash)?;
+                if *require_canonical {
+                    object.serialize_field("requireCanonical", require_canonical)?;
+                }
+                object.end()
+            }
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for BlockId {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Repr {
+            Tag(String),
+            Hash {
+                #[serde(rename = "blockHash")]
+                hash: Hash,
+                #[serde(rename = "requireCanonical", default)]
+                require_canonical: bool,
+            },
+            Number {
+                #[serde(rename = "blockNumber", with = "quantity")]
+                number: u64,
+            },
+        }
+
+        match Repr::deserialize(deserializer)? {
+            Repr::Tag(tag) => match tag.as_str() {
+                "latest" => Ok(BlockId::Latest),
+                "earliest" => Ok(BlockId::Earliest),
+                "pending" => Ok(BlockId::Pending),
+                "safe" => Ok(BlockId::Safe),
+                "finalized" => Ok(BlockId::Finalized),
+                number => Ok(BlockId::Number(
+                    Quantity::deserialize(number.into_deserializer())?.0,
+                )),
+            },
+            Repr::Hash {
+                hash,
+                require_canonical,
+            } => Ok(BlockId::Hash {
+                hash,
+                require_canonical,
+            }),
+            Repr::Number { number } => Ok(BlockId::Number(number)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn block_id_serialization() {
+        let hash = FixedBytes([0x42; 32]);
+        for (block, json) in [
+            (BlockId::Number(42), json!("0x2a")),
+            (BlockId::Latest, json!("latest")),
+            (BlockId::Earliest, json!("earliest")),
+            (BlockId::Pending, json!("pending")),
+            (BlockId::Safe, json!("safe")),
+            (BlockId::Finalized, json!("finalized")),
+            (BlockId::from(hash), json!({ "blockHash": hash })),
+            (
+                BlockId::Hash {
+                    hash,
+                    require_canonical: true,
+                },
+                json!({ "blockHash": hash, "requireCanonical": true }),
+            ),
+        ] {
+            assert_eq!(serde_json::to_value(block).unwrap(), json);
+            assert_eq!(serde_json::from_value::<BlockId>(json).unwrap(), block);
+        }
+
+        assert_eq!(
+            serde_json::from_value::<BlockId>(json!({ "blockNumber": "0x2a" })).unwrap(),
+            BlockId::Number(42),
+        );
+        assert!(serde_json::from_value::<BlockId>(json!("0x02a")).is_err());
+        assert!(serde_json::from_value::<BlockId>(json!("safest")).is_err());
+    }
+}
@@ -3,8 +3,12 @@
 //! `Cow` in that it can be owned or borrowed so that the provider can be shared
 //! with a API's sub namespaces.
 
-use crate::jsonrpc::Client;
-use std::ops::{Deref, DerefMut};
+use crate::jsonrpc::{Batch, Client, ClientError};
+use crate::transport::Transport;
+use serde::de::DeserializeOwned;
+use serde::ser::Serialize;
+use std::future::Future;
+use std::ops::Deref;
 
 /// A JSON RPC client API provider. This enum wraps [`ethrs::jsonrpc::Client`]
 /// but allows sharing.
@@ -13,7 +17,9 @@ pub enum Provider<'a, T> {
     /// An owned JSON RPC client.
     Owned(Client<T>),
     /// A shared JSON RPC client.
-    Shared(&'a mut Client<T>),
+    Shared(&'a Client<T>),
+    /// A JSON RPC batch, where calls are queued until the batch is sent.
+    Batch(&'a Batch<'a, T>),
 }
 
 impl<T> Provider<'static, T> {
@@ -25,10 +31,42 @@ impl<T> Provider<'static, T> {
 
 impl<T> Provider<'_, T> {
     /// Get a shared copy of the provider.
-    pub fn shared(&mut self) -> Provider<'_, T> {
+    pub fn shared(&self) -> Provider<'_, T> {
         match self {
             Provider::Owned(client) => Provider::Shared(client),
             Provider::Shared(client) => Provider::Shared(client),
+            Provider::Batch(batch) => Provider::Batch(batch),
+        }
+    }
+}
+
+impl<T> Provider<'_, T>
+where
+    T: Transport,
+{
+    /// Performs a JSON RPC call, or queues it if this is a batch provider.
+    ///
+    /// Note that the parameters are serialized and the call is queued eagerly,
+    /// before the returned future is first polled.
+    pub fn call<'b, P, R>(
+        &'b self,
+        method: &'b str,
+        params: P,
+    ) -> impl Future<Output = Result<R, ClientError<T::Error>>> + 'b
+    where
+        P: Serialize,
+        R: DeserializeOwned + 'b,
+    {
+        let call = match self {
+            Provider::Batch(batch) => Ok(batch.call(method, params)),
+            _ => Err(serde_json::value::to_raw_value(&params)),
+        };
+
+        async move {
+            match call {
+                Ok(batched) => batched.await,
+                Err(params) => self.deref().call(method, params?).await,
+            }
         }
     }
 }
@@ -39,6 +77,12 @@ impl<T> From<Client<T>> for Provider<'static, T> {
     }
 }
 
+impl<'a, T> From<&'a Batch<'a, T>> for Provider<'a, T> {
+    fn from(batch: &'a Batch<'a, T>) -> Self {
+        Provider::Batch(batch)
+    }
+}
+
 impl<T> Deref for Provider<'_, T> {
     type Target = Client<T>;
 
@@ -46,15 +90,7 @@ impl<T> Deref for Provider<'_, T> {
         match self {
             Provider::Owned(client) => client,
             Provider::Shared(client) => client,
-        }
-    }
-}
-
-impl<T> DerefMut for Provider<'_, T> {
-    fn deref_mut(&mut self) -> &mut Self::Target {
-        match self {
-            Provider::Owned(client) => client,
-            Provider::Shared(client) => client,
+            Provider::Batch(batch) => batch.client(),
         }
     }
 }
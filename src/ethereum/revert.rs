@@ -0,0 +1,222 @@
+//! Module for decoding revert data from "execution reverted" RPC errors, such
+//! as the ones returned by `eth_call` and `eth_estimateGas` when the executed
+//! code reverts.
+
+use super::encoding::{Data, U256};
+use crate::jsonrpc::{ClientError, ErrorCode, RpcError};
+use serde_json::Value;
+use std::convert::{TryFrom, TryInto};
+use std::error::Error;
+
+/// Selector for the `Error(string)` revert payload.
+const ERROR_SELECTOR: [u8; 4] = [0x08, 0xc3, 0x79, 0xa0];
+
+/// Selector for the `Panic(uint256)` revert payload.
+const PANIC_SELECTOR: [u8; 4] = [0x4e, 0x48, 0x7b, 0x71];
+
+/// Error code used by Geth and compatible nodes for execution reverts.
+const EXECUTION_REVERTED: i32 = 3;
+
+/// Decoded revert data.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Revert {
+    /// A revert with a reason string, from `require(condition, "reason")` or
+    /// `revert("reason")`.
+    Error(String),
+    /// A panic from a failed assertion, arithmetic overflow, out-of-bounds
+    /// access, etc. with its panic code.
+    Panic(U256),
+    /// A revert with a custom error or no reason, with its raw revert data.
+    Other(Vec<u8>),
+}
+
+impl Revert {
+    /// Extracts the revert from an RPC error. Returns `None` if the error is
+    /// not an execution revert.
+    ///
+    /// Errors are execution reverts if they have the execution reverted error
+    /// code or a revert message. Otherwise, only errors with `Error(string)` or
+    /// `Panic(uint256)` revert data are, as other errors may also include hex
+    /// data that isn't revert data.
+    pub fn from_rpc_error(error: &RpcError) -> Option<Self> {
+        let data = error.data.as_ref().and_then(revert_data);
+        let reverted = error.code == ErrorCode::Other(EXECUTION_REVERTED)
+            || error.message.to_lowercase().contains("revert");
+        if !reverted {
+            let selector = data.as_ref()?.get(..4)?;
+            if selector != ERROR_SELECTOR && selector != PANIC_SELECTOR {
+                return None;
+            }
+        }
+
+        Some(Revert::decode(&data.unwrap_or_default()))
+    }
+
+    /// Decodes raw revert data.
+    pub fn decode(data: &[u8]) -> Self {
+        let (selector, payload) = match data.len() {
+            len if len >= 4 => data.split_at(4),
+            _ => return Revert::Other(data.to_vec()),
+        };
+
+        let decoded = match selector {
+            s if s == ERROR_SELECTOR => decode_string(payload).map(Revert::Error),
+            s if s == PANIC_SELECTOR => decode_u256(payload).map(Revert::Panic),
+            _ => None,
+        };
+        decoded.unwrap_or_else(|| Revert::Other(data.to_vec()))
+    }
+}
+
+impl<E> ClientError<E>
+where
+    E: Error,
+{
+    /// Returns the decoded revert if the error is an execution revert.
+    pub fn revert(&self) -> Option<Revert> {
+        match self {
+            ClientError::Rpc(error) => Revert::from_rpc_error(error),
+            _ => None,
+        }
+    }
+}
+
+/// Extracts hex encoded revert data from an RPC error data value.
+///
+/// Nodes report revert data either directly as a hex string (Geth, Erigon,
+/// Nethermind), prefixed with a "Reverted " message (older Nethermind
+/// versions), or nested in an object with a `data` field (Hardhat, Ganache).
+fn revert_data(data: &Value) -> Option<Vec<u8>> {
+    match data {
+        Value::String(hex) => {
+            let hex = hex.strip_prefix("Reverted ").unwrap_or(hex);
+            let data = serde_json::from_value::<Data<Vec<u8>>>(hex.into()).ok()?;
+            Some(data.0)
+        }
+        Value::Object(object) => revert_data(object.get("data")?),
+        _ => None,
+    }
+}
+
+/// Reads an ABI encoded 32-byte word.
+fn word(payload: &[u8], offset: usize) -> Option<&[u8]> {
+    payload.get(offset..offset.checked_add(32)?)
+}
+
+/// Decodes an ABI encoded `uint256`.
+fn decode_u256(payload: &[u8]) -> Option<U256> {
+    Some(U256::from_be_bytes(word(payload, 0)?.try_into().ok()?))
+}
+
+/// Decodes an ABI encoded `uint256` that fits in a `u64`.
+fn decode_u64(payload: &[u8]) -> Option<u64> {
+    let word = word(payload, 0)?;
+    let (high, low) = word.split_at(24);
+    if high.iter().any(|&b| b != 0) {
+        return None;
+    }
+    Some(u64::from_be_bytes(low.try_into().ok()?))
+}
+
+/// Decodes an ABI encoded `string`.
+fn decode_string(payload: &[u8]) -> Option<String> {
+    let offset = usize::try_from(decode_u64(payload)?).ok()?;
+    let len = usize::try_from(decode_u64(payload.get(offset..)?)?).ok()?;
+    let start = offset.checked_add(32)?;
+    let bytes = payload.get(start..start.checked_add(len)?)?;
+    String::from_utf8(bytes.to_vec()).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::convert::Infallible;
+
+    fn rpc_error(value: Value) -> ClientError<Infallible> {
+        ClientError::Rpc(serde_json::from_value(value).unwrap())
+    }
+
+    #[test]
+    fn decodes_error_reason() {
+        let error = rpc_error(json!({
+            "code": 3,
+            "message": "execution reverted: nope",
+            "data": "0x08c379a0\
+                       0000000000000000000000000000000000000000000000000000000000000020\
+                       0000000000000000000000000000000000000000000000000000000000000004\
+                       6e6f706500000000000000000000000000000000000000000000000000000000",
+        }));
+        assert_eq!(error.revert(), Some(Revert::Error("nope".to_owned())));
+    }
+
+    #[test]
+    fn decodes_panic_code() {
+        let error = rpc_error(json!({
+            "code": -32000,
+            "message": "Reverted",
+            "data": "Reverted 0x4e487b71\
+                       0000000000000000000000000000000000000000000000000000000000000011",
+        }));
+        assert_eq!(error.revert(), Some(Revert::Panic(U256::from(0x11_u64))));
+    }
+
+    #[test]
+    fn custom_and_empty_reverts() {
+        let error = rpc_error(json!({
+            "code": -32603,
+            "message": "Error: VM Exception while processing transaction: revert",
+            "data": { "message": "revert", "data": "0xdeadbeef" },
+        }));
+        assert_eq!(
+            error.revert(),
+            Some(Revert::Other(vec![0xde, 0xad, 0xbe, 0xef])),
+        );
+
+        let error = rpc_error(json!({
+            "code": 3,
+            "message": "execution reverted",
+        }));
+        assert_eq!(error.revert(), Some(Revert::Other(Vec::new())));
+    }
+
+    #[test]
+    fn non_revert_errors() {
+        let error = rpc_error(json!({
+            "code": -32000,
+            "message": "header not found",
+        }));
+        assert_eq!(error.revert(), None);
+
+        let error = rpc_error(json!({
+            "code": -32000,
+            "message": "invalid transaction",
+            "data": "0xdeadbeef",
+        }));
+        assert_eq!(error.revert(), None);
+
+        assert_eq!(
+            ClientError::<Infallible>::NoResponse.revert(),
+            None::<Revert>,
+        );
+    }
+
+    #[test]
+    fn malformed_payloads() {
+        assert_eq!(
+            Revert::decode(&ERROR_SELECTOR),
+            Revert::Other(ERROR_SELECTOR.to_vec()),
+        );
+
+        let mut panic = PANIC_SELECTOR.to_vec();
+        panic.extend_from_slice(&[0xff; 31]);
+        assert_eq!(Revert::decode(&panic), Revert::Other(panic.clone()));
+    }
+
+    #[test]
+    fn decodes_large_panic_codes() {
+        let mut panic = PANIC_SELECTOR.to_vec();
+        panic.extend_from_slice(&[0xff; 32]);
+        assert_eq!(Revert::decode(&panic), Revert::Panic(U256::MAX));
+    }
+}
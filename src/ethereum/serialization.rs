@@ -2,7 +2,8 @@
 
 use super::encoding::Decode;
 use super::models::Syncing;
-use serde::de::{self, Deserialize, Deserializer};
+use serde::de::{self, Deserializer};
+use serde::Deserialize;
 use serde_json::Value;
 
 /// A struct for deserializing [`std::option::Option`] of [`ethrs::Syncing`]
@@ -40,3 +41,42 @@ impl<'de> Deserialize<'de> for MaybeSyncing {
         }
     }
 }
+
+/// A struct for deserializing `syncing` subscription notifications into an
+/// [`std::option::Option`] of [`ethrs::Syncing`].
+///
+/// Nodes either send `false` or an object with a `syncing` flag and the sync
+/// `status`, so both are accepted.
+pub struct SyncingStatus(Option<Syncing>);
+
+impl Decode<SyncingStatus> for Option<Syncing> {
+    fn decode(encoded: SyncingStatus) -> Self {
+        encoded.0
+    }
+}
+
+impl<'de> Deserialize<'de> for SyncingStatus {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        struct Status {
+            syncing: bool,
+            status: Option<Syncing>,
+        }
+
+        let json = Value::deserialize(deserializer)?;
+        match json {
+            Value::Bool(false) => Ok(SyncingStatus(None)),
+            value => {
+                let status = Status::deserialize(value).map_err(de::Error::custom)?;
+                match (status.syncing, status.status) {
+                    (false, _) => Ok(SyncingStatus(None)),
+                    (true, Some(syncing)) => Ok(SyncingStatus(Some(syncing))),
+                    (true, None) => Err(de::Error::missing_field("status")),
+                }
+            }
+        }
+    }
+}
@@ -1,6 +1,21 @@
 //! Module containing JSON RPC client implementation used by the Ethereum APIs.
 
+mod batch;
 mod client;
-mod data;
+pub(crate) mod data;
+mod duplex;
+mod id;
+pub mod metrics;
+mod notifications;
+pub mod server;
+mod subscription;
+#[cfg(feature = "tracing")]
+mod trace;
 
+pub use batch::{Batch, BatchCall};
 pub use client::{Client, ClientError};
+pub use data::{Error as RpcError, ErrorCode};
+pub use duplex::Duplex;
+pub use id::{Id, IdGenerator, NumericIds, StringIds, UuidIds};
+pub use notifications::Notifications;
+pub use subscription::Subscription;
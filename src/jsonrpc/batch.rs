@@ -0,0 +1,391 @@
+//! Module containing the JSON RPC batch request implementation.
+
+use super::client::{Client, ClientError};
+use super::data::{self, Id, Request, Response, Version};
+use super::metrics::CallError;
+use crate::transport::Transport;
+use serde::de::DeserializeOwned;
+use serde::ser::Serialize;
+use serde_json::value::RawValue;
+use std::collections::HashMap;
+use std::future::Future;
+use std::marker::PhantomData;
+use std::mem;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::task::{Context, Poll, Waker};
+use web_time::Instant;
+
+/// A batch of JSON RPC calls that are sent together in a single request.
+///
+/// Each call added to the batch returns a future that resolves with its own
+/// result once the batch is sent, so an error for one call does not affect the
+/// others. Calls that never receive a response, because the batch failed or
+/// was dropped without being sent, resolve to [`ClientError::NoResponse`].
+#[derive(Debug)]
+pub struct Batch<'a, T> {
+    client: &'a Client<T>,
+    calls: Mutex<Vec<Queued>>,
+}
+
+/// A call that is queued in a batch. Dropping a queued call without resolving
+/// it first resolves it without a response.
+#[derive(Debug)]
+struct Queued {
+    id: Id,
+    method: String,
+    params: Box<RawValue>,
+    slot: Arc<Mutex<Slot>>,
+}
+
+/// The result of a batched call, if there was a response for it.
+type CallResult = Option<Result<Box<RawValue>, data::Error>>;
+
+/// The result slot for a batched call.
+#[derive(Debug)]
+enum Slot {
+    /// The batch has not yet been sent, with an optional waker for the task
+    /// that last polled the call.
+    Waiting(Option<Waker>),
+    /// The batch was sent, with the call result if there was a response for
+    /// it.
+    Done(CallResult),
+}
+
+impl<'a, T> Batch<'a, T> {
+    /// Creates a new empty batch for the specified client.
+    pub(super) fn new(client: &'a Client<T>) -> Self {
+        Batch {
+            client,
+            calls: Default::default(),
+        }
+    }
+
+    /// Returns the client used for sending the batch.
+    pub fn client(&self) -> &'a Client<T> {
+        self.client
+    }
+
+    /// Returns the number of calls queued in the batch.
+    pub fn len(&self) -> usize {
+        self.calls().len()
+    }
+
+    /// Returns `true` if there are no calls queued in the batch.
+    pub fn is_empty(&self) -> bool {
+        self.calls().is_empty()
+    }
+
+    /// Queues a call in the batch. The returned future resolves once the batch
+    /// is sent.
+    pub fn call<P, R>(&self, method: &str, params: P) -> BatchCall<R, T>
+    where
+        P: Serialize,
+        R: DeserializeOwned,
+    {
+        let id = self.client.next_id();
+        let params = serde_json::value::to_raw_value(&params);
+
+        let state = params.map(|params| {
+            let slot = Arc::new(Mutex::new(Slot::Waiting(None)));
+            self.calls().push(Queued {
+                id,
+                method: method.to_owned(),
+                params,
+                slot: slot.clone(),
+            });
+            slot
+        });
+
+        BatchCall {
+            state: state.map_err(Some),
+            _marker: PhantomData,
+        }
+    }
+
+    fn calls(&self) -> MutexGuard<'_, Vec<Queued>> {
+        self.calls.lock().expect("poisoned batch calls")
+    }
+}
+
+impl<T> Batch<'_, T>
+where
+    T: Transport,
+{
+    /// Sends all queued calls in a single request, resolving their futures.
+    /// Calls queued after this point are sent with the next batch.
+    ///
+    /// An error is returned if the batch as a whole failed, in which case the
+    /// queued calls resolve without a response.
+    ///
+    /// Metrics and `tracing` spans for the calls are recorded once the batch is
+    /// sent, with the latency and request and response sizes of the whole
+    /// batch. As such, errors decoding the results of individual calls are not
+    /// recorded.
+    pub async fn send(&self) -> Result<(), ClientError<T::Error>> {
+        let calls = mem::take(&mut *self.calls());
+        if calls.is_empty() {
+            return Ok(());
+        }
+
+        let start = Instant::now();
+        let result = self.send_calls(&calls).await;
+        match &result {
+            Ok(results) => {
+                for (call, result) in calls.iter().zip(results) {
+                    let error = match result {
+                        Some(Ok(_)) => None,
+                        Some(Err(err)) => Some(CallError::Rpc(err.code)),
+                        None => Some(CallError::NoResponse),
+                    };
+                    self.client.record_call(&call.method, start, error);
+                }
+            }
+            Err(err) => {
+                for call in &calls {
+                    self.client
+                        .record_call(&call.method, start, Some(CallError::new(err)));
+                }
+            }
+        }
+
+        for (call, result) in calls.into_iter().zip(result?) {
+            call.resolve(result);
+        }
+
+        Ok(())
+    }
+
+    /// Sends the calls in a single request, returning the result for each call
+    /// if there was a response for it.
+    async fn send_calls(&self, calls: &[Queued]) -> Result<Vec<CallResult>, ClientError<T::Error>> {
+        let requests = calls
+            .iter()
+            .map(|call| Request {
+                jsonrpc: Version::V2,
+                method: &call.method,
+                params: &call.params,
+                id: call.id.clone(),
+            })
+            .collect::<Vec<_>>();
+        let request_buffer = serde_json::to_vec(&requests)?;
+
+        #[cfg(feature = "tracing")]
+        let traces = calls
+            .iter()
+            .map(|call| {
+                self.client
+                    .tracing()
+                    .start(&call.method, &call.id, &call.params, &request_buffer)
+            })
+            .collect::<Vec<_>>();
+
+        let result = match self.client.transport().call(&request_buffer).await {
+            Ok(response_buffer) => {
+                #[cfg(feature = "tracing")]
+                for trace in &traces {
+                    trace.response(&response_buffer);
+                }
+                results(calls, &response_buffer)
+            }
+            Err(err) => Err(ClientError::Transport(err)),
+        };
+
+        #[cfg(feature = "tracing")]
+        match &result {
+            Ok(results) => {
+                for (trace, result) in traces.into_iter().zip(results) {
+                    let error: Option<ClientError<T::Error>> = match result {
+                        Some(Ok(_)) => None,
+                        Some(Err(err)) => Some(ClientError::Rpc(err.clone())),
+                        None => Some(ClientError::NoResponse),
+                    };
+                    trace.finish(error.as_ref());
+                }
+            }
+            Err(err) => {
+                for trace in traces {
+                    trace.finish(Some(err));
+                }
+            }
+        }
+
+        result
+    }
+}
+
+/// Returns the result for each call from a batch response, if there was a
+/// response for it.
+fn results<E>(calls: &[Queued], response_buffer: &[u8]) -> Result<Vec<CallResult>, ClientError<E>>
+where
+    E: std::error::Error,
+{
+    let responses = match serde_json::from_slice::<Vec<Response<Box<RawValue>>>>(response_buffer) {
+        Ok(responses) => responses,
+        Err(err) => {
+            // NOTE: Nodes that reject a batch as a whole respond with a
+            // single error object instead of an array.
+            return Err(
+                match serde_json::from_slice::<Response<Box<RawValue>>>(response_buffer) {
+                    Ok(Response {
+                        result: Err(err), ..
+                    }) => err.into(),
+                    _ => err.into(),
+                },
+            );
+        }
+    };
+
+    let mut results = responses
+        .into_iter()
+        .filter_map(|response| Some((response.id?, response.result)))
+        .collect::<HashMap<_, _>>();
+    Ok(calls.iter().map(|call| results.remove(&call.id)).collect())
+}
+
+impl Queued {
+    fn resolve(&self, result: CallResult) {
+        let mut slot = self.slot.lock().expect("poisoned batch call");
+        if let Slot::Waiting(Some(waker)) = mem::replace(&mut *slot, Slot::Done(result)) {
+            waker.wake();
+        }
+    }
+}
+
+impl Drop for Queued {
+    fn drop(&mut self) {
+        let resolved = matches!(
+            *self.slot.lock().expect("poisoned batch call"),
+            Slot::Done(_)
+        );
+        if !resolved {
+            self.resolve(None);
+        }
+    }
+}
+
+/// Future for the result of a call queued in a [`Batch`].
+#[derive(Debug)]
+pub struct BatchCall<R, T> {
+    state: Result<Arc<Mutex<Slot>>, Option<serde_json::Error>>,
+    _marker: PhantomData<fn() -> (R, T)>,
+}
+
+impl<R, T> Future for BatchCall<R, T>
+where
+    R: DeserializeOwned,
+    T: Transport,
+{
+    type Output = Result<R, ClientError<T::Error>>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        let slot = match &mut self.state {
+            Ok(slot) => slot,
+            Err(err) => {
+                let err = err.take().expect("batch call polled after completion");
+                return Poll::Ready(Err(err.into()));
+            }
+        };
+
+        let mut slot = slot.lock().expect("poisoned batch call");
+        match &mut *slot {
+            Slot::Waiting(waker) => {
+                *waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+            Slot::Done(result) => {
+                let result = result.take().ok_or(ClientError::NoResponse)?;
+                Poll::Ready(Ok(serde_json::from_str(result?.get())?))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(feature = "mock")]
+    use crate::transport::MockTransport;
+    use futures::executor::block_on;
+    use serde_json::json;
+    use std::future::{self, Ready};
+
+    /// A transport that responds with a fixed response.
+    struct Fixed(Vec<u8>);
+
+    impl Transport for Fixed {
+        type Error = std::convert::Infallible;
+        type Call<'a> = Ready<Result<Vec<u8>, Self::Error>>;
+
+        fn call<'a>(&'a self, _: &'a [u8]) -> Self::Call<'a> {
+            future::ready(Ok(self.0.clone()))
+        }
+    }
+
+    #[test]
+    fn matches_responses_by_id() {
+        let client = Client::new(Fixed(
+            json!([
+                {
+                    "jsonrpc": "2.0",
+                    "error": { "code": -32000, "message": "oops", "data": null },
+                    "id": 1,
+                },
+                { "jsonrpc": "2.0", "result": null, "id": 2 },
+                { "jsonrpc": "2.0", "result": "0x2a", "id": 0 },
+            ])
+            .to_string()
+            .into_bytes(),
+        ));
+
+        let batch = client.batch();
+        let first = batch.call::<_, String>("eth_chainId", ());
+        let second = batch.call::<_, String>("eth_chainId", ());
+        let third = batch.call::<_, Option<String>>("eth_getBlockByNumber", ("latest", false));
+        let fourth = batch.call::<_, String>("eth_chainId", ());
+        assert_eq!(batch.len(), 4);
+
+        block_on(async {
+            batch.send().await.unwrap();
+            assert!(batch.is_empty());
+
+            assert_eq!(first.await.unwrap(), "0x2a");
+            assert!(matches!(second.await, Err(ClientError::Rpc(_))));
+            assert_eq!(third.await.unwrap(), None);
+            assert!(matches!(fourth.await, Err(ClientError::NoResponse)));
+        });
+    }
+
+    #[test]
+    fn batch_error() {
+        let client = Client::new(Fixed(
+            json!({
+                "jsonrpc": "2.0",
+                "error": { "code": -32600, "message": "batches not supported", "data": null },
+                "id": null,
+            })
+            .to_string()
+            .into_bytes(),
+        ));
+
+        let batch = client.batch();
+        let call = batch.call::<_, String>("eth_chainId", ());
+
+        block_on(async {
+            assert!(matches!(batch.send().await, Err(ClientError::Rpc(_))));
+            assert!(matches!(call.await, Err(ClientError::NoResponse)));
+        });
+    }
+
+    #[cfg(feature = "mock")]
+    #[test]
+    fn dropped_batch() {
+        let client = Client::new(MockTransport::default());
+
+        let batch = client.batch();
+        let call = batch.call::<_, String>("eth_chainId", ());
+        drop(batch);
+
+        assert!(matches!(block_on(call), Err(ClientError::NoResponse)));
+    }
+}
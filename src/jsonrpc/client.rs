@@ -1,21 +1,31 @@
 //! Module containing transport agnostic JSON RPC client implementation.
 
-use super::data::{self, Id, Request, Response, Version};
-use crate::transport::Transport;
+use super::batch::Batch;
+use super::data::{self, Notification, Request, Response, Version};
+use super::duplex::Duplex;
+use super::id::{Id, IdGenerator, NumericIds};
+use super::metrics::{CallError, Metrics};
+use super::notifications::Notifications;
+use super::subscription::Subscription;
+#[cfg(feature = "tracing")]
+use super::trace;
+use crate::transport::{DuplexTransport, Transport};
 use serde::de::DeserializeOwned;
 use serde::ser::Serialize;
+use serde_json::value::{RawValue, Value};
 use std::error::Error;
-use std::sync::atomic::{AtomicU32, Ordering};
 use thiserror::Error;
+use web_time::Instant;
 
-/// Default buffer size used for writing response bytes.
-const BUFFER_SIZE: usize = 1024;
-
-/// A JSON RPC client over a generic simplex transport.
+/// A JSON RPC client over a generic simplex transport. Duplex transports can
+/// be used by wrapping them in a [`Duplex`] router.
 #[derive(Debug)]
 pub struct Client<T> {
     transport: T,
-    current_id: AtomicU32,
+    ids: Box<dyn IdGenerator>,
+    metrics: Option<Box<dyn Metrics>>,
+    #[cfg(feature = "tracing")]
+    tracing: trace::Config,
 }
 
 impl<T> Client<T> {
@@ -23,37 +33,262 @@ impl<T> Client<T> {
     pub fn new(transport: T) -> Self {
         Client {
             transport,
-            current_id: Default::default(),
+            ids: Box::new(NumericIds::default()),
+            metrics: None,
+            #[cfg(feature = "tracing")]
+            tracing: Default::default(),
         }
     }
+
+    /// Sets the generator used for request IDs. Sequential numerical IDs are
+    /// used by default.
+    pub fn with_id_generator(mut self, ids: impl IdGenerator + 'static) -> Self {
+        self.ids = Box::new(ids);
+        self
+    }
+
+    /// Sets the hook for recording metrics of the calls made by the client.
+    pub fn with_metrics(mut self, metrics: impl Metrics + 'static) -> Self {
+        self.metrics = Some(Box::new(metrics));
+        self
+    }
+
+    /// Sets whether call parameters are recorded in the `tracing` spans of
+    /// calls. Parameters are not recorded by default.
+    #[cfg(feature = "tracing")]
+    pub fn with_traced_params(mut self, enabled: bool) -> Self {
+        self.tracing.set_params(enabled);
+        self
+    }
+
+    /// Redacts the parameters of the specified method from `tracing` spans.
+    /// Parameters for methods that send transactions or sign data are redacted
+    /// by default.
+    #[cfg(feature = "tracing")]
+    pub fn with_redacted_params(mut self, method: impl Into<String>) -> Self {
+        self.tracing.redact(method.into());
+        self
+    }
+
+    /// Create a new empty batch of calls to send with this client.
+    pub fn batch(&self) -> Batch<'_, T> {
+        Batch::new(self)
+    }
+
+    /// Returns the ID for the next request.
+    pub(super) fn next_id(&self) -> Id {
+        self.ids.next_id()
+    }
+
+    /// Records the metrics of a completed call, if the client has a metrics
+    /// hook.
+    pub(super) fn record_call(&self, method: &str, start: Instant, error: Option<CallError>) {
+        if let Some(metrics) = &self.metrics {
+            metrics.record_call(method, start.elapsed(), error);
+        }
+    }
+
+    /// Returns a reference to the underlying transport.
+    pub fn transport(&self) -> &T {
+        &self.transport
+    }
+
+    /// Returns the tracing configuration of the client.
+    #[cfg(feature = "tracing")]
+    pub(super) fn tracing(&self) -> &trace::Config {
+        &self.tracing
+    }
+}
+
+impl<T> Client<Duplex<T>>
+where
+    T: DuplexTransport,
+{
+    /// Create a new client over the specified duplex transport. Responses are
+    /// routed back to their calls by ID.
+    pub fn duplex(transport: T) -> Self {
+        Client::new(Duplex::new(transport))
+    }
+
+    /// Returns a stream of the parameters of inbound notifications with the
+    /// specified method that are not part of any subscription.
+    pub fn notifications<P>(&self, method: &str) -> Notifications<'_, T, P>
+    where
+        P: DeserializeOwned,
+    {
+        Notifications::new(self, method)
+    }
+
+    /// Subscribes to notifications with a `<namespace>_subscribe` call,
+    /// returning a stream of the notification results.
+    pub async fn subscribe<P, R>(
+        &self,
+        namespace: &str,
+        params: P,
+    ) -> Result<Subscription<'_, T, R>, ClientError<T::Error>>
+    where
+        P: Serialize,
+        R: DeserializeOwned,
+    {
+        self.subscribe_with(namespace, params, |notification| {
+            serde_json::from_str(notification)
+        })
+        .await
+    }
+
+    /// Subscribes to notifications with a `<namespace>_subscribe` call using a
+    /// custom function for decoding the notification results.
+    pub(crate) async fn subscribe_with<P, R>(
+        &self,
+        namespace: &str,
+        params: P,
+        decode: fn(&str) -> Result<R, serde_json::Error>,
+    ) -> Result<Subscription<'_, T, R>, ClientError<T::Error>>
+    where
+        P: Serialize,
+    {
+        let start = Instant::now();
+        let method = format!("{}_subscribe", namespace);
+        let result = self.subscribe_request(&method, params).await;
+        self.record_call(&method, start, result.as_ref().err().map(CallError::new));
+
+        Ok(Subscription::new(self, namespace, result?, decode))
+    }
+
+    /// Sends a subscription request, returning the subscription ID.
+    async fn subscribe_request<P>(
+        &self,
+        method: &str,
+        params: P,
+    ) -> Result<String, ClientError<T::Error>>
+    where
+        P: Serialize,
+    {
+        let id = self.next_id();
+        let request_buffer = serde_json::to_vec(&Request {
+            jsonrpc: Version::V2,
+            method,
+            params: &params,
+            id: id.clone(),
+        })?;
+
+        #[cfg(feature = "tracing")]
+        let trace = self.tracing.start(method, &id, &params, &request_buffer);
+
+        let call = self.transport.subscribe(&request_buffer);
+        #[cfg(feature = "tracing")]
+        let call = tracing::Instrument::instrument(call, trace.span().clone());
+
+        let result = match call.await {
+            Ok(response_buffer) => {
+                #[cfg(feature = "tracing")]
+                trace.response(&response_buffer);
+                result(id, &response_buffer)
+                    .and_then(|result| Ok(serde_json::from_str(result.get())?))
+            }
+            Err(err) => Err(ClientError::Transport(err)),
+        };
+
+        #[cfg(feature = "tracing")]
+        trace.finish(result.as_ref().err());
+        result
+    }
 }
 
 impl<T> Client<T>
 where
     T: Transport,
 {
-    pub async fn call<P, R>(&mut self, method: &str, params: P) -> Result<R, ClientError<T::Error>>
+    pub async fn call<P, R>(&self, method: &str, params: P) -> Result<R, ClientError<T::Error>>
     where
         P: Serialize,
         R: DeserializeOwned,
     {
-        let id = self.current_id.fetch_add(1, Ordering::SeqCst);
+        self.call_with_params(method, params, |result| serde_json::from_str(result.get()))
+            .await
+    }
+
+    /// Performs a call with untyped parameters, returning the raw JSON result
+    /// as is. This is useful for forwarding calls without knowing their types
+    /// up front.
+    pub async fn call_raw(
+        &self,
+        method: &str,
+        params: Value,
+    ) -> Result<Box<RawValue>, ClientError<T::Error>> {
+        self.call_with_params(method, params, Ok).await
+    }
+
+    async fn call_with_params<P, R>(
+        &self,
+        method: &str,
+        params: P,
+        decode: fn(Box<RawValue>) -> Result<R, serde_json::Error>,
+    ) -> Result<R, ClientError<T::Error>>
+    where
+        P: Serialize,
+    {
+        let start = Instant::now();
+        let id = self.next_id();
         let request_buffer = serde_json::to_vec(&Request {
+            jsonrpc: Version::V2,
+            method,
+            params: &params,
+            id: id.clone(),
+        })?;
+
+        #[cfg(feature = "tracing")]
+        let trace = self.tracing.start(method, &id, &params, &request_buffer);
+
+        let call = self.transport.call(&request_buffer);
+        #[cfg(feature = "tracing")]
+        let call = tracing::Instrument::instrument(call, trace.span().clone());
+
+        let result = match call.await {
+            Ok(response_buffer) => {
+                #[cfg(feature = "tracing")]
+                trace.response(&response_buffer);
+                result(id, &response_buffer).and_then(|result| Ok(decode(result)?))
+            }
+            Err(err) => Err(ClientError::Transport(err)),
+        };
+
+        #[cfg(feature = "tracing")]
+        trace.finish(result.as_ref().err());
+        self.record_call(method, start, result.as_ref().err().map(CallError::new));
+        result
+    }
+
+    /// Sends a notification. Notifications are requests without an ID, for
+    /// which the server does not reply.
+    pub async fn notify<P>(&self, method: &str, params: P) -> Result<(), ClientError<T::Error>>
+    where
+        P: Serialize,
+    {
+        let start = Instant::now();
+        let result = self.notify_request(method, params).await;
+        self.record_call(method, start, result.as_ref().err().map(CallError::new));
+        result
+    }
+
+    async fn notify_request<P>(&self, method: &str, params: P) -> Result<(), ClientError<T::Error>>
+    where
+        P: Serialize,
+    {
+        let request_buffer = serde_json::to_vec(&Notification {
             jsonrpc: Version::V2,
             method,
             params,
-            id: Id(id),
         })?;
 
-        let mut response_buffer = Vec::with_capacity(BUFFER_SIZE);
+        // NOTE: Simplex transports may still produce a (typically empty) reply
+        // which we ignore.
         self.transport
-            .call(&*request_buffer, &mut response_buffer)
+            .call(&request_buffer)
             .await
             .map_err(ClientError::Transport)?;
 
-        let response = serde_json::from_slice::<Response<R>>(&*response_buffer)?;
-
-        Ok(response.result?)
+        Ok(())
     }
 }
 
@@ -73,4 +308,153 @@ where
     /// An RPC error was returned by the transport.
     #[error("RPC error: {0}")]
     Rpc(#[from] data::Error),
+    /// No response was received for a batched call, either because the batch
+    /// failed as a whole or because it did not include a response for the
+    /// call.
+    #[error("no response for batched call")]
+    NoResponse,
+    /// The response ID does not match the request ID.
+    #[error("response ID does not match request ID {expected}")]
+    IdMismatch {
+        /// The ID of the request.
+        expected: Id,
+        /// The ID of the response.
+        actual: Option<Id>,
+    },
+}
+
+/// Reads the raw result from a serialized response to the request with the
+/// specified ID.
+///
+/// Error responses with a `null` ID are accepted, as servers use them when
+/// the request ID could not be determined, for example because the request
+/// could not be parsed.
+fn result<E>(id: Id, response_buffer: &[u8]) -> Result<Box<RawValue>, ClientError<E>>
+where
+    E: Error,
+{
+    let response = serde_json::from_slice::<Response<Box<RawValue>>>(response_buffer)?;
+    match (&response.id, &response.result) {
+        (Some(actual), _) if *actual == id => {}
+        (None, Err(_)) => {}
+        _ => {
+            return Err(ClientError::IdMismatch {
+                expected: id,
+                actual: response.id,
+            })
+        }
+    }
+
+    Ok(response.result?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::jsonrpc::StringIds;
+    use futures::executor::block_on;
+    use serde_json::json;
+    use std::convert::Infallible;
+    use std::future::{self, Ready};
+
+    /// A transport that always responds with the specified response, using
+    /// the request ID if the response doesn't specify one.
+    struct Respond(Value);
+
+    impl Transport for Respond {
+        type Error = Infallible;
+        type Call<'a> = Ready<Result<Vec<u8>, Infallible>>;
+
+        fn call<'a>(&'a self, request: &'a [u8]) -> Self::Call<'a> {
+            let request = serde_json::from_slice::<Value>(request).unwrap();
+            let mut response = self.0.clone();
+            if response.get("id").is_none() {
+                response["id"] = request["id"].clone();
+            }
+            future::ready(Ok(serde_json::to_vec(&response).unwrap()))
+        }
+    }
+
+    #[test]
+    fn custom_ids() {
+        let client = Client::new(Respond(json!({ "jsonrpc": "2.0", "result": true })))
+            .with_id_generator(StringIds::new("test-"));
+        assert!(block_on(client.call::<_, bool>("test", ())).unwrap());
+    }
+
+    #[test]
+    fn raw_calls() {
+        let client = Client::new(Respond(json!({
+            "jsonrpc": "2.0",
+            "result": { "number": "0x1", "hash": null },
+        })));
+        let result = block_on(client.call_raw("eth_getBlockByNumber", json!(["0x1", false])));
+        assert_eq!(result.unwrap().get(), r#"{"hash":null,"number":"0x1"}"#);
+
+        let client = Client::new(Respond(json!({
+            "jsonrpc": "2.0",
+            "error": { "code": -32601, "message": "method not found" },
+        })));
+        assert!(matches!(
+            block_on(client.call_raw("eth_foo", Value::Null)),
+            Err(ClientError::Rpc(_)),
+        ));
+    }
+
+    #[test]
+    fn validates_response_ids() {
+        let client = Client::new(Respond(json!({
+            "jsonrpc": "2.0",
+            "result": true,
+            "id": "other",
+        })));
+        match block_on(client.call::<_, bool>("test", ())) {
+            Err(ClientError::IdMismatch { expected, actual }) => {
+                assert_eq!(expected, Id::Number(0));
+                assert_eq!(actual, Some(Id::String("other".to_owned())));
+            }
+            result => panic!("unexpected result {:?}", result),
+        }
+
+        let client = Client::new(Respond(json!({
+            "jsonrpc": "2.0",
+            "result": true,
+            "id": null,
+        })));
+        assert!(matches!(
+            block_on(client.call::<_, bool>("test", ())),
+            Err(ClientError::IdMismatch { actual: None, .. }),
+        ));
+    }
+
+    #[test]
+    fn reports_odd_numeric_ids_as_mismatches() {
+        for id in [json!(-1), json!(0.0)] {
+            let client = Client::new(Respond(json!({
+                "jsonrpc": "2.0",
+                "result": true,
+                "id": id,
+            })));
+            assert!(matches!(
+                block_on(client.call::<_, bool>("test", ())),
+                Err(ClientError::IdMismatch {
+                    actual: Some(Id::Other(_)),
+                    ..
+                }),
+            ));
+        }
+    }
+
+    #[test]
+    fn accepts_null_id_errors() {
+        let client = Client::new(Respond(json!({
+            "jsonrpc": "2.0",
+            "error": { "code": -32700, "message": "parse error" },
+            "id": null,
+        })));
+        assert!(matches!(
+            block_on(client.call::<_, bool>("test", ())),
+            Err(ClientError::Rpc(_)),
+        ));
+    }
 }
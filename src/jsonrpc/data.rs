@@ -1,8 +1,10 @@
 //! Module containing serializable JSON RPC data types.
 
-use serde::{Deserialize, Serialize};
-use serde_json::Value;
+use serde::ser::SerializeStruct;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::value::{RawValue, Value};
 use std::convert::TryFrom;
+use std::slice;
 use thiserror::Error;
 
 /// JSON RPC supported version.
@@ -13,15 +15,7 @@ pub enum Version {
     V2,
 }
 
-/// Request and response ID.
-///
-/// Note that `u32` is used. This is so it always fits in a `f64` and obeys the
-/// "SHOULD NOT have fractional parts" rule from the specification.  Since the
-/// ID is set by the client, we shouldn't run into issues where a numerical ID
-/// does not fit into this value or a string ID is used.
-#[derive(Debug, Deserialize, Serialize)]
-#[serde(transparent)]
-pub struct Id(pub u32);
+pub use super::id::Id;
 
 /// Request object.
 #[derive(Debug, Serialize)]
@@ -32,7 +26,31 @@ pub struct Request<'a, P> {
     pub id: Id,
 }
 
+/// Request object with unparsed parameters, as received by a server.
+#[allow(dead_code)]
+#[derive(Debug, Deserialize)]
+pub struct RawRequest {
+    pub jsonrpc: Version,
+    pub method: String,
+    #[serde(default)]
+    pub params: Option<Box<RawValue>>,
+    /// The request ID, or `None` for notifications. Note that a `null` ID is
+    /// different from a missing ID.
+    #[serde(default, deserialize_with = "some")]
+    pub id: Option<Option<Id>>,
+}
+
+/// Notification object. This is a request object without an ID, for which
+/// the server does not reply.
+#[derive(Debug, Serialize)]
+pub struct Notification<'a, P> {
+    pub jsonrpc: Version,
+    pub method: &'a str,
+    pub params: P,
+}
+
 /// Response object.
+#[allow(dead_code)]
 #[derive(Debug, Deserialize)]
 #[serde(try_from = "RawResponse<R>")]
 pub struct Response<R> {
@@ -41,6 +59,25 @@ pub struct Response<R> {
     pub id: Option<Id>,
 }
 
+impl<R> Serialize for Response<R>
+where
+    R: Serialize,
+{
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut response = serializer.serialize_struct("Response", 3)?;
+        response.serialize_field("jsonrpc", &self.jsonrpc)?;
+        match &self.result {
+            Ok(result) => response.serialize_field("result", result)?,
+            Err(error) => response.serialize_field("error", error)?,
+        }
+        response.serialize_field("id", &self.id)?;
+        response.end()
+    }
+}
+
 impl<R> TryFrom<RawResponse<R>> for Response<R> {
     type Error = MissingResultError;
 
@@ -62,27 +99,128 @@ impl<R> TryFrom<RawResponse<R>> for Response<R> {
 pub struct MissingResultError;
 
 #[derive(Debug, Deserialize)]
-#[serde(deny_unknown_fields)]
+#[serde(bound = "R: Deserialize<'de>")]
 struct RawResponse<R> {
     pub jsonrpc: Version,
+    #[serde(default, deserialize_with = "some")]
     pub result: Option<R>,
     pub error: Option<Error>,
     pub id: Option<Id>,
 }
 
+/// Deserializes a present value into `Some`. This is used so that `null`
+/// results can be distinguished from missing ones.
+fn some<'de, T, D>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    T: Deserialize<'de>,
+    D: Deserializer<'de>,
+{
+    T::deserialize(deserializer).map(Some)
+}
+
+/// Partial message object used for routing messages sent and received over
+/// duplex transports by their ID without fully deserializing them.
+#[derive(Debug, Deserialize)]
+pub struct Envelope {
+    pub id: Option<Id>,
+}
+
+/// A single message object or a batch of message objects.
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+pub enum Messages<T> {
+    Single(T),
+    Batch(Vec<T>),
+}
+
+impl<T> Messages<T> {
+    /// Returns an iterator over the messages.
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        match self {
+            Messages::Single(message) => slice::from_ref(message).iter(),
+            Messages::Batch(messages) => messages.iter(),
+        }
+    }
+}
+
+impl Messages<Envelope> {
+    /// Returns the IDs of the messages.
+    pub fn ids(&self) -> Vec<Id> {
+        self.iter()
+            .filter_map(|envelope| envelope.id.clone())
+            .collect()
+    }
+}
+
+/// Partial request object used for inspecting the method of requests without
+/// fully deserializing them.
+#[derive(Debug, Deserialize)]
+pub struct MethodEnvelope {
+    pub method: String,
+}
+
+/// Partial response object used for inspecting the error code of responses
+/// without fully deserializing them.
+#[derive(Debug, Deserialize)]
+pub struct ErrorEnvelope {
+    pub error: Option<ErrorCodeEnvelope>,
+}
+
+/// Partial error object containing only the error code.
+#[derive(Debug, Deserialize)]
+pub struct ErrorCodeEnvelope {
+    pub code: i32,
+}
+
+/// Partial notification object used for routing notifications received over
+/// duplex transports without fully deserializing their parameters.
+#[derive(Debug, Deserialize)]
+pub struct NotificationEnvelope {
+    pub method: String,
+    #[serde(default)]
+    pub params: Option<Box<RawValue>>,
+}
+
+/// Subscription notification parameters.
+#[derive(Debug, Deserialize)]
+pub struct SubscriptionParams {
+    pub subscription: String,
+    pub result: Box<RawValue>,
+}
+
 /// An RPC error that may be produced on a response.
-#[derive(Debug, Deserialize, Error)]
-#[error("{code}: {error}")]
-#[serde(deny_unknown_fields)]
+#[derive(Clone, Debug, Deserialize, Error, Serialize)]
+#[error("{code}: {message}")]
 pub struct Error {
+    /// The error code.
     pub code: ErrorCode,
-    pub error: String,
-    pub data: Value,
+    /// A short description of the error.
+    pub message: String,
+    /// Additional information about the error defined by the server.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data: Option<Value>,
+}
+
+impl Error {
+    /// Creates a new error with the specified code and message.
+    pub fn new(code: impl Into<ErrorCode>, message: impl Into<String>) -> Self {
+        Error {
+            code: code.into(),
+            message: message.into(),
+            data: None,
+        }
+    }
+
+    /// Sets additional information about the error.
+    pub fn with_data(mut self, data: Value) -> Self {
+        self.data = Some(data);
+        self
+    }
 }
 
 /// An error code.
-#[derive(Debug, Deserialize, Error)]
-#[serde(from = "i32")]
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Error, PartialEq, Serialize)]
+#[serde(from = "i32", into = "i32")]
 pub enum ErrorCode {
     #[error("parse error")]
     ParseError,
@@ -117,3 +255,63 @@ impl From<i32> for ErrorCode {
         }
     }
 }
+
+impl From<ErrorCode> for i32 {
+    fn from(code: ErrorCode) -> Self {
+        match code {
+            ErrorCode::ParseError => -32700,
+            ErrorCode::InvalidRequest => -32600,
+            ErrorCode::MethodNotFound => -32601,
+            ErrorCode::InvalidParams => -32602,
+            ErrorCode::InternalError => -32603,
+            ErrorCode::ServerError(code) | ErrorCode::Reserved(code) | ErrorCode::Other(code) => {
+                code
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn node_errors() {
+        let response = serde_json::from_value::<Response<Value>>(json!({
+            "jsonrpc": "2.0",
+            "error": {
+                "code": 3,
+                "message": "execution reverted: nope",
+                "data": "0x08c379a0",
+            },
+            "id": 1,
+        }))
+        .unwrap();
+        let error = response.result.unwrap_err();
+        assert_eq!(error.code, ErrorCode::Other(3));
+        assert_eq!(error.message, "execution reverted: nope");
+        assert_eq!(error.data, Some(json!("0x08c379a0")));
+
+        let error = serde_json::from_value::<Error>(json!({
+            "code": -32601,
+            "message": "the method eth_foo does not exist/is not available",
+            "extra": true,
+        }))
+        .unwrap();
+        assert_eq!(error.code, ErrorCode::MethodNotFound);
+        assert_eq!(error.data, None);
+    }
+
+    #[test]
+    fn ignores_unknown_response_fields() {
+        let response = serde_json::from_value::<Response<Value>>(json!({
+            "jsonrpc": "2.0",
+            "result": "0x1",
+            "id": 1,
+            "extra": true,
+        }))
+        .unwrap();
+        assert_eq!(response.result.unwrap(), json!("0x1"));
+    }
+}
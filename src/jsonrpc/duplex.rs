@@ -0,0 +1,725 @@
+//! Module containing the routing logic for using duplex transports with the
+//! JSON RPC client.
+//!
+//! There is no background task receiving messages from the transport. Instead,
+//! whichever pending call gets polled drives the transport and dispatches the
+//! received responses to the calls they belong to. Subscriptions and
+//! notification listeners are driven in the same way when their streams are
+//! polled. This keeps the implementation independent of any specific async
+//! runtime.
+
+#[cfg(feature = "tracing")]
+use super::data::ErrorEnvelope;
+use super::data::{Envelope, Id, Messages, NotificationEnvelope, Response, SubscriptionParams};
+use crate::transport::{DuplexTransport, Transport};
+use serde_json::value::RawValue;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::future::Future;
+use std::mem;
+use std::pin::Pin;
+use std::sync::{Mutex, MutexGuard};
+use std::task::{ready, Context, Poll, Waker};
+
+/// A duplex transport wrapper that routes responses back to the calls that
+/// are waiting on them by matching JSON RPC IDs, subscription notifications to
+/// their subscriptions, and other notifications to their listeners.
+#[derive(Debug)]
+pub struct Duplex<T> {
+    transport: T,
+    routes: Mutex<Routes>,
+}
+
+/// The routing table for pending calls and active subscriptions.
+#[derive(Debug, Default)]
+struct Routes {
+    /// Pending calls keyed by their ID. Batch calls are keyed by the ID of
+    /// their first request.
+    pending: HashMap<Id, Slot>,
+    /// Mapping of the remaining request IDs of pending batch calls to the key
+    /// of the batch in the pending calls.
+    batched: HashMap<Id, Id>,
+    /// Keys of the pending calls that create subscriptions.
+    subscribing: HashSet<Id>,
+    /// Active subscriptions keyed by their subscription ID.
+    subscriptions: HashMap<String, Inbox>,
+    /// Notification listeners keyed by an internal listener ID.
+    listeners: HashMap<u64, Listener>,
+    /// The ID of the next notification listener.
+    next_listener: u64,
+    /// Messages that are queued to be sent ahead of the next call.
+    outbox: VecDeque<Vec<u8>>,
+}
+
+/// The routing slot for a pending call.
+#[derive(Debug)]
+enum Slot {
+    /// The call is waiting on its response, with an optional waker for the
+    /// task that last polled it.
+    Waiting(Option<Waker>),
+    /// The response was received and is waiting to be read.
+    Ready(Vec<u8>),
+}
+
+/// The received notifications for an active subscription or listener.
+#[derive(Debug, Default)]
+struct Inbox {
+    notifications: VecDeque<Box<RawValue>>,
+    waker: Option<Waker>,
+}
+
+/// A listener for notifications with a specific method that are not routed to
+/// any subscription.
+#[derive(Debug)]
+struct Listener {
+    method: String,
+    inbox: Inbox,
+}
+
+impl<T> Duplex<T> {
+    /// Creates a new router for the specified duplex transport.
+    pub fn new(transport: T) -> Self {
+        Duplex {
+            transport,
+            routes: Default::default(),
+        }
+    }
+
+    /// Returns a reference to the underlying duplex transport.
+    pub fn transport(&self) -> &T {
+        &self.transport
+    }
+
+    /// Removes an active subscription so that its notifications are no longer
+    /// routed. Returns `true` if the subscription was active.
+    pub(super) fn unsubscribe(&self, id: &str) -> bool {
+        let mut routes = self.routes();
+        let removed = routes.subscriptions.remove(id).is_some();
+        routes.wake_all();
+        removed
+    }
+
+    /// Starts listening for notifications with the specified method, returning
+    /// the ID of the new listener.
+    pub(super) fn listen(&self, method: &str) -> u64 {
+        let mut routes = self.routes();
+        let id = routes.next_listener;
+        routes.next_listener += 1;
+        routes.listeners.insert(
+            id,
+            Listener {
+                method: method.to_owned(),
+                inbox: Inbox::default(),
+            },
+        );
+        id
+    }
+
+    /// Removes a notification listener.
+    pub(super) fn unlisten(&self, id: u64) {
+        let mut routes = self.routes();
+        routes.listeners.remove(&id);
+        routes.wake_all();
+    }
+
+    /// Queues a message to be sent ahead of the next call.
+    pub(super) fn queue(&self, message: Vec<u8>) {
+        self.routes().outbox.push_back(message);
+    }
+
+    fn routes(&self) -> MutexGuard<'_, Routes> {
+        self.routes.lock().expect("poisoned routes")
+    }
+}
+
+impl<T> Duplex<T>
+where
+    T: DuplexTransport,
+{
+    /// Sends a serialized subscription request and waits for its response.
+    /// Once the response is received, notifications for the subscription are
+    /// routed and can be received with [`Duplex::poll_notification`].
+    pub(super) fn subscribe<'a>(&'a self, request: &'a [u8]) -> Call<'a, T> {
+        self.start(request, true)
+    }
+
+    /// Attempts to receive the next notification for the specified
+    /// subscription. Returns `None` if the subscription is not active.
+    ///
+    /// Queued messages are sent using the specified slot for the message that
+    /// is currently being sent.
+    pub(super) fn poll_notification<'a>(
+        &'a self,
+        id: &str,
+        send: &mut Option<Pin<Box<T::Send<'a>>>>,
+        cx: &mut Context,
+    ) -> Poll<Option<Result<Box<RawValue>, T::Error>>> {
+        self.poll_inbox(send, cx, |routes| routes.subscriptions.get_mut(id))
+    }
+
+    /// Attempts to receive the next notification for the specified listener.
+    /// Returns `None` if the listener was removed.
+    ///
+    /// Queued messages are sent using the specified slot for the message that
+    /// is currently being sent.
+    pub(super) fn poll_listener<'a>(
+        &'a self,
+        id: u64,
+        send: &mut Option<Pin<Box<T::Send<'a>>>>,
+        cx: &mut Context,
+    ) -> Poll<Option<Result<Box<RawValue>, T::Error>>> {
+        self.poll_inbox(send, cx, |routes| {
+            routes
+                .listeners
+                .get_mut(&id)
+                .map(|listener| &mut listener.inbox)
+        })
+    }
+
+    fn poll_inbox<'a>(
+        &'a self,
+        send: &mut Option<Pin<Box<T::Send<'a>>>>,
+        cx: &mut Context,
+        mut inbox: impl FnMut(&mut Routes) -> Option<&mut Inbox>,
+    ) -> Poll<Option<Result<Box<RawValue>, T::Error>>> {
+        // NOTE: Streams may be polled without any calls being made, so they
+        // also need to send queued messages (such as unsubscribe requests from
+        // dropped subscriptions). Receiving does not wait on sending though.
+        if let Poll::Ready(Err(err)) = self.poll_flush(send, cx) {
+            return Poll::Ready(Some(Err(err)));
+        }
+
+        let mut routes = self.routes();
+        let result = loop {
+            match inbox(&mut routes) {
+                Some(inbox) => {
+                    if let Some(notification) = inbox.notifications.pop_front() {
+                        break Some(Ok(notification));
+                    }
+                }
+                None => break None,
+            }
+
+            match self.transport.poll_receive(cx) {
+                Poll::Ready(Ok(message)) => routes.dispatch(message),
+                Poll::Ready(Err(err)) => break Some(Err(err)),
+                Poll::Pending => {
+                    if let Some(inbox) = inbox(&mut routes) {
+                        inbox.waker = Some(cx.waker().clone());
+                    }
+                    return Poll::Pending;
+                }
+            }
+        };
+
+        // NOTE: The transport only wakes the task that last polled it, which
+        // may be this stream's. The stream's task may not poll it again after
+        // it produced an item, so wake up the remaining calls and streams so
+        // that one of them can take over driving the transport, the same way
+        // as when a call is dropped.
+        routes.wake_all();
+        Poll::Ready(result)
+    }
+
+    /// Sends queued messages until the queue is empty, using the specified
+    /// slot for the message that is currently being sent.
+    fn poll_flush<'a>(
+        &'a self,
+        send: &mut Option<Pin<Box<T::Send<'a>>>>,
+        cx: &mut Context,
+    ) -> Poll<Result<(), T::Error>> {
+        loop {
+            if let Some(sending) = send {
+                ready!(sending.as_mut().poll(cx))?;
+                *send = None;
+            }
+
+            let message = match self.routes().outbox.pop_front() {
+                Some(message) => message,
+                None => return Poll::Ready(Ok(())),
+            };
+            *send = Some(Box::pin(self.transport.send(message)));
+        }
+    }
+
+    fn start<'a>(&'a self, request: &'a [u8], subscribe: bool) -> Call<'a, T> {
+        // NOTE: Register the call before sending the request so that the
+        // response can't arrive before we are ready to route it.
+        let ids = serde_json::from_slice::<Messages<Envelope>>(request)
+            .map(|envelopes| envelopes.ids())
+            .unwrap_or_default();
+        let registration = Registration::new(self, ids, subscribe);
+
+        Call {
+            router: self,
+            registration,
+            request: Some(request.to_vec()),
+            send: None,
+        }
+    }
+}
+
+impl<T> Transport for Duplex<T>
+where
+    T: DuplexTransport,
+{
+    type Error = T::Error;
+    type Call<'a>
+        = Call<'a, T>
+    where
+        T: 'a;
+
+    /// Sends a serialized request and waits for the response with a matching
+    /// ID. Requests without an ID are sent without waiting for a response.
+    fn call<'a>(&'a self, request: &'a [u8]) -> Self::Call<'a> {
+        self.start(request, false)
+    }
+}
+
+impl Routes {
+    /// Routes a received message to its pending call, subscription or
+    /// notification listeners, waking their tasks. Messages that do not belong
+    /// to any of them, such as responses to calls that were dropped, are
+    /// discarded.
+    ///
+    /// Error responses with a `null` ID, which servers send when the request
+    /// ID could not be determined (for example, for parse errors), can't be
+    /// matched to a call. They are logged and discarded, as failing pending
+    /// calls would also fail unrelated calls sharing the connection.
+    fn dispatch(&mut self, message: Vec<u8>) {
+        let ids = match serde_json::from_slice::<Messages<Envelope>>(&message) {
+            Ok(envelopes) => envelopes.ids(),
+            Err(_) => return,
+        };
+        if ids.is_empty() {
+            if let Ok(notification) = serde_json::from_slice::<NotificationEnvelope>(&message) {
+                self.notify(notification);
+            } else {
+                #[cfg(feature = "tracing")]
+                warn_unrouted_error(&message);
+            }
+            return;
+        }
+
+        for id in ids {
+            let key = self.batched.get(&id).cloned().unwrap_or(id);
+            if let Some(slot @ Slot::Waiting(_)) = self.pending.get_mut(&key) {
+                // NOTE: Start routing notifications for new subscriptions right
+                // away, as they may arrive before the subscription response is
+                // read by its call.
+                if self.subscribing.contains(&key) {
+                    if let Ok(Response { result: Ok(id), .. }) =
+                        serde_json::from_slice::<Response<String>>(&message)
+                    {
+                        self.subscriptions.insert(id, Inbox::default());
+                    }
+                }
+
+                if let Slot::Waiting(Some(waker)) = mem::replace(slot, Slot::Ready(message)) {
+                    waker.wake();
+                }
+                return;
+            }
+        }
+    }
+
+    fn notify(&mut self, notification: NotificationEnvelope) {
+        let params = notification.params;
+        if let Some(params) = &params {
+            if let Ok(params) = serde_json::from_str::<SubscriptionParams>(params.get()) {
+                if let Some(inbox) = self.subscriptions.get_mut(&params.subscription) {
+                    inbox.push(params.result);
+                    return;
+                }
+            }
+        }
+
+        // NOTE: Notifications without parameters are delivered as `null` to
+        // their listeners.
+        let params = match params {
+            Some(params) => params,
+            None => RawValue::from_string("null".to_owned()).expect("valid JSON"),
+        };
+        for listener in self.listeners.values_mut() {
+            if listener.method == notification.method {
+                listener.inbox.push(params.clone());
+            }
+        }
+    }
+
+    /// Wakes all waiting calls and subscriptions.
+    fn wake_all(&mut self) {
+        for slot in self.pending.values_mut() {
+            if let Slot::Waiting(waker) = slot {
+                if let Some(waker) = waker.take() {
+                    waker.wake();
+                }
+            }
+        }
+        for inbox in self.subscriptions.values_mut() {
+            if let Some(waker) = inbox.waker.take() {
+                waker.wake();
+            }
+        }
+        for listener in self.listeners.values_mut() {
+            if let Some(waker) = listener.inbox.waker.take() {
+                waker.wake();
+            }
+        }
+    }
+}
+
+/// Logs a discarded error response that has no ID.
+#[cfg(feature = "tracing")]
+fn warn_unrouted_error(message: &[u8]) {
+    if let Ok(errors) = serde_json::from_slice::<Messages<ErrorEnvelope>>(message) {
+        if errors.iter().any(|envelope| envelope.error.is_some()) {
+            tracing::warn!(
+                message = %String::from_utf8_lossy(message),
+                "discarding error response without ID",
+            );
+        }
+    }
+}
+
+impl Inbox {
+    /// Pushes a received notification to the inbox, waking its task.
+    fn push(&mut self, notification: Box<RawValue>) {
+        self.notifications.push_back(notification);
+        if let Some(waker) = self.waker.take() {
+            waker.wake();
+        }
+    }
+}
+
+/// Future returned by [`Duplex`] calls.
+pub struct Call<'a, T>
+where
+    T: DuplexTransport,
+{
+    router: &'a Duplex<T>,
+    registration: Option<Registration<'a, T>>,
+    request: Option<Vec<u8>>,
+    send: Option<Pin<Box<T::Send<'a>>>>,
+}
+
+impl<T> Future for Call<'_, T>
+where
+    T: DuplexTransport,
+{
+    type Output = Result<Vec<u8>, T::Error>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        // NOTE: Send any queued messages (such as unsubscribe requests from
+        // dropped subscriptions) before the request itself.
+        loop {
+            if let Some(send) = &mut self.send {
+                ready!(send.as_mut().poll(cx))?;
+                self.send = None;
+            }
+
+            let queued = self.router.routes().outbox.pop_front();
+            let message = match queued.or_else(|| self.request.take()) {
+                Some(message) => message,
+                None => break,
+            };
+            self.send = Some(Box::pin(self.router.transport.send(message)));
+        }
+
+        match &self.registration {
+            Some(registration) => registration.poll_response(cx),
+            None => Poll::Ready(Ok(Vec::new())),
+        }
+    }
+}
+
+/// A registration for a pending call. Dropping the registration removes the
+/// call from the router.
+struct Registration<'a, T> {
+    router: &'a Duplex<T>,
+    ids: Vec<Id>,
+}
+
+impl<'a, T> Registration<'a, T>
+where
+    T: DuplexTransport,
+{
+    /// Registers a call for the specified request IDs. Returns `None` if there
+    /// are no IDs, meaning that no response is expected.
+    fn new(router: &'a Duplex<T>, ids: Vec<Id>, subscribe: bool) -> Option<Self> {
+        let (key, batched) = ids.split_first()?;
+
+        let mut routes = router.routes();
+        routes.pending.insert(key.clone(), Slot::Waiting(None));
+        for id in batched {
+            routes.batched.insert(id.clone(), key.clone());
+        }
+        if subscribe {
+            routes.subscribing.insert(key.clone());
+        }
+        drop(routes);
+
+        Some(Registration { router, ids })
+    }
+
+    fn poll_response(&self, cx: &mut Context) -> Poll<Result<Vec<u8>, T::Error>> {
+        let mut routes = self.router.routes();
+        loop {
+            if let Some(Slot::Ready(response)) = routes.pending.get_mut(self.key()) {
+                let response = mem::take(response);
+                routes.subscribing.remove(self.key());
+                return Poll::Ready(Ok(response));
+            }
+
+            match self.router.transport.poll_receive(cx) {
+                Poll::Ready(Ok(message)) => routes.dispatch(message),
+                Poll::Ready(Err(err)) => return Poll::Ready(Err(err)),
+                Poll::Pending => {
+                    let slot = Slot::Waiting(Some(cx.waker().clone()));
+                    routes.pending.insert(self.key().clone(), slot);
+                    return Poll::Pending;
+                }
+            }
+        }
+    }
+}
+
+impl<T> Registration<'_, T> {
+    fn key(&self) -> &Id {
+        &self.ids[0]
+    }
+}
+
+impl<T> Drop for Registration<'_, T> {
+    fn drop(&mut self) {
+        let mut routes = self.router.routes();
+        let slot = routes.pending.remove(self.key());
+        for id in &self.ids[1..] {
+            routes.batched.remove(id);
+        }
+
+        // NOTE: Subscriptions are routed as soon as their response arrives, so
+        // make sure to stop routing them if the response was never read.
+        if routes.subscribing.remove(self.key()) {
+            if let Some(Slot::Ready(response)) = slot {
+                if let Ok(Response { result: Ok(id), .. }) =
+                    serde_json::from_slice::<Response<String>>(&response)
+                {
+                    routes.subscriptions.remove(&id);
+                }
+            }
+        }
+
+        // NOTE: This call may have been the one driving the transport, so wake
+        // up the remaining calls and subscriptions so that one of them can take
+        // over.
+        routes.wake_all();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::jsonrpc::Client;
+    use futures::future;
+    use serde_json::{json, Value};
+    use std::collections::VecDeque;
+    use std::convert::Infallible;
+    use std::future::Ready;
+
+    /// An in-memory duplex transport that echoes back the request parameters
+    /// as results once two requests were sent, in reverse order.
+    #[derive(Default)]
+    struct Reverse(Mutex<ReverseState>);
+
+    #[derive(Default)]
+    struct ReverseState {
+        requests: Vec<Value>,
+        inbox: VecDeque<Vec<u8>>,
+        waker: Option<Waker>,
+    }
+
+    impl DuplexTransport for Reverse {
+        type Error = Infallible;
+        type Send<'a> = Ready<Result<(), Infallible>>;
+
+        fn send(&self, message: Vec<u8>) -> Self::Send<'_> {
+            let mut state = self.0.lock().unwrap();
+            let ReverseState {
+                requests,
+                inbox,
+                waker,
+            } = &mut *state;
+
+            requests.push(serde_json::from_slice(&message).unwrap());
+            if requests.len() == 2 {
+                for request in requests.drain(..).rev() {
+                    inbox.push_back(
+                        serde_json::to_vec(&json!({
+                            "jsonrpc": "2.0",
+                            "result": request["params"][0],
+                            "id": request["id"],
+                        }))
+                        .unwrap(),
+                    );
+                }
+                inbox.push_front(br#"{"jsonrpc":"2.0","result":null,"id":1337}"#.to_vec());
+                if let Some(waker) = waker.take() {
+                    waker.wake();
+                }
+            }
+
+            std::future::ready(Ok(()))
+        }
+
+        fn poll_receive(&self, cx: &mut Context) -> Poll<Result<Vec<u8>, Infallible>> {
+            let mut state = self.0.lock().unwrap();
+            match state.inbox.pop_front() {
+                Some(message) => Poll::Ready(Ok(message)),
+                None => {
+                    state.waker = Some(cx.waker().clone());
+                    Poll::Pending
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn routes_out_of_order_responses() {
+        let client = Client::duplex(Reverse::default());
+
+        let (first, second) = futures::executor::block_on(future::join(
+            client.call::<_, String>("test_echo", ["first"]),
+            client.call::<_, String>("test_echo", ["second"]),
+        ));
+
+        assert_eq!(first.unwrap(), "first");
+        assert_eq!(second.unwrap(), "second");
+    }
+
+    /// An in-memory duplex transport where messages are received from the
+    /// node manually. Subscription requests are responded to right away.
+    #[derive(Default)]
+    struct Manual(Mutex<ManualState>);
+
+    #[derive(Default)]
+    struct ManualState {
+        requests: Vec<Value>,
+        inbox: VecDeque<Vec<u8>>,
+        waker: Option<Waker>,
+    }
+
+    impl Manual {
+        fn receive(&self, message: Value) {
+            let mut state = self.0.lock().unwrap();
+            state.inbox.push_back(serde_json::to_vec(&message).unwrap());
+            if let Some(waker) = state.waker.take() {
+                waker.wake();
+            }
+        }
+
+        fn last_id(&self) -> Value {
+            let state = self.0.lock().unwrap();
+            state.requests.last().unwrap()["id"].clone()
+        }
+    }
+
+    impl DuplexTransport for Manual {
+        type Error = Infallible;
+        type Send<'a> = Ready<Result<(), Infallible>>;
+
+        fn send(&self, message: Vec<u8>) -> Self::Send<'_> {
+            let request = serde_json::from_slice::<Value>(&message).unwrap();
+            self.0.lock().unwrap().requests.push(request.clone());
+            if request["method"] == "test_subscribe" {
+                self.receive(json!({
+                    "jsonrpc": "2.0",
+                    "result": "0x1",
+                    "id": request["id"],
+                }));
+            }
+
+            std::future::ready(Ok(()))
+        }
+
+        fn poll_receive(&self, cx: &mut Context) -> Poll<Result<Vec<u8>, Infallible>> {
+            let mut state = self.0.lock().unwrap();
+            match state.inbox.pop_front() {
+                Some(message) => Poll::Ready(Ok(message)),
+                None => {
+                    state.waker = Some(cx.waker().clone());
+                    Poll::Pending
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn streams_hand_off_driving_the_transport() {
+        use futures::executor::LocalPool;
+        use futures::task::LocalSpawnExt;
+        use futures::StreamExt;
+        use std::cell::Cell;
+        use std::rc::Rc;
+
+        let client = &*Box::leak(Box::new(Client::duplex(Manual::default())));
+        let mut subscription =
+            futures::executor::block_on(client.subscribe::<_, u32>("test", ("numbers",))).unwrap();
+
+        let mut pool = LocalPool::new();
+        let result = Rc::new(Cell::new(None));
+
+        // The call registers its waker with the transport first...
+        let call_result = result.clone();
+        pool.spawner()
+            .spawn_local(async move {
+                call_result.set(Some(client.call::<_, u32>("test_call", ()).await.unwrap()));
+            })
+            .unwrap();
+        pool.run_until_stalled();
+        let id = client.transport().transport().last_id();
+
+        // ...and the subscription replaces it, and stops being polled once
+        // it received a notification.
+        pool.spawner()
+            .spawn_local(async move {
+                subscription.next().await.unwrap().unwrap();
+                future::pending::<()>().await;
+            })
+            .unwrap();
+        pool.run_until_stalled();
+
+        let transport = client.transport().transport();
+        transport.receive(json!({
+            "jsonrpc": "2.0",
+            "method": "test_subscription",
+            "params": { "subscription": "0x1", "result": 1 },
+        }));
+        transport.receive(json!({ "jsonrpc": "2.0", "result": 42, "id": id }));
+        pool.run_until_stalled();
+
+        assert_eq!(result.get(), Some(42));
+    }
+
+    #[test]
+    fn ignores_null_id_errors() {
+        let client = Client::duplex(Manual::default());
+
+        let (first, second, _) = futures::executor::block_on(future::join3(
+            client.call::<_, u32>("test_call", ()),
+            client.call::<_, u32>("test_call", ()),
+            async {
+                let node = client.transport().transport();
+                node.receive(json!({
+                    "jsonrpc": "2.0",
+                    "error": { "code": -32700, "message": "parse error" },
+                    "id": null,
+                }));
+                node.receive(json!({ "jsonrpc": "2.0", "result": 2, "id": 1 }));
+                node.receive(json!({ "jsonrpc": "2.0", "result": 1, "id": 0 }));
+            },
+        ));
+
+        assert_eq!(first.unwrap(), 1);
+        assert_eq!(second.unwrap(), 2);
+    }
+}
@@ -0,0 +1,160 @@
+//! Module containing JSON RPC request IDs and the strategies for generating
+//! them.
+
+use crate::util::random;
+use serde::{Deserialize, Serialize};
+use serde_json::Number;
+use std::fmt::{self, Debug, Display, Formatter};
+use std::sync::atomic::{AtomicU32, Ordering};
+
+/// Request and response ID.
+///
+/// Responses with a `null` ID, which servers use when the request ID could not
+/// be determined, are represented with `Option<Id>`.
+#[derive(Clone, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+#[serde(untagged)]
+pub enum Id {
+    /// A numerical ID.
+    Number(u64),
+    /// A string ID.
+    String(String),
+    /// A numerical ID that is not an unsigned integer, such as `-1` or `1.0`.
+    /// These are never generated by the client, but may be returned by
+    /// misbehaving servers and proxies, in which case they are reported as ID
+    /// mismatches.
+    Other(Number),
+}
+
+impl Display for Id {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Id::Number(id) => write!(f, "{}", id),
+            Id::String(id) => write!(f, "{:?}", id),
+            Id::Other(id) => write!(f, "{}", id),
+        }
+    }
+}
+
+/// A strategy for generating request IDs. IDs should be unique for the lifetime
+/// of a client, so that responses can be matched to their requests.
+pub trait IdGenerator: Debug + Send + Sync {
+    /// Returns the ID for the next request.
+    fn next_id(&self) -> Id;
+}
+
+/// Generates sequential numerical IDs starting from 0. This is the default ID
+/// generator.
+///
+/// Note that the sequence is `u32`. This is so IDs always fit in a `f64` and
+/// obey the "SHOULD NOT have fractional parts" rule from the specification.
+#[derive(Debug, Default)]
+pub struct NumericIds(AtomicU32);
+
+impl IdGenerator for NumericIds {
+    fn next_id(&self) -> Id {
+        Id::Number(self.0.fetch_add(1, Ordering::SeqCst).into())
+    }
+}
+
+/// Generates sequential string IDs with a prefix, such as `"worker-1:0"`,
+/// `"worker-1:1"`, etc. This can be used for identifying the originating
+/// process of requests in proxy and node logs.
+#[derive(Debug)]
+pub struct StringIds {
+    prefix: String,
+    current: AtomicU32,
+}
+
+impl StringIds {
+    /// Creates a new string ID generator with the specified prefix.
+    pub fn new(prefix: impl Into<String>) -> Self {
+        StringIds {
+            prefix: prefix.into(),
+            current: Default::default(),
+        }
+    }
+}
+
+impl IdGenerator for StringIds {
+    fn next_id(&self) -> Id {
+        let current = self.current.fetch_add(1, Ordering::SeqCst);
+        Id::String(format!("{}{}", self.prefix, current))
+    }
+}
+
+/// Generates random UUID (version 4) string IDs. This can be used for
+/// correlating requests across load balancers and logs of multiple clients.
+///
+/// Note that the random IDs are not generated with a cryptographically secure
+/// random number generator.
+#[derive(Debug, Default)]
+pub struct UuidIds;
+
+impl IdGenerator for UuidIds {
+    fn next_id(&self) -> Id {
+        let bits = (u128::from(random()) << 64) | u128::from(random());
+        let bits = (bits & !(0xf << 76) & !(0x3 << 62)) | (0x4 << 76) | (0x2 << 62);
+
+        let uuid = format!("{:032x}", bits);
+        Id::String(format!(
+            "{}-{}-{}-{}-{}",
+            &uuid[..8],
+            &uuid[8..12],
+            &uuid[12..16],
+            &uuid[16..20],
+            &uuid[20..],
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn serialization() {
+        assert_eq!(serde_json::to_value(Id::Number(42)).unwrap(), json!(42));
+        assert_eq!(
+            serde_json::to_value(Id::String("a".to_owned())).unwrap(),
+            json!("a"),
+        );
+        assert_eq!(
+            serde_json::from_value::<Option<Id>>(json!("a")).unwrap(),
+            Some(Id::String("a".to_owned())),
+        );
+        assert_eq!(
+            serde_json::from_value::<Option<Id>>(json!(null)).unwrap(),
+            None,
+        );
+
+        for id in [json!(-1), json!(1.0)] {
+            let parsed = serde_json::from_value::<Id>(id.clone()).unwrap();
+            assert!(matches!(parsed, Id::Other(_)));
+            assert_ne!(parsed, Id::Number(1));
+            assert_eq!(serde_json::to_value(parsed).unwrap(), id);
+        }
+    }
+
+    #[test]
+    fn generators() {
+        let ids = NumericIds::default();
+        assert_eq!(ids.next_id(), Id::Number(0));
+        assert_eq!(ids.next_id(), Id::Number(1));
+
+        let ids = StringIds::new("worker:");
+        assert_eq!(ids.next_id(), Id::String("worker:0".to_owned()));
+        assert_eq!(ids.next_id(), Id::String("worker:1".to_owned()));
+
+        let (first, second) = (UuidIds.next_id(), UuidIds.next_id());
+        assert_ne!(first, second);
+        match first {
+            Id::String(uuid) => {
+                assert_eq!(uuid.len(), 36);
+                assert_eq!(&uuid[14..15], "4");
+                assert!(matches!(&uuid[19..20], "8" | "9" | "a" | "b"));
+            }
+            _ => panic!("expected string ID"),
+        }
+    }
+}
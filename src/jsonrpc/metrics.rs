@@ -0,0 +1,152 @@
+//! Module containing the hook for recording metrics of JSON RPC calls.
+//!
+//! A [`Metrics`] implementation can be set on a client with
+//! [`Client::with_metrics`](super::Client::with_metrics), and is called once
+//! for every completed call, including calls sent as part of a batch,
+//! subscribe and unsubscribe calls, and notifications. A Prometheus
+//! implementation is included with the `prometheus` feature.
+
+#[cfg(feature = "prometheus")]
+mod prometheus;
+
+#[cfg(feature = "prometheus")]
+pub use self::prometheus::PrometheusMetrics;
+
+use super::client::ClientError;
+use super::data::ErrorCode;
+use std::error::Error;
+use std::fmt::Debug;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// A hook for recording metrics of the calls made by a client.
+pub trait Metrics: Debug + Send + Sync {
+    /// Records a completed call to the specified method, with its latency and
+    /// the error if it failed.
+    fn record_call(&self, method: &str, latency: Duration, error: Option<CallError>);
+}
+
+impl<M> Metrics for Arc<M>
+where
+    M: Metrics + ?Sized,
+{
+    fn record_call(&self, method: &str, latency: Duration, error: Option<CallError>) {
+        (**self).record_call(method, latency, error)
+    }
+}
+
+/// The class of error of a failed call, corresponding to the [`ClientError`]
+/// variants.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum CallError {
+    /// An error sending or receiving data over a transport.
+    Transport,
+    /// An error serializing or deserializing the JSON RPC message objects.
+    Json,
+    /// An RPC error with the specified code.
+    Rpc(ErrorCode),
+    /// No response was received for a batched call.
+    NoResponse,
+    /// The response ID does not match the request ID.
+    IdMismatch,
+}
+
+impl CallError {
+    /// Returns the class of a client error.
+    pub fn new<E>(err: &ClientError<E>) -> Self
+    where
+        E: Error,
+    {
+        match err {
+            ClientError::Transport(_) => CallError::Transport,
+            ClientError::Json(_) => CallError::Json,
+            ClientError::Rpc(err) => CallError::Rpc(err.code),
+            ClientError::NoResponse => CallError::NoResponse,
+            ClientError::IdMismatch { .. } => CallError::IdMismatch,
+        }
+    }
+
+    /// Returns a short name for the error class, suitable for use as a metric
+    /// label.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            CallError::Transport => "transport",
+            CallError::Json => "json",
+            CallError::Rpc(_) => "rpc",
+            CallError::NoResponse => "no_response",
+            CallError::IdMismatch => "id_mismatch",
+        }
+    }
+
+    /// Returns the RPC error code for RPC errors.
+    pub fn code(&self) -> Option<ErrorCode> {
+        match self {
+            CallError::Rpc(code) => Some(*code),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::jsonrpc::server::Server;
+    use crate::jsonrpc::{Client, RpcError};
+    use futures::executor::block_on;
+    use std::sync::Mutex;
+
+    /// Metrics that keep a log of the recorded calls.
+    #[derive(Debug, Default)]
+    struct Log(Mutex<Vec<(String, Option<CallError>)>>);
+
+    impl Metrics for Log {
+        fn record_call(&self, method: &str, _: Duration, error: Option<CallError>) {
+            self.0.lock().unwrap().push((method.to_owned(), error));
+        }
+    }
+
+    #[test]
+    fn records_calls_and_batched_calls() {
+        let log = Arc::new(Log::default());
+        let client = Client::new(
+            Server::new()
+                .with_method("eth_chainId", |(): ()| async { Ok("0x1") })
+                .with_method("eth_call", |(): ()| async {
+                    Err::<(), _>(RpcError::new(3, "execution reverted"))
+                }),
+        )
+        .with_metrics(log.clone());
+
+        block_on(async {
+            client.call::<_, String>("eth_chainId", ()).await.unwrap();
+            client.call::<_, u64>("eth_chainId", ()).await.unwrap_err();
+            client.call_raw("eth_call", ().into()).await.unwrap_err();
+
+            let batch = client.batch();
+            let calls = (
+                batch.call::<_, String>("eth_chainId", ()),
+                batch.call::<_, ()>("eth_call", ()),
+            );
+            batch.send().await.unwrap();
+            calls.0.await.unwrap();
+            calls.1.await.unwrap_err();
+        });
+
+        assert_eq!(
+            *log.0.lock().unwrap(),
+            [
+                ("eth_chainId".to_owned(), None),
+                ("eth_chainId".to_owned(), Some(CallError::Json)),
+                (
+                    "eth_call".to_owned(),
+                    Some(CallError::Rpc(ErrorCode::Other(3)))
+                ),
+                ("eth_chainId".to_owned(), None),
+                (
+                    "eth_call".to_owned(),
+                    Some(CallError::Rpc(ErrorCode::Other(3)))
+                ),
+            ],
+        );
+    }
+}
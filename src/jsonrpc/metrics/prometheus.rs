@@ -0,0 +1,118 @@
+//! Module containing a Prometheus implementation of the metrics hook.
+
+use super::{CallError, Metrics};
+use ::prometheus::{HistogramOpts, HistogramVec, IntCounterVec, Opts, Registry};
+use std::time::Duration;
+
+/// Call metrics recorded in a Prometheus registry.
+///
+/// The following metrics are registered:
+/// - `ethrs_rpc_calls_total` counter of calls by `method`.
+/// - `ethrs_rpc_errors_total` counter of failed calls by `method`, error
+///   `kind` and RPC error `code` (empty for errors other than RPC errors).
+/// - `ethrs_rpc_call_duration_seconds` histogram of call latencies by
+///   `method`.
+#[derive(Clone, Debug)]
+pub struct PrometheusMetrics {
+    calls: IntCounterVec,
+    errors: IntCounterVec,
+    latency: HistogramVec,
+}
+
+impl PrometheusMetrics {
+    /// Creates new call metrics, registering them with the specified registry.
+    pub fn new(registry: &Registry) -> ::prometheus::Result<Self> {
+        let calls = IntCounterVec::new(
+            Opts::new("ethrs_rpc_calls_total", "Number of JSON RPC calls."),
+            &["method"],
+        )?;
+        let errors = IntCounterVec::new(
+            Opts::new("ethrs_rpc_errors_total", "Number of failed JSON RPC calls."),
+            &["method", "kind", "code"],
+        )?;
+        let latency = HistogramVec::new(
+            HistogramOpts::new(
+                "ethrs_rpc_call_duration_seconds",
+                "Latency of JSON RPC calls in seconds.",
+            ),
+            &["method"],
+        )?;
+
+        registry.register(Box::new(calls.clone()))?;
+        registry.register(Box::new(errors.clone()))?;
+        registry.register(Box::new(latency.clone()))?;
+
+        Ok(PrometheusMetrics {
+            calls,
+            errors,
+            latency,
+        })
+    }
+}
+
+impl Metrics for PrometheusMetrics {
+    fn record_call(&self, method: &str, latency: Duration, error: Option<CallError>) {
+        self.calls.with_label_values(&[method]).inc();
+        self.latency
+            .with_label_values(&[method])
+            .observe(latency.as_secs_f64());
+        if let Some(error) = error {
+            let code = error
+                .code()
+                .map(|code| i32::from(code).to_string())
+                .unwrap_or_default();
+            self.errors
+                .with_label_values(&[method, error.kind(), &code])
+                .inc();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::jsonrpc::server::Server;
+    use crate::jsonrpc::{Client, ErrorCode, RpcError};
+    use ::prometheus::{Encoder, TextEncoder};
+    use futures::executor::block_on;
+
+    #[test]
+    fn records_call_metrics() {
+        let registry = Registry::new();
+        let client = Client::new(
+            Server::new()
+                .with_method("eth_chainId", |(): ()| async { Ok("0x1") })
+                .with_method("eth_call", |(): ()| async {
+                    Err::<(), _>(RpcError::new(ErrorCode::ServerError(-32000), "reverted"))
+                }),
+        )
+        .with_metrics(PrometheusMetrics::new(&registry).unwrap());
+
+        block_on(async {
+            client.call::<_, String>("eth_chainId", ()).await.unwrap();
+            client.call::<_, ()>("eth_call", ()).await.unwrap_err();
+            client.call::<_, ()>("eth_foo", ()).await.unwrap_err();
+
+            let batch = client.batch();
+            let chain_id = batch.call::<_, String>("eth_chainId", ());
+            batch.send().await.unwrap();
+            chain_id.await.unwrap();
+        });
+
+        let mut buffer = Vec::new();
+        TextEncoder::new()
+            .encode(&registry.gather(), &mut buffer)
+            .unwrap();
+        let metrics = String::from_utf8(buffer).unwrap();
+
+        for line in [
+            r#"ethrs_rpc_calls_total{method="eth_chainId"} 2"#,
+            r#"ethrs_rpc_calls_total{method="eth_call"} 1"#,
+            r#"ethrs_rpc_errors_total{code="-32000",kind="rpc",method="eth_call"} 1"#,
+            r#"ethrs_rpc_errors_total{code="-32601",kind="rpc",method="eth_foo"} 1"#,
+            r#"ethrs_rpc_call_duration_seconds_count{method="eth_chainId"} 2"#,
+        ] {
+            assert!(metrics.contains(line), "missing {} in:\n{}", line, metrics);
+        }
+    }
+}
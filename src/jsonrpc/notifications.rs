@@ -0,0 +1,208 @@
+//! Module containing the stream of server-initiated notifications received
+//! over duplex transports.
+
+use super::client::{Client, ClientError};
+use super::duplex::Duplex;
+use crate::transport::DuplexTransport;
+use futures_core::Stream;
+use serde::de::DeserializeOwned;
+use std::fmt::{self, Debug, Formatter};
+use std::marker::PhantomData;
+use std::pin::Pin;
+use std::task::{ready, Context, Poll};
+
+/// A stream of the parameters of inbound notifications with a specific
+/// method.
+///
+/// Notifications for active [`Subscription`](super::Subscription)s are routed
+/// to their subscription streams instead. Notifications are only received
+/// while the stream exists, and dropping it stops routing notifications to it.
+pub struct Notifications<'a, T, P>
+where
+    T: DuplexTransport,
+{
+    client: &'a Client<Duplex<T>>,
+    listener: u64,
+    send: Option<Pin<Box<T::Send<'a>>>>,
+    done: bool,
+    _marker: PhantomData<fn() -> P>,
+}
+
+impl<'a, T, P> Notifications<'a, T, P>
+where
+    T: DuplexTransport,
+{
+    /// Starts listening for notifications with the specified method.
+    pub(super) fn new(client: &'a Client<Duplex<T>>, method: &str) -> Self {
+        Notifications {
+            client,
+            listener: client.transport().listen(method),
+            send: None,
+            done: false,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<T, P> Stream for Notifications<'_, T, P>
+where
+    T: DuplexTransport,
+    P: DeserializeOwned,
+{
+    type Item = Result<P, ClientError<T::Error>>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Self::Item>> {
+        if self.done {
+            return Poll::Ready(None);
+        }
+
+        let this = &mut *self;
+        let notification =
+            ready!(this
+                .client
+                .transport()
+                .poll_listener(this.listener, &mut this.send, cx));
+        Poll::Ready(match notification {
+            Some(Ok(params)) => Some(serde_json::from_str(params.get()).map_err(From::from)),
+            Some(Err(err)) => {
+                // NOTE: Transport errors are not recoverable, so end the stream
+                // after reporting it.
+                self.done = true;
+                Some(Err(ClientError::Transport(err)))
+            }
+            None => {
+                self.done = true;
+                None
+            }
+        })
+    }
+}
+
+impl<T, P> Debug for Notifications<'_, T, P>
+where
+    T: DuplexTransport,
+{
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        f.debug_struct("Notifications")
+            .field("listener", &self.listener)
+            .field("done", &self.done)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<T, P> Drop for Notifications<'_, T, P>
+where
+    T: DuplexTransport,
+{
+    fn drop(&mut self) {
+        self.client.transport().unlisten(self.listener);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::executor::block_on;
+    use futures::StreamExt;
+    use serde_json::{json, Value};
+    use std::collections::VecDeque;
+    use std::convert::Infallible;
+    use std::future::{self, Ready};
+    use std::sync::Mutex;
+    use std::task::Waker;
+
+    /// An in-memory duplex transport acting as a node that responds to
+    /// notifications with notifications of its own.
+    #[derive(Default)]
+    struct Node(Mutex<NodeState>);
+
+    #[derive(Default)]
+    struct NodeState {
+        sent: Vec<Value>,
+        inbox: VecDeque<Value>,
+        waker: Option<Waker>,
+    }
+
+    impl DuplexTransport for Node {
+        type Error = Infallible;
+        type Send<'a> = Ready<Result<(), Infallible>>;
+
+        fn send(&self, message: Vec<u8>) -> Self::Send<'_> {
+            let notification = serde_json::from_slice::<Value>(&message).unwrap();
+
+            let mut state = self.0.lock().unwrap();
+            for (method, params) in [
+                ("test_pong", notification["params"].clone()),
+                ("test_other", json!(null)),
+                ("test_pong", json!(null)),
+            ] {
+                state.inbox.push_back(json!({
+                    "jsonrpc": "2.0",
+                    "method": method,
+                    "params": params,
+                }));
+            }
+            state.inbox.push_back(json!({
+                "jsonrpc": "2.0",
+                "method": "test_pong",
+            }));
+            state.sent.push(notification);
+            if let Some(waker) = state.waker.take() {
+                waker.wake();
+            }
+
+            future::ready(Ok(()))
+        }
+
+        fn poll_receive(&self, cx: &mut Context) -> Poll<Result<Vec<u8>, Infallible>> {
+            let mut state = self.0.lock().unwrap();
+            match state.inbox.pop_front() {
+                Some(message) => Poll::Ready(Ok(serde_json::to_vec(&message).unwrap())),
+                None => {
+                    state.waker = Some(cx.waker().clone());
+                    Poll::Pending
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn sends_and_receives_notifications() {
+        let client = Client::duplex(Node::default());
+
+        block_on(async {
+            let mut pongs = client.notifications::<Option<[u32; 1]>>("test_pong");
+            let mut others = client.notifications::<Value>("test_other");
+
+            client.notify("test_ping", [42]).await.unwrap();
+
+            assert_eq!(pongs.next().await.unwrap().unwrap(), Some([42]));
+            assert_eq!(pongs.next().await.unwrap().unwrap(), None);
+            assert_eq!(pongs.next().await.unwrap().unwrap(), None);
+            assert_eq!(others.next().await.unwrap().unwrap(), json!(null));
+        });
+
+        assert_eq!(
+            client.transport().transport().0.lock().unwrap().sent,
+            [json!({
+                "jsonrpc": "2.0",
+                "method": "test_ping",
+                "params": [42],
+            })],
+        );
+    }
+
+    #[test]
+    fn drops_listeners() {
+        let client = Client::duplex(Node::default());
+
+        block_on(async {
+            let mut pongs = client.notifications::<Value>("test_pong");
+            let others = client.notifications::<Value>("test_other");
+            drop(others);
+
+            client.notify("test_ping", ()).await.unwrap();
+            pongs.next().await.unwrap().unwrap();
+        });
+    }
+}
@@ -0,0 +1,364 @@
+//! Module containing an embeddable JSON RPC server.
+//!
+//! The server routes requests to typed async handlers registered per method.
+//! It is transport agnostic: serialized requests are handled with
+//! [`Server::handle`], and the server can be used in-process as a
+//! [`Transport`] for a [`Client`](super::Client), for example as a local
+//! stand-in for a node in tests.
+//!
+//! ```
+//! # use ethrs::jsonrpc::{Client, ErrorCode, RpcError};
+//! # use ethrs::jsonrpc::server::Server;
+//! # futures::executor::block_on(async {
+//! let server = Server::new()
+//!     .with_method("eth_chainId", |_: [(); 0]| async { Ok("0x1") })
+//!     .with_method("test_div", |(a, b): (u64, u64)| async move {
+//!         a.checked_div(b)
+//!             .ok_or_else(|| RpcError::new(ErrorCode::InvalidParams, "division by zero"))
+//!     });
+//!
+//! let client = Client::new(server);
+//! assert_eq!(client.call::<_, String>("eth_chainId", [(); 0]).await.unwrap(), "0x1");
+//! assert_eq!(client.call::<_, u64>("test_div", (6, 3)).await.unwrap(), 2);
+//! # });
+//! ```
+
+use super::data::{Error, ErrorCode, Messages, RawRequest, Response, Version};
+use crate::transport::Transport;
+use serde::de::DeserializeOwned;
+use serde::ser::Serialize;
+use serde_json::value::{RawValue, Value};
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::fmt::{self, Debug, Formatter};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+
+/// A boxed future returned by type erased method handlers.
+type BoxFuture<T> = Pin<Box<dyn Future<Output = T> + Send>>;
+
+/// A type erased method handler.
+type Handler = Arc<dyn Fn(&RawValue) -> BoxFuture<Result<Box<RawValue>, Error>> + Send + Sync>;
+
+/// A JSON RPC server with typed async method handlers.
+#[derive(Clone, Default)]
+pub struct Server {
+    methods: HashMap<String, Handler>,
+}
+
+impl Server {
+    /// Creates a new server without any methods.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers a handler for the specified method, replacing any existing
+    /// handler for it.
+    ///
+    /// Request parameters are deserialized into the handler's parameter type,
+    /// responding with an invalid parameters error if they don't match. Missing
+    /// parameters are deserialized from `null`, and empty parameters are also
+    /// deserialized from `null` if they don't match, so that `()` can be used
+    /// for methods without parameters.
+    pub fn with_method<P, R, F, Fut>(mut self, method: impl Into<String>, handler: F) -> Self
+    where
+        P: DeserializeOwned,
+        R: Serialize,
+        F: Fn(P) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<R, Error>> + Send + 'static,
+    {
+        let handler = Arc::new(move |params: &RawValue| -> BoxFuture<_> {
+            let params = match decode_params::<P>(params) {
+                Ok(params) => params,
+                Err(err) => {
+                    let err = Error::new(ErrorCode::InvalidParams, err.to_string());
+                    return Box::pin(async { Err(err) });
+                }
+            };
+
+            let result = handler(params);
+            Box::pin(async move {
+                let result = result.await?;
+                serde_json::value::to_raw_value(&result)
+                    .map_err(|err| Error::new(ErrorCode::InternalError, err.to_string()))
+            })
+        });
+        self.methods.insert(method.into(), handler);
+        self
+    }
+
+    /// Returns `true` if the server has a handler for the specified method.
+    pub fn has_method(&self, method: &str) -> bool {
+        self.methods.contains_key(method)
+    }
+
+    /// Handles a serialized single or batch request, returning the serialized
+    /// response. The response is empty if the request only contained
+    /// notifications.
+    ///
+    /// Calls in batch requests are handled in order.
+    pub async fn handle(&self, request: &[u8]) -> Vec<u8> {
+        // NOTE: Untagged enums can't be used for deserializing raw values, so
+        // check whether the request is a batch manually.
+        let requests = serde_json::from_slice::<&RawValue>(request).and_then(|request| {
+            if request.get().starts_with('[') {
+                serde_json::from_str(request.get()).map(Messages::Batch)
+            } else {
+                Ok(Messages::Single(request))
+            }
+        });
+        let requests = match requests {
+            Ok(requests) => requests,
+            Err(err) => return serialize(&error_response(ErrorCode::ParseError, err)),
+        };
+
+        match requests {
+            Messages::Single(request) => match self.handle_request(request).await {
+                Some(response) => serialize(&response),
+                None => Vec::new(),
+            },
+            Messages::Batch(requests) if requests.is_empty() => serialize(&error_response(
+                ErrorCode::InvalidRequest,
+                "empty batch request",
+            )),
+            Messages::Batch(requests) => {
+                let mut responses = Vec::new();
+                for request in requests {
+                    if let Some(response) = self.handle_request(request).await {
+                        responses.push(response);
+                    }
+                }
+
+                if responses.is_empty() {
+                    Vec::new()
+                } else {
+                    serialize(&responses)
+                }
+            }
+        }
+    }
+
+    /// Handles a single request object, returning its response or `None` for
+    /// notifications.
+    async fn handle_request(&self, request: &RawValue) -> Option<Response<Box<RawValue>>> {
+        let request = match serde_json::from_str::<RawRequest>(request.get()) {
+            Ok(request) => request,
+            Err(err) => return Some(error_response(ErrorCode::InvalidRequest, err)),
+        };
+
+        let result = match self.methods.get(&request.method) {
+            Some(handler) => match &request.params {
+                Some(params) => handler(params).await,
+                None => handler(null()).await,
+            },
+            None => Err(Error::new(
+                ErrorCode::MethodNotFound,
+                format!("method {} not found", request.method),
+            )),
+        };
+
+        Some(Response {
+            jsonrpc: Version::V2,
+            result,
+            id: request.id?,
+        })
+    }
+}
+
+impl Debug for Server {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        f.debug_struct("Server")
+            .field("methods", &self.methods.keys().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+impl Transport for Server {
+    type Error = Infallible;
+    type Call<'a> = Pin<Box<dyn Future<Output = Result<Vec<u8>, Infallible>> + Send + 'a>>;
+
+    fn call<'a>(&'a self, request: &'a [u8]) -> Self::Call<'a> {
+        Box::pin(async move { Ok(self.handle(request).await) })
+    }
+}
+
+/// Decodes request parameters, falling back to `null` for empty parameters.
+fn decode_params<P>(params: &RawValue) -> Result<P, serde_json::Error>
+where
+    P: DeserializeOwned,
+{
+    serde_json::from_str(params.get()).or_else(|err| {
+        match serde_json::from_str::<Value>(params.get()) {
+            Ok(Value::Array(params)) if params.is_empty() => serde_json::from_str(null().get()),
+            Ok(Value::Object(params)) if params.is_empty() => serde_json::from_str(null().get()),
+            _ => Err(err),
+        }
+    })
+}
+
+/// Returns a raw `null` value.
+fn null() -> &'static RawValue {
+    serde_json::from_str("null").expect("valid JSON")
+}
+
+/// Creates an error response without an ID, for requests whose ID could not be
+/// determined.
+fn error_response(code: ErrorCode, message: impl ToString) -> Response<Box<RawValue>> {
+    Response {
+        jsonrpc: Version::V2,
+        result: Err(Error::new(code, message.to_string())),
+        id: None,
+    }
+}
+
+fn serialize(response: &impl Serialize) -> Vec<u8> {
+    serde_json::to_vec(response).expect("response serialization is infallible")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::jsonrpc::{Client, ClientError};
+    use futures::executor::block_on;
+    use serde_json::json;
+
+    fn server() -> Server {
+        Server::new()
+            .with_method("test_echo", |params: Value| async { Ok(params) })
+            .with_method("test_unit", |(): ()| async { Ok(true) })
+            .with_method("test_add", |(a, b): (u64, u64)| async move { Ok(a + b) })
+            .with_method("test_fail", |(): ()| async {
+                Err::<(), _>(Error::new(42, "failed").with_data(json!("0x")))
+            })
+    }
+
+    fn handle(server: &Server, request: Value) -> Value {
+        let response = block_on(server.handle(&serde_json::to_vec(&request).unwrap()));
+        if response.is_empty() {
+            Value::Null
+        } else {
+            serde_json::from_slice(&response).unwrap()
+        }
+    }
+
+    #[test]
+    fn single_requests() {
+        let server = server();
+        assert_eq!(
+            handle(
+                &server,
+                json!({ "jsonrpc": "2.0", "method": "test_add", "params": [1, 2], "id": 1 }),
+            ),
+            json!({ "jsonrpc": "2.0", "result": 3, "id": 1 }),
+        );
+        assert_eq!(
+            handle(
+                &server,
+                json!({ "jsonrpc": "2.0", "method": "test_unit", "id": "a" }),
+            ),
+            json!({ "jsonrpc": "2.0", "result": true, "id": "a" }),
+        );
+        assert_eq!(
+            handle(
+                &server,
+                json!({ "jsonrpc": "2.0", "method": "test_unit", "params": [], "id": null }),
+            ),
+            json!({ "jsonrpc": "2.0", "result": true, "id": null }),
+        );
+    }
+
+    #[test]
+    fn notifications() {
+        let server = server();
+        assert_eq!(
+            handle(&server, json!({ "jsonrpc": "2.0", "method": "test_unit" }),),
+            Value::Null,
+        );
+        assert_eq!(
+            handle(
+                &server,
+                json!([
+                    { "jsonrpc": "2.0", "method": "test_unit" },
+                    { "jsonrpc": "2.0", "method": "test_missing" },
+                ]),
+            ),
+            Value::Null,
+        );
+    }
+
+    #[test]
+    fn batch_requests() {
+        let server = server();
+        assert_eq!(
+            handle(
+                &server,
+                json!([
+                    { "jsonrpc": "2.0", "method": "test_echo", "params": ["a"], "id": 1 },
+                    { "jsonrpc": "2.0", "method": "test_unit" },
+                    { "jsonrpc": "2.0", "method": "test_add", "params": [1], "id": 2 },
+                    42,
+                ]),
+            ),
+            json!([
+                { "jsonrpc": "2.0", "result": ["a"], "id": 1 },
+                {
+                    "jsonrpc": "2.0",
+                    "error": {
+                        "code": -32602,
+                        "message": "invalid length 1, expected a tuple of size 2 at line 1 column 3",
+                    },
+                    "id": 2,
+                },
+                {
+                    "jsonrpc": "2.0",
+                    "error": {
+                        "code": -32600,
+                        "message": "invalid type: integer `42`, expected struct RawRequest at line 1 column 2",
+                    },
+                    "id": null,
+                },
+            ]),
+        );
+    }
+
+    #[test]
+    fn error_responses() {
+        let server = server();
+        let error = |request: &[u8]| {
+            let response = block_on(server.handle(request));
+            serde_json::from_slice::<Value>(&response).unwrap()["error"].clone()
+        };
+
+        assert_eq!(error(b"{")["code"], -32700);
+        assert_eq!(error(b"[]")["code"], -32600);
+        assert_eq!(error(br#"{"method":"test_unit","id":1}"#)["code"], -32600);
+        assert_eq!(
+            error(br#"{"jsonrpc":"2.0","method":"test_missing","id":1}"#),
+            json!({ "code": -32601, "message": "method test_missing not found" }),
+        );
+        assert_eq!(
+            error(br#"{"jsonrpc":"2.0","method":"test_fail","id":1}"#),
+            json!({ "code": 42, "message": "failed", "data": "0x" }),
+        );
+    }
+
+    #[test]
+    fn in_process_transport() {
+        let client = Client::new(server());
+        block_on(async {
+            assert_eq!(client.call::<_, u64>("test_add", (1, 2)).await.unwrap(), 3);
+            client.notify("test_unit", ()).await.unwrap();
+
+            let batch = client.batch();
+            let echo = batch.call::<_, Vec<String>>("test_echo", ["a"]);
+            let fail = batch.call::<_, ()>("test_fail", ());
+            batch.send().await.unwrap();
+            assert_eq!(echo.await.unwrap(), ["a"]);
+            match fail.await {
+                Err(ClientError::Rpc(err)) => assert_eq!(err.code, ErrorCode::Other(42)),
+                result => panic!("unexpected result {:?}", result),
+            }
+        });
+    }
+}
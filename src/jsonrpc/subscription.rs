@@ -0,0 +1,362 @@
+//! Module containing the JSON RPC publish/subscribe implementation for duplex
+//! transports.
+//!
+//! This follows the conventions used by Ethereum nodes where a subscription is
+//! created with a `<namespace>_subscribe` call, notifications are received as
+//! `<namespace>_subscription` messages that include the subscription ID, and
+//! the subscription is removed with a `<namespace>_unsubscribe` call.
+
+use super::client::{Client, ClientError};
+use super::data::{Request, Version};
+use super::duplex::Duplex;
+use super::metrics::CallError;
+use crate::transport::DuplexTransport;
+use futures_core::Stream;
+use std::fmt::{self, Debug, Formatter};
+use std::pin::Pin;
+use std::task::{ready, Context, Poll};
+use web_time::Instant;
+
+/// A stream of subscription notifications.
+///
+/// Dropping the subscription stops routing its notifications and queues an
+/// unsubscribe request. The request is sent the next time the client uses the
+/// transport, either for a call or for polling any subscription or
+/// notification stream. Use [`Subscription::unsubscribe`] to unsubscribe right
+/// away instead.
+pub struct Subscription<'a, T, R>
+where
+    T: DuplexTransport,
+{
+    client: &'a Client<Duplex<T>>,
+    namespace: String,
+    id: String,
+    decode: fn(&str) -> Result<R, serde_json::Error>,
+    send: Option<Pin<Box<T::Send<'a>>>>,
+    done: bool,
+}
+
+impl<'a, T, R> Subscription<'a, T, R>
+where
+    T: DuplexTransport,
+{
+    /// Creates a new subscription for a subscription ID returned by the node.
+    pub(super) fn new(
+        client: &'a Client<Duplex<T>>,
+        namespace: &str,
+        id: String,
+        decode: fn(&str) -> Result<R, serde_json::Error>,
+    ) -> Self {
+        Subscription {
+            client,
+            namespace: namespace.to_owned(),
+            id,
+            decode,
+            send: None,
+            done: false,
+        }
+    }
+
+    /// Returns the subscription ID.
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    fn unsubscribe_method(&self) -> String {
+        format!("{}_unsubscribe", self.namespace)
+    }
+
+    /// Unsubscribes from the node, returning whether the subscription was
+    /// successfully removed.
+    pub async fn unsubscribe(mut self) -> Result<bool, ClientError<T::Error>> {
+        self.done = true;
+        self.client.transport().unsubscribe(&self.id);
+        self.client
+            .call(&self.unsubscribe_method(), [&self.id])
+            .await
+    }
+}
+
+impl<T, R> Stream for Subscription<'_, T, R>
+where
+    T: DuplexTransport,
+{
+    type Item = Result<R, ClientError<T::Error>>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Self::Item>> {
+        if self.done {
+            return Poll::Ready(None);
+        }
+
+        let this = &mut *self;
+        let notification =
+            ready!(this
+                .client
+                .transport()
+                .poll_notification(&this.id, &mut this.send, cx));
+        Poll::Ready(match notification {
+            Some(Ok(notification)) => Some((self.decode)(notification.get()).map_err(From::from)),
+            Some(Err(err)) => {
+                // NOTE: Transport errors are not recoverable, so end the stream
+                // after reporting it.
+                self.done = true;
+                Some(Err(ClientError::Transport(err)))
+            }
+            None => {
+                self.done = true;
+                None
+            }
+        })
+    }
+}
+
+impl<T, R> Debug for Subscription<'_, T, R>
+where
+    T: DuplexTransport,
+{
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        f.debug_struct("Subscription")
+            .field("namespace", &self.namespace)
+            .field("id", &self.id)
+            .field("done", &self.done)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<T, R> Drop for Subscription<'_, T, R>
+where
+    T: DuplexTransport,
+{
+    fn drop(&mut self) {
+        let duplex = self.client.transport();
+        if !duplex.unsubscribe(&self.id) {
+            return;
+        }
+
+        let start = Instant::now();
+        let method = self.unsubscribe_method();
+        let request = serde_json::to_vec(&Request {
+            jsonrpc: Version::V2,
+            method: &method,
+            params: [&self.id],
+            id: self.client.next_id(),
+        });
+
+        // NOTE: The response to the queued request is never read, so it is
+        // recorded once queued, without its result.
+        let error = match request {
+            Ok(request) => {
+                duplex.queue(request);
+                None
+            }
+            Err(_) => Some(CallError::Json),
+        };
+        self.client.record_call(&method, start, error);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::jsonrpc::metrics::Metrics;
+    use futures::executor::block_on;
+    use futures::{FutureExt, StreamExt};
+    use serde_json::{json, Value};
+    use std::collections::VecDeque;
+    use std::convert::Infallible;
+    use std::future::{self, Ready};
+    use std::sync::{Arc, Mutex};
+    use std::task::Waker;
+    use std::time::Duration;
+
+    /// An in-memory duplex transport acting as a node that sends notifications
+    /// right after the subscription response, including one for a different
+    /// subscription.
+    #[derive(Default)]
+    struct Node(Mutex<NodeState>);
+
+    #[derive(Default)]
+    struct NodeState {
+        methods: Vec<String>,
+        inbox: VecDeque<Value>,
+        waker: Option<Waker>,
+    }
+
+    impl Node {
+        fn methods(&self) -> Vec<String> {
+            self.0.lock().unwrap().methods.clone()
+        }
+    }
+
+    impl DuplexTransport for Node {
+        type Error = Infallible;
+        type Send<'a> = Ready<Result<(), Infallible>>;
+
+        fn send(&self, message: Vec<u8>) -> Self::Send<'_> {
+            let request = serde_json::from_slice::<Value>(&message).unwrap();
+            let method = request["method"].as_str().unwrap().to_owned();
+
+            let mut state = self.0.lock().unwrap();
+            let result = match method.as_str() {
+                "test_subscribe" => json!("0x1"),
+                "test_unsubscribe" => json!(true),
+                _ => json!(null),
+            };
+            state.inbox.push_back(json!({
+                "jsonrpc": "2.0",
+                "result": result,
+                "id": request["id"],
+            }));
+            if method == "test_subscribe" {
+                for (subscription, result) in [("0x1", 1), ("0x2", 2), ("0x1", 3)] {
+                    state.inbox.push_back(json!({
+                        "jsonrpc": "2.0",
+                        "method": "test_subscription",
+                        "params": {
+                            "subscription": subscription,
+                            "result": result,
+                        },
+                    }));
+                }
+            }
+            state.methods.push(method);
+            if let Some(waker) = state.waker.take() {
+                waker.wake();
+            }
+
+            future::ready(Ok(()))
+        }
+
+        fn poll_receive(&self, cx: &mut Context) -> Poll<Result<Vec<u8>, Infallible>> {
+            let mut state = self.0.lock().unwrap();
+            match state.inbox.pop_front() {
+                Some(message) => Poll::Ready(Ok(serde_json::to_vec(&message).unwrap())),
+                None => {
+                    state.waker = Some(cx.waker().clone());
+                    Poll::Pending
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn receives_notifications() {
+        let client = Client::duplex(Node::default());
+
+        block_on(async {
+            let mut subscription = client
+                .subscribe::<_, u32>("test", ("numbers",))
+                .await
+                .unwrap();
+            assert_eq!(subscription.id(), "0x1");
+
+            assert_eq!(subscription.next().await.unwrap().unwrap(), 1);
+            assert_eq!(subscription.next().await.unwrap().unwrap(), 3);
+        });
+    }
+
+    #[test]
+    fn unsubscribes_on_drop() {
+        let client = Client::duplex(Node::default());
+
+        block_on(async {
+            let subscription = client
+                .subscribe::<_, u32>("test", ("numbers",))
+                .await
+                .unwrap();
+            drop(subscription);
+
+            client.call::<_, ()>("test_call", ()).await.unwrap();
+        });
+
+        assert_eq!(
+            client.transport().transport().methods(),
+            ["test_subscribe", "test_unsubscribe", "test_call"],
+        );
+    }
+
+    #[test]
+    fn sends_unsubscribe_when_polling_streams() {
+        let client = Client::duplex(Node::default());
+
+        block_on(async {
+            let subscription = client
+                .subscribe::<_, u32>("test", ("numbers",))
+                .await
+                .unwrap();
+            let mut notifications = client.notifications::<Value>("test_other");
+            drop(subscription);
+
+            assert!(notifications.next().now_or_never().is_none());
+        });
+
+        assert_eq!(
+            client.transport().transport().methods(),
+            ["test_subscribe", "test_unsubscribe"],
+        );
+    }
+
+    #[test]
+    fn explicit_unsubscribe() {
+        let client = Client::duplex(Node::default());
+
+        block_on(async {
+            let subscription = client
+                .subscribe::<_, u32>("test", ("numbers",))
+                .await
+                .unwrap();
+            assert!(subscription.unsubscribe().await.unwrap());
+
+            client.call::<_, ()>("test_call", ()).await.unwrap();
+        });
+
+        assert_eq!(
+            client.transport().transport().methods(),
+            ["test_subscribe", "test_unsubscribe", "test_call"],
+        );
+    }
+
+    /// Metrics that keep a log of the recorded methods.
+    #[derive(Debug, Default)]
+    struct Log(Mutex<Vec<String>>);
+
+    impl Metrics for Log {
+        fn record_call(&self, method: &str, _: Duration, error: Option<CallError>) {
+            assert_eq!(error, None);
+            self.0.lock().unwrap().push(method.to_owned());
+        }
+    }
+
+    #[test]
+    fn records_metrics() {
+        let log = Arc::new(Log::default());
+        let client = Client::duplex(Node::default()).with_metrics(log.clone());
+
+        block_on(async {
+            let subscription = client
+                .subscribe::<_, u32>("test", ("numbers",))
+                .await
+                .unwrap();
+            subscription.unsubscribe().await.unwrap();
+
+            let subscription = client
+                .subscribe::<_, u32>("test", ("numbers",))
+                .await
+                .unwrap();
+            drop(subscription);
+
+            client.notify("test_notify", ()).await.unwrap();
+        });
+
+        assert_eq!(
+            *log.0.lock().unwrap(),
+            [
+                "test_subscribe",
+                "test_unsubscribe",
+                "test_subscribe",
+                "test_unsubscribe",
+                "test_notify",
+            ],
+        );
+    }
+}
@@ -0,0 +1,328 @@
+//! Module containing the `tracing` instrumentation for JSON RPC calls.
+//!
+//! Each call is made in an `rpc_call` span that records the method, request ID
+//! and size, and once the call completes, the response size, latency in
+//! milliseconds and the class of the error if the call failed. Request
+//! parameters can optionally be recorded as well, with redaction for methods
+//! whose parameters are sensitive.
+//!
+//! Subscription requests are traced like any other call. Calls in a batch each
+//! get their own span, with the request and response sizes of the whole batch.
+
+use super::client::ClientError;
+use super::id::Id;
+use super::metrics::CallError;
+use serde::ser::Serialize;
+use std::collections::HashSet;
+use std::error::Error;
+use tracing::field::{self, Empty};
+use tracing::Span;
+use web_time::Instant;
+
+/// Methods with redacted parameters by default, as they include signed
+/// transactions or data to sign.
+const REDACTED: &[&str] = &[
+    "eth_sendRawTransaction",
+    "eth_sendTransaction",
+    "eth_sign",
+    "eth_signTransaction",
+    "eth_signTypedData",
+    "personal_sendTransaction",
+    "personal_sign",
+];
+
+/// Tracing configuration for a client.
+#[derive(Debug)]
+pub struct Config {
+    params: bool,
+    redacted: HashSet<String>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            params: false,
+            redacted: REDACTED.iter().map(|&method| method.to_owned()).collect(),
+        }
+    }
+}
+
+impl Config {
+    /// Sets whether request parameters are recorded in call spans.
+    pub fn set_params(&mut self, enabled: bool) {
+        self.params = enabled;
+    }
+
+    /// Redacts the parameters of the specified method from call spans.
+    pub fn redact(&mut self, method: String) {
+        self.redacted.insert(method);
+    }
+
+    /// Starts tracing a call.
+    pub fn start<P>(&self, method: &str, id: &Id, params: &P, request: &[u8]) -> Call
+    where
+        P: Serialize,
+    {
+        let span = tracing::debug_span!(
+            "rpc_call",
+            method,
+            id = %id,
+            params = Empty,
+            request_bytes = request.len(),
+            response_bytes = Empty,
+            latency_ms = Empty,
+            error = Empty,
+        );
+        if self.params && !span.is_disabled() {
+            if self.redacted.contains(method) {
+                span.record("params", "[redacted]");
+            } else if let Ok(params) = serde_json::to_string(params) {
+                span.record("params", params.as_str());
+            }
+        }
+
+        Call {
+            span,
+            start: Instant::now(),
+        }
+    }
+}
+
+/// A traced call.
+pub struct Call {
+    span: Span,
+    start: Instant,
+}
+
+impl Call {
+    /// Returns the span for the call.
+    pub fn span(&self) -> &Span {
+        &self.span
+    }
+
+    /// Records the size of the response.
+    pub fn response(&self, response: &[u8]) {
+        self.span.record("response_bytes", response.len());
+    }
+
+    /// Records the completion of the call, with the error if it failed.
+    pub fn finish<E>(self, error: Option<&ClientError<E>>)
+    where
+        E: Error,
+    {
+        let latency = self.start.elapsed().as_secs_f64() * 1000.0;
+        self.span.record("latency_ms", latency);
+
+        match error {
+            None => tracing::debug!(parent: &self.span, "RPC call succeeded"),
+            Some(err) => {
+                self.span.record("error", CallError::new(err).kind());
+                tracing::debug!(parent: &self.span, error = field::display(err), "RPC call failed");
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::jsonrpc::server::Server;
+    use crate::jsonrpc::{Client, ErrorCode, RpcError};
+    use crate::transport::DuplexTransport;
+    use futures::executor::block_on;
+    use serde_json::{json, Value};
+    use std::collections::{HashMap, VecDeque};
+    use std::convert::Infallible;
+    use std::fmt::Debug;
+    use std::future::{self, Ready};
+    use std::sync::{Arc, Mutex};
+    use std::task::{Context, Poll, Waker};
+    use tracing::field::{Field, Visit};
+    use tracing::span::{Attributes, Id, Record};
+    use tracing::{Event, Metadata, Subscriber};
+
+    /// A subscriber that captures the fields of all spans, keyed by span ID.
+    #[derive(Clone, Default)]
+    struct Capture(Arc<Mutex<Vec<HashMap<String, String>>>>);
+
+    impl Capture {
+        fn spans(&self) -> Vec<HashMap<String, String>> {
+            self.0.lock().unwrap().clone()
+        }
+
+        fn visit(&self, id: &Id) -> Visitor<'_> {
+            Visitor(self, id.into_u64() as usize - 1)
+        }
+    }
+
+    struct Visitor<'a>(&'a Capture, usize);
+
+    impl Visit for Visitor<'_> {
+        fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+            let mut spans = (self.0).0.lock().unwrap();
+            spans[self.1].insert(field.name().to_owned(), format!("{:?}", value));
+        }
+
+        fn record_str(&mut self, field: &Field, value: &str) {
+            let mut spans = (self.0).0.lock().unwrap();
+            spans[self.1].insert(field.name().to_owned(), value.to_owned());
+        }
+    }
+
+    impl Subscriber for Capture {
+        fn enabled(&self, _: &Metadata) -> bool {
+            true
+        }
+
+        fn new_span(&self, span: &Attributes) -> Id {
+            let id = {
+                let mut spans = self.0.lock().unwrap();
+                spans.push(HashMap::new());
+                Id::from_u64(spans.len() as _)
+            };
+            span.record(&mut self.visit(&id));
+            id
+        }
+
+        fn record(&self, span: &Id, values: &Record) {
+            values.record(&mut self.visit(span));
+        }
+
+        fn record_follows_from(&self, _: &Id, _: &Id) {}
+        fn event(&self, _: &Event) {}
+        fn enter(&self, _: &Id) {}
+        fn exit(&self, _: &Id) {}
+    }
+
+    fn traced<T>(
+        client: Client<T>,
+        calls: impl FnOnce(&Client<T>),
+    ) -> Vec<HashMap<String, String>> {
+        let capture = Capture::default();
+        tracing::subscriber::with_default(capture.clone(), || calls(&client));
+        capture.spans()
+    }
+
+    fn server() -> Server {
+        Server::new()
+            .with_method("eth_chainId", |(): ()| async { Ok("0x1") })
+            .with_method("eth_sendRawTransaction", |_: [String; 1]| async {
+                Err::<(), _>(RpcError::new(
+                    ErrorCode::ServerError(-32000),
+                    "nonce too low",
+                ))
+            })
+    }
+
+    #[test]
+    fn records_call_spans() {
+        let spans = traced(Client::new(server()), |client| {
+            block_on(client.call::<_, String>("eth_chainId", ())).unwrap();
+            block_on(client.call::<_, ()>("eth_sendRawTransaction", ["0x00"])).unwrap_err();
+        });
+
+        assert_eq!(spans.len(), 2);
+        assert_eq!(spans[0]["method"], "eth_chainId");
+        assert_eq!(spans[0]["id"], "0");
+        assert_eq!(spans[0]["request_bytes"], "61");
+        assert_eq!(spans[0]["response_bytes"], "39");
+        assert!(spans[0].contains_key("latency_ms"));
+        assert!(!spans[0].contains_key("params"));
+        assert!(!spans[0].contains_key("error"));
+
+        assert_eq!(spans[1]["method"], "eth_sendRawTransaction");
+        assert_eq!(spans[1]["error"], "rpc");
+    }
+
+    #[test]
+    fn records_and_redacts_params() {
+        let client = Client::new(server())
+            .with_traced_params(true)
+            .with_redacted_params("eth_chainId");
+        let spans = traced(client, |client| {
+            block_on(client.call::<_, String>("eth_chainId", ())).unwrap();
+            block_on(client.call::<_, ()>("eth_sendRawTransaction", ["0x00"])).unwrap_err();
+        });
+        assert_eq!(spans[0]["params"], "[redacted]");
+        assert_eq!(spans[1]["params"], "[redacted]");
+
+        let client = Client::new(server()).with_traced_params(true);
+        let spans = traced(client, |client| {
+            block_on(client.call::<_, String>("eth_chainId", ())).unwrap();
+        });
+        assert_eq!(spans[0]["params"], "null");
+    }
+
+    #[test]
+    fn records_batch_call_spans() {
+        let spans = traced(Client::new(server()), |client| {
+            block_on(async {
+                let batch = client.batch();
+                let chain_id = batch.call::<_, String>("eth_chainId", ());
+                let send = batch.call::<_, ()>("eth_sendRawTransaction", ["0x00"]);
+                batch.send().await.unwrap();
+                chain_id.await.unwrap();
+                send.await.unwrap_err();
+            });
+        });
+
+        assert_eq!(spans.len(), 2);
+        assert_eq!(spans[0]["method"], "eth_chainId");
+        assert_eq!(spans[0]["id"], "0");
+        assert!(!spans[0].contains_key("error"));
+        assert_eq!(spans[1]["method"], "eth_sendRawTransaction");
+        assert_eq!(spans[1]["id"], "1");
+        assert_eq!(spans[1]["error"], "rpc");
+        for field in ["request_bytes", "response_bytes"] {
+            assert_eq!(spans[0][field], spans[1][field]);
+        }
+    }
+
+    /// An in-memory duplex transport that responds to all requests with the
+    /// same subscription ID.
+    #[derive(Default)]
+    struct Node(Mutex<(VecDeque<Value>, Option<Waker>)>);
+
+    impl DuplexTransport for Node {
+        type Error = Infallible;
+        type Send<'a> = Ready<Result<(), Infallible>>;
+
+        fn send(&self, message: Vec<u8>) -> Self::Send<'_> {
+            let request = serde_json::from_slice::<Value>(&message).unwrap();
+            let mut state = self.0.lock().unwrap();
+            state.0.push_back(json!({
+                "jsonrpc": "2.0",
+                "result": "0x1",
+                "id": request["id"],
+            }));
+            if let Some(waker) = state.1.take() {
+                waker.wake();
+            }
+            future::ready(Ok(()))
+        }
+
+        fn poll_receive(&self, cx: &mut Context) -> Poll<Result<Vec<u8>, Infallible>> {
+            let mut state = self.0.lock().unwrap();
+            match state.0.pop_front() {
+                Some(message) => Poll::Ready(Ok(serde_json::to_vec(&message).unwrap())),
+                None => {
+                    state.1 = Some(cx.waker().clone());
+                    Poll::Pending
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn records_subscription_spans() {
+        let spans = traced(Client::duplex(Node::default()), |client| {
+            block_on(client.subscribe::<_, Value>("eth", ("newHeads",))).unwrap();
+        });
+
+        assert_eq!(spans.len(), 1);
+        assert_eq!(spans[0]["method"], "eth_subscribe");
+        assert_eq!(spans[0]["id"], "0");
+        assert!(spans[0].contains_key("response_bytes"));
+        assert!(spans[0].contains_key("latency_ms"));
+        assert!(!spans[0].contains_key("error"));
+    }
+}
@@ -16,5 +16,6 @@
 pub mod ethereum;
 pub mod jsonrpc;
 pub mod transport;
+mod util;
 
 pub use ethereum::Web3;
@@ -1,10 +1,25 @@
-//! Module re-exporting transport implementations included via features.
+//! Module re-exporting transport implementations included via features, along
+//! with generic transport wrappers.
 
+pub mod cache;
 #[cfg(feature = "mock")]
 pub mod mock;
+pub mod multi;
+pub mod rate_limit;
+pub mod replay;
+pub mod retry;
 
+pub use self::cache::Cache;
 #[cfg(feature = "mock")]
 pub use self::mock::MockTransport;
+pub use self::multi::Multi;
+pub use self::rate_limit::RateLimit;
+pub use self::replay::{Record, Replay};
+pub use self::retry::Retry;
 pub use ethrs_transport::*;
 #[cfg(feature = "http")]
 pub use ethrs_transport_http as http;
+#[cfg(feature = "ipc")]
+pub use ethrs_transport_ipc as ipc;
+#[cfg(feature = "ws")]
+pub use ethrs_transport_ws as ws;
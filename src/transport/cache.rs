@@ -0,0 +1,537 @@
+//! This module contains a transport wrapper that caches the responses of calls
+//! whose results can never change, in an in-memory LRU cache.
+//!
+//! Methods are classified as cacheable automatically:
+//! - Methods that are immutable by definition, such as `eth_chainId` and
+//!   queries for blocks by hash.
+//! - Queries for transactions and receipts by hash, and for the logs and
+//!   receipts of a block by hash, once their block is finalized, since a reorg
+//!   can move transactions to other blocks. The cache does not track finality
+//!   itself, see [`Cache::set_finalized_block`].
+//!
+//! State queries, such as `eth_getBalance` and `eth_call`, are not cached even
+//! when they are pinned to a block by hash, as their results don't include the
+//! block number needed to check whether the block is finalized.
+//!
+//! Only successful non-`null` results are cached, so that calls for things that
+//! don't exist yet, such as receipts for pending transactions, are retried.
+
+use crate::jsonrpc::data::{Id, Messages, Response, Version};
+use crate::transport::Transport;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, HashMap};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, MutexGuard};
+use std::task::{ready, Context, Poll};
+
+/// Methods whose results never change.
+const IMMUTABLE_METHODS: &[&str] = &[
+    "eth_chainId",
+    "net_version",
+    "eth_getBlockByHash",
+    "eth_getBlockTransactionCountByHash",
+    "eth_getTransactionByBlockHashAndIndex",
+    "eth_getUncleByBlockHashAndIndex",
+    "eth_getUncleCountByBlockHash",
+];
+
+/// Methods whose results never change once the block they are included in is
+/// finalized.
+const FINALIZED_METHODS: &[&str] = &["eth_getTransactionByHash", "eth_getTransactionReceipt"];
+
+/// A transport wrapper that caches the responses of calls with immutable
+/// results.
+///
+/// Batch requests are served from the cache only if all of their calls are
+/// cached, otherwise the whole batch is sent to the underlying transport and
+/// the results of its cacheable calls are cached.
+#[derive(Debug)]
+pub struct Cache<T> {
+    transport: T,
+    capacity: usize,
+    entries: Mutex<Lru>,
+    finalized: AtomicU64,
+}
+
+/// The cache key of a cacheable call.
+#[derive(Debug)]
+struct Key {
+    key: String,
+    /// Whether the result can only be cached once its block is finalized.
+    finalized: bool,
+}
+
+/// A least recently used cache of call results.
+#[derive(Debug, Default)]
+struct Lru {
+    /// The cached results by call key, along with the time they were last
+    /// used.
+    results: HashMap<String, (Value, u64)>,
+    /// The call keys by the time they were last used.
+    order: BTreeMap<u64, String>,
+    /// The current time, which increases every time an entry is used.
+    now: u64,
+}
+
+impl<T> Cache<T> {
+    /// Creates a new caching transport wrapper, with a capacity of 1000
+    /// cached results.
+    pub fn new(transport: T) -> Self {
+        Cache {
+            transport,
+            capacity: 1000,
+            entries: Default::default(),
+            finalized: AtomicU64::new(0),
+        }
+    }
+
+    /// Sets the maximum number of cached results, after which the least
+    /// recently used ones are evicted.
+    ///
+    /// # Panics
+    ///
+    /// This method panics if the capacity is zero.
+    pub fn with_capacity(mut self, capacity: usize) -> Self {
+        assert!(capacity > 0, "invalid cache capacity");
+        self.capacity = capacity;
+        self
+    }
+
+    /// Returns a reference to the underlying transport.
+    pub fn transport(&self) -> &T {
+        &self.transport
+    }
+
+    /// Returns the number of cached results.
+    pub fn len(&self) -> usize {
+        self.entries().results.len()
+    }
+
+    /// Returns `true` if there are no cached results.
+    pub fn is_empty(&self) -> bool {
+        self.entries().results.is_empty()
+    }
+
+    /// Sets the number of the latest finalized block. Transactions, receipts
+    /// and logs are only cached once they are included in a finalized block,
+    /// so they are not cached until this is set.
+    ///
+    /// The finalized block number never decreases, setting it to an older
+    /// block has no effect.
+    pub fn set_finalized_block(&self, number: u64) {
+        self.finalized.fetch_max(number, Ordering::Relaxed);
+    }
+
+    /// Removes all cached results.
+    pub fn clear(&self) {
+        *self.entries() = Lru::default();
+    }
+
+    /// Returns the cached response for a request if all of its calls are
+    /// cached, or the keys of its cacheable calls by ID otherwise.
+    fn lookup(&self, request: &[u8]) -> Result<Vec<u8>, Vec<(Id, Key)>> {
+        let requests = match serde_json::from_slice::<Messages<CallEnvelope>>(request) {
+            Ok(requests) => requests,
+            Err(_) => return Err(Vec::new()),
+        };
+
+        let mut entries = self.entries();
+        let mut keys = Vec::new();
+        let mut hits = Vec::new();
+        let mut complete = true;
+        for request in requests.iter() {
+            let (id, key) = match (&request.id, key(request)) {
+                (Some(id), Some(key)) => (id, key),
+                _ => {
+                    complete = false;
+                    continue;
+                }
+            };
+            match entries.get(&key.key) {
+                Some(result) => hits.push(Response {
+                    jsonrpc: Version::V2,
+                    result: Ok(result),
+                    id: Some(id.clone()),
+                }),
+                None => complete = false,
+            }
+            keys.push((id.clone(), key));
+        }
+
+        if !complete || keys.is_empty() {
+            return Err(keys);
+        }
+        let response = match requests {
+            Messages::Single(_) => serde_json::to_vec(&hits[0]),
+            Messages::Batch(_) => serde_json::to_vec(&hits),
+        };
+        Ok(response.expect("JSON value serialization is infallible"))
+    }
+
+    /// Caches the cacheable results from a response.
+    fn store(&self, keys: &[(Id, Key)], response: &[u8]) {
+        if keys.is_empty() {
+            return;
+        }
+        let responses = match serde_json::from_slice::<Messages<Response<Value>>>(response) {
+            Ok(responses) => responses,
+            Err(_) => return,
+        };
+
+        let finalized = self.finalized.load(Ordering::Relaxed);
+        let mut entries = self.entries();
+        for response in responses.iter() {
+            let result = match &response.result {
+                Ok(result) if is_final(result) => result,
+                _ => continue,
+            };
+            let key = keys
+                .iter()
+                .find(|(id, _)| Some(id) == response.id.as_ref())
+                .map(|(_, key)| key);
+            if let Some(key) = key {
+                if key.finalized && !is_finalized(result, finalized) {
+                    continue;
+                }
+                entries.insert(key.key.clone(), result.clone(), self.capacity);
+            }
+        }
+    }
+
+    fn entries(&self) -> MutexGuard<'_, Lru> {
+        self.entries.lock().expect("poisoned cache entries")
+    }
+}
+
+impl Lru {
+    /// Returns a cached result, marking it as used.
+    fn get(&mut self, key: &str) -> Option<Value> {
+        let now = self.tick();
+        let (result, used) = self.results.get_mut(key)?;
+        let key = self.order.remove(used).expect("cache order out of sync");
+        self.order.insert(now, key);
+        *used = now;
+        Some(result.clone())
+    }
+
+    /// Inserts a result, evicting the least recently used results if the
+    /// cache is over capacity.
+    fn insert(&mut self, key: String, result: Value, capacity: usize) {
+        let now = self.tick();
+        if let Some((_, used)) = self.results.insert(key.clone(), (result, now)) {
+            self.order.remove(&used);
+        }
+        self.order.insert(now, key);
+
+        while self.results.len() > capacity {
+            let (_, key) = self.order.pop_first().expect("cache order out of sync");
+            self.results.remove(&key);
+        }
+    }
+
+    fn tick(&mut self) -> u64 {
+        self.now += 1;
+        self.now
+    }
+}
+
+impl<T> Transport for Cache<T>
+where
+    T: Transport,
+{
+    type Error = T::Error;
+    type Call<'a>
+        = CacheCall<'a, T>
+    where
+        T: 'a;
+
+    fn call<'a>(&'a self, request: &'a [u8]) -> Self::Call<'a> {
+        let state = match self.lookup(request) {
+            Ok(response) => State::Cached(Some(response)),
+            Err(keys) => State::Calling {
+                keys,
+                call: Box::pin(self.transport.call(request)),
+            },
+        };
+        CacheCall { cache: self, state }
+    }
+}
+
+/// Future returned by [`Cache`] calls.
+pub struct CacheCall<'a, T>
+where
+    T: Transport + 'a,
+{
+    cache: &'a Cache<T>,
+    state: State<'a, T>,
+}
+
+/// The state of a cached call.
+enum State<'a, T>
+where
+    T: Transport + 'a,
+{
+    /// The response was cached.
+    Cached(Option<Vec<u8>>),
+    /// The call is in flight, with the cache keys of its cacheable calls.
+    Calling {
+        keys: Vec<(Id, Key)>,
+        call: Pin<Box<T::Call<'a>>>,
+    },
+}
+
+impl<T> Future for CacheCall<'_, T>
+where
+    T: Transport,
+{
+    type Output = Result<Vec<u8>, T::Error>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        let this = &mut *self;
+        match &mut this.state {
+            State::Cached(response) => Poll::Ready(Ok(response
+                .take()
+                .expect("cached call polled after completion"))),
+            State::Calling { keys, call } => {
+                let response = ready!(call.as_mut().poll(cx))?;
+                this.cache.store(keys, &response);
+                Poll::Ready(Ok(response))
+            }
+        }
+    }
+}
+
+/// Partial request object used for classifying calls.
+#[derive(Debug, Deserialize)]
+struct CallEnvelope {
+    method: String,
+    #[serde(default)]
+    params: Value,
+    id: Option<Id>,
+}
+
+/// Returns the cache key for a call, or `None` if its result can change.
+fn key(call: &CallEnvelope) -> Option<Key> {
+    let method = call.method.as_str();
+    let block = call.params.get(0);
+    let finalized = match method {
+        _ if IMMUTABLE_METHODS.contains(&method) => false,
+        _ if FINALIZED_METHODS.contains(&method) => true,
+        "eth_getBlockReceipts" if block.is_some_and(is_block_hash) => true,
+        "eth_getLogs" if block.and_then(|filter| filter.get("blockHash")).is_some() => true,
+        _ => return None,
+    };
+
+    Some(Key {
+        key: json!([method, call.params]).to_string(),
+        finalized,
+    })
+}
+
+/// Returns `true` if a block parameter specifies a block by hash.
+fn is_block_hash(block: &Value) -> bool {
+    match block {
+        Value::String(block) => block.len() == 66 && block.starts_with("0x"),
+        Value::Object(block) => block.contains_key("blockHash"),
+        _ => false,
+    }
+}
+
+/// Returns `true` if a result is final and can be cached. This excludes `null`
+/// results, and pending transactions which don't have a block hash yet.
+fn is_final(result: &Value) -> bool {
+    !result.is_null() && result.get("blockHash") != Some(&Value::Null)
+}
+
+/// Returns `true` if a transaction, receipt or log result is included in a
+/// block that is at or below the finalized block number. Lists of results are
+/// finalized if they are not empty and all of their items are.
+fn is_finalized(result: &Value, finalized: u64) -> bool {
+    match result {
+        Value::Array(items) => {
+            !items.is_empty() && items.iter().all(|item| is_finalized(item, finalized))
+        }
+        result => result
+            .get("blockNumber")
+            .and_then(Value::as_str)
+            .and_then(|number| u64::from_str_radix(number.strip_prefix("0x")?, 16).ok())
+            .is_some_and(|number| number <= finalized),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::jsonrpc::server::Server;
+    use crate::jsonrpc::Client;
+    use futures::executor::block_on;
+    use std::sync::atomic::{AtomicU64, Ordering};
+    use std::sync::Arc;
+
+    fn classify(method: &str, params: Value) -> bool {
+        key(&CallEnvelope {
+            method: method.to_owned(),
+            params,
+            id: None,
+        })
+        .is_some()
+    }
+
+    #[test]
+    fn classifies_methods() {
+        let hash = format!("0x{}", "11".repeat(32));
+
+        assert!(classify("eth_chainId", json!([])));
+        assert!(classify("eth_getBlockByHash", json!([hash, false])));
+        assert!(classify("eth_getTransactionReceipt", json!([hash])));
+        assert!(!classify("eth_blockNumber", json!([])));
+        assert!(!classify("eth_getBlockByNumber", json!(["0x1", false])));
+        assert!(!classify("eth_sendRawTransaction", json!(["0x"])));
+
+        let canonical = json!({ "blockHash": hash, "requireCanonical": true });
+        assert!(!classify("eth_getBalance", json!(["0x00", canonical])));
+        assert!(!classify("eth_call", json!([{}, canonical])));
+        assert!(!classify("eth_getBalance", json!(["0x00", hash])));
+        assert!(!classify("eth_getBalance", json!(["0x00", "latest"])));
+
+        assert!(classify("eth_getBlockReceipts", json!([hash])));
+        assert!(classify("eth_getBlockReceipts", json!([canonical])));
+        assert!(!classify("eth_getBlockReceipts", json!(["latest"])));
+        assert!(classify("eth_getLogs", json!([{ "blockHash": hash }])));
+        assert!(!classify("eth_getLogs", json!([{ "fromBlock": "0x1" }])));
+    }
+
+    #[test]
+    fn checks_finality() {
+        let log = |number: &str| json!({ "blockNumber": number });
+
+        assert!(is_finalized(&log("0x1"), 1));
+        assert!(!is_finalized(&log("0x2"), 1));
+        assert!(!is_finalized(&json!({}), 1));
+        assert!(is_finalized(&json!([log("0x0"), log("0x1")]), 1));
+        assert!(!is_finalized(&json!([log("0x1"), log("0x2")]), 1));
+        assert!(!is_finalized(&json!([]), 1));
+    }
+
+    /// A server counting the number of calls it handles.
+    fn node(calls: Arc<AtomicU64>) -> Server {
+        let count = move || calls.fetch_add(1, Ordering::SeqCst);
+        let (a, b, c) = (count.clone(), count.clone(), count);
+        Server::new()
+            .with_method("eth_chainId", move |(): ()| {
+                a();
+                async { Ok("0x1") }
+            })
+            .with_method("eth_blockNumber", move |(): ()| {
+                b();
+                async { Ok("0x2a") }
+            })
+            .with_method("eth_getTransactionReceipt", move |[hash]: [String; 1]| {
+                c();
+                async move {
+                    Ok((hash != "0x00")
+                        .then(|| json!({ "blockHash": "0x01", "blockNumber": "0x1" })))
+                }
+            })
+    }
+
+    #[test]
+    fn caches_immutable_results() {
+        let calls = Arc::new(AtomicU64::new(0));
+        let client = Client::new(Cache::new(node(calls.clone())));
+        client.transport().set_finalized_block(1);
+
+        block_on(async {
+            for _ in 0..2 {
+                client.call::<_, String>("eth_chainId", ()).await.unwrap();
+                client
+                    .call::<_, String>("eth_blockNumber", ())
+                    .await
+                    .unwrap();
+                client
+                    .call::<_, Option<Value>>("eth_getTransactionReceipt", ["0x00"])
+                    .await
+                    .unwrap();
+                client
+                    .call::<_, Option<Value>>("eth_getTransactionReceipt", ["0x01"])
+                    .await
+                    .unwrap();
+            }
+        });
+
+        assert_eq!(calls.load(Ordering::SeqCst), 6);
+        assert_eq!(client.transport().len(), 2);
+    }
+
+    #[test]
+    fn caches_receipts_once_finalized() {
+        let calls = Arc::new(AtomicU64::new(0));
+        let client = Client::new(Cache::new(node(calls.clone())));
+        let receipt = || {
+            block_on(client.call::<_, Option<Value>>("eth_getTransactionReceipt", ["0x01"]))
+                .unwrap()
+        };
+
+        receipt();
+        assert!(client.transport().is_empty());
+
+        client.transport().set_finalized_block(1);
+        receipt();
+        receipt();
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+
+        client.transport().set_finalized_block(0);
+        assert_eq!(client.transport().finalized.load(Ordering::Relaxed), 1);
+    }
+
+    #[test]
+    fn caches_batches() {
+        let calls = Arc::new(AtomicU64::new(0));
+        let client = Client::new(Cache::new(node(calls.clone())));
+        client.transport().set_finalized_block(1);
+
+        block_on(async {
+            for _ in 0..2 {
+                let batch = client.batch();
+                let results = (
+                    batch.call::<_, String>("eth_chainId", ()),
+                    batch.call::<_, Value>("eth_getTransactionReceipt", ["0x01"]),
+                );
+                batch.send().await.unwrap();
+                assert_eq!(results.0.await.unwrap(), "0x1");
+                assert_eq!(
+                    results.1.await.unwrap(),
+                    json!({ "blockHash": "0x01", "blockNumber": "0x1" }),
+                );
+            }
+
+            let batch = client.batch();
+            let results = (
+                batch.call::<_, String>("eth_chainId", ()),
+                batch.call::<_, String>("eth_blockNumber", ()),
+            );
+            batch.send().await.unwrap();
+            results.0.await.unwrap();
+            results.1.await.unwrap();
+        });
+
+        assert_eq!(calls.load(Ordering::SeqCst), 4);
+    }
+
+    #[test]
+    fn evicts_least_recently_used() {
+        let mut lru = Lru::default();
+        lru.insert("a".to_owned(), json!(1), 2);
+        lru.insert("b".to_owned(), json!(2), 2);
+        assert_eq!(lru.get("a"), Some(json!(1)));
+        lru.insert("c".to_owned(), json!(3), 2);
+
+        assert_eq!(lru.get("b"), None);
+        assert_eq!(lru.get("a"), Some(json!(1)));
+        assert_eq!(lru.get("c"), Some(json!(3)));
+        assert_eq!(lru.results.len(), lru.order.len());
+    }
+}
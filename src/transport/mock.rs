@@ -1,53 +1,133 @@
 //! This crate provides a mock [`ethrs::transport::Transport`] implementation
 //! that can be used for unit testing.
 
+use crate::jsonrpc::{ErrorCode, RpcError};
 use crate::transport::Transport;
 use serde_json::{json, Value};
-use std::collections::VecDeque;
+use std::fmt::{self, Debug, Formatter};
 use std::future::Future;
 use std::pin::Pin;
+use std::sync::{Arc, Mutex, MutexGuard};
 use std::task::{Context, Poll};
+use std::thread;
 use thiserror::Error;
 
-/// A mock transport that allows setting request expectations
-#[derive(Clone, Debug, Default)]
+/// Macro for construcing a mock transport error.
+macro_rules! error {
+    ($($x:tt)*) => {
+        Error(format!($($x)*))
+    }
+}
+
+/// A mock transport that allows setting request expectations.
+///
+/// Once the transport is dropped, its expectations are verified, panicking if
+/// any of them were not met. Cloning the transport copies its expectations, so
+/// that each clone is used and verified independently. Use
+/// [`MockTransport::shared`] for handles that share their expectations.
+#[derive(Debug, Default)]
 pub struct MockTransport {
-    calls: VecDeque<(String, Value, Result<Value, Error>)>,
+    expectations: Arc<Mutex<Vec<Expected>>>,
+}
+
+/// An expectation along with the number of calls that matched it.
+#[derive(Clone, Debug)]
+struct Expected {
+    expectation: Expectation,
+    calls: usize,
+}
+
+/// An expected call.
+#[derive(Clone, Debug)]
+pub struct Expectation {
+    method: String,
+    params: Matcher,
+    result: Result<Value, RpcError>,
+    times: Option<usize>,
+    ordered: bool,
+}
+
+/// A matcher for the parameters of an expected call.
+#[derive(Clone)]
+pub enum Matcher {
+    /// Matches any parameters.
+    Any,
+    /// Matches parameters that are exactly equal to the value.
+    Exact(Value),
+    /// Matches parameters that contain the value. Objects match if they
+    /// contain all of the fields of the value, and arrays match if they start
+    /// with the elements of the value, both recursively.
+    Partial(Value),
+    /// Matches parameters for which the predicate returns `true`.
+    Predicate(Arc<dyn Fn(&Value) -> bool + Send + Sync>),
+}
+
+impl Clone for MockTransport {
+    fn clone(&self) -> Self {
+        let expectations = self.expectations().clone();
+        MockTransport {
+            expectations: Arc::new(Mutex::new(expectations)),
+        }
+    }
 }
 
 impl MockTransport {
-    /// Adds a call expectation to the transport.
+    /// Returns a handle to the transport that shares its expectations. This
+    /// allows adding or verifying expectations after the transport was moved
+    /// into a client. The expectations are verified once the last handle is
+    /// dropped.
+    pub fn shared(&self) -> Self {
+        MockTransport {
+            expectations: self.expectations.clone(),
+        }
+    }
+
+    /// Adds a call expectation to the transport with exact parameters. Errors
+    /// are returned with the `-32000` server error code.
     pub fn expect_call(
         &mut self,
         method: impl Into<String>,
         params: Value,
         result: Result<Value, String>,
     ) -> &mut Self {
-        self.calls
-            .push_back((method.into(), params, result.map_err(Error)));
+        let expectation = Expectation::new(method).with_params(params);
+        self.expect(match result {
+            Ok(result) => expectation.with_result(result),
+            Err(message) => {
+                expectation.with_error(RpcError::new(ErrorCode::ServerError(-32000), message))
+            }
+        })
+    }
+
+    /// Adds an expectation to the transport.
+    pub fn expect(&mut self, expectation: Expectation) -> &mut Self {
+        self.expectations().push(Expected {
+            expectation,
+            calls: 0,
+        });
         self
     }
 
-    /// Check call to the mock transport.
-    fn call_inner(&mut self, request: &[u8], response: &mut Vec<u8>) -> Result<(), Error> {
-        /// Macro for construcing a mock transport error.
-        macro_rules! error {
-            ($($x:tt)*) => {
-                Error(format!($($x)*))
-            }
-        };
-        macro_rules! ensure_eq {
-            ($actual:expr, $expected:expr, $msg:expr) => {{
-                let (actual, expected) = (&$actual, &$expected);
-                if actual != expected {
-                    return Err(error!(
-                        "{}, got {:?} but expected {:?}",
-                        $msg, actual, expected,
-                    ));
-                }
-            }};
-        };
+    /// Verifies that all expectations were met, panicking otherwise.
+    pub fn verify(&self) {
+        let unmet = self
+            .expectations()
+            .iter()
+            .filter(|expected| !expected.is_satisfied())
+            .map(|expected| {
+                format!(
+                    "{} {:?} (called {} times)",
+                    expected.expectation.method, expected.expectation.params, expected.calls,
+                )
+            })
+            .collect::<Vec<_>>();
+        if !unmet.is_empty() {
+            panic!("unmet mock transport expectations: {}", unmet.join(", "));
+        }
+    }
 
+    /// Check call to the mock transport.
+    fn call_inner(&self, request: &[u8]) -> Result<Vec<u8>, Error> {
         let request = serde_json::from_slice::<Value>(request).map_err(|err| {
             error!(
                 "invalid request JSON '{}': {}",
@@ -56,42 +136,244 @@ impl MockTransport {
             )
         })?;
 
-        let (method, expected, result) = self
-            .calls
-            .pop_front()
-            .ok_or_else(|| error!("unexpected '{}' request", request["method"]))?;
-        ensure_eq!(request["method"], method, "unexpected method");
-        ensure_eq!(request["params"], expected, "unexpected parameters");
-
-        let json = match result {
-            Ok(result) => json!({
-                "jsonrpc": "2.0",
-                "result": result,
-                "id": request["id"],
-            }),
-            Err(err) => json!({
-                "jsonrpc": "2.0",
-                "error": {
-                    "code": -32000,
-                    "error": err.0,
-                    "data": {},
-                },
-                "id": request["id"],
-            }),
+        let mut expectations = self.expectations();
+        let response = match request {
+            Value::Array(requests) => {
+                // NOTE: Batches either match as a whole or not at all, so that
+                // failed batches don't count towards any expectation.
+                let calls = expectations
+                    .iter()
+                    .map(|expected| expected.calls)
+                    .collect::<Vec<_>>();
+                let responses = requests
+                    .iter()
+                    .filter_map(|request| respond(&mut expectations, request).transpose())
+                    .collect::<Result<Vec<_>, _>>();
+                let responses = match responses {
+                    Ok(responses) => responses,
+                    Err(err) => {
+                        for (expected, calls) in expectations.iter_mut().zip(calls) {
+                            expected.calls = calls;
+                        }
+                        return Err(err);
+                    }
+                };
+                match responses.is_empty() {
+                    true => None,
+                    false => Some(Value::Array(responses)),
+                }
+            }
+            request => respond(&mut expectations, &request)?,
         };
-        serde_json::to_writer(response, &json)
-            .map_err(|err| error!("error serializing response JSON: {}", err))?;
+        drop(expectations);
 
-        Ok(())
+        match response {
+            Some(response) => serde_json::to_vec(&response)
+                .map_err(|err| error!("error serializing response JSON: {}", err)),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    fn expectations(&self) -> MutexGuard<'_, Vec<Expected>> {
+        self.expectations
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// Matches a single request object to an expectation, returning the response
+/// or `None` for notifications.
+fn respond(expectations: &mut [Expected], request: &Value) -> Result<Option<Value>, Error> {
+    let method = request["method"]
+        .as_str()
+        .ok_or_else(|| error!("missing method in request {}", request))?;
+    let params = &request["params"];
+
+    let next = expectations
+        .iter()
+        .position(|expected| expected.expectation.ordered && !expected.is_exhausted());
+    let matched = next
+        .filter(|&i| expectations[i].matches(method, params))
+        .or_else(|| {
+            expectations.iter().position(|expected| {
+                !expected.expectation.ordered
+                    && !expected.is_exhausted()
+                    && expected.matches(method, params)
+            })
+        });
+    let expected = match matched {
+        Some(i) => &mut expectations[i],
+        None => {
+            return Err(match next {
+                Some(i) => error!(
+                    "unexpected call {} {}, expected {} {:?}",
+                    method,
+                    params,
+                    expectations[i].expectation.method,
+                    expectations[i].expectation.params,
+                ),
+                None => error!("unexpected call {} {}", method, params),
+            })
+        }
+    };
+    expected.calls += 1;
+
+    let id = match request.get("id") {
+        Some(id) => id,
+        None => return Ok(None),
+    };
+    Ok(Some(match &expected.expectation.result {
+        Ok(result) => json!({
+            "jsonrpc": "2.0",
+            "result": result,
+            "id": id,
+        }),
+        Err(err) => json!({
+            "jsonrpc": "2.0",
+            "error": err,
+            "id": id,
+        }),
+    }))
+}
+
+impl Drop for MockTransport {
+    fn drop(&mut self) {
+        if Arc::strong_count(&self.expectations) == 1 && !thread::panicking() {
+            self.verify();
+        }
     }
 }
 
 impl Transport for MockTransport {
     type Error = Error;
-    type Call = Ready<Result<(), Error>>;
+    type Call<'a> = Ready<Result<Vec<u8>, Error>>;
 
-    fn call(&mut self, request: &[u8], response: &mut Vec<u8>) -> Self::Call {
-        Ready::new(self.call_inner(request, response))
+    fn call<'a>(&'a self, request: &'a [u8]) -> Self::Call<'a> {
+        Ready::new(self.call_inner(request))
+    }
+}
+
+impl Expectation {
+    /// Creates a new expectation for a single call to the specified method
+    /// with any parameters, returning a `null` result.
+    ///
+    /// Expectations are ordered by default, meaning that they must be met in
+    /// the order that they were added to the transport.
+    pub fn new(method: impl Into<String>) -> Self {
+        Expectation {
+            method: method.into(),
+            params: Matcher::Any,
+            result: Ok(Value::Null),
+            times: Some(1),
+            ordered: true,
+        }
+    }
+
+    /// Sets the matcher for the call parameters. Values match exactly.
+    pub fn with_params(mut self, params: impl Into<Matcher>) -> Self {
+        self.params = params.into();
+        self
+    }
+
+    /// Sets the result to respond with.
+    pub fn with_result(mut self, result: Value) -> Self {
+        self.result = Ok(result);
+        self
+    }
+
+    /// Sets the RPC error to respond with.
+    pub fn with_error(mut self, error: RpcError) -> Self {
+        self.result = Err(error);
+        self
+    }
+
+    /// Sets the exact number of times the call is expected.
+    pub fn times(mut self, times: usize) -> Self {
+        self.times = Some(times);
+        self
+    }
+
+    /// Expects the call at least once, and allows it to be repeated any number
+    /// of times. Repeated expectations are always unordered.
+    pub fn repeatedly(mut self) -> Self {
+        self.times = None;
+        self.ordered = false;
+        self
+    }
+
+    /// Allows the call to happen in any order relative to the other
+    /// expectations.
+    pub fn unordered(mut self) -> Self {
+        self.ordered = false;
+        self
+    }
+}
+
+impl Expected {
+    fn matches(&self, method: &str, params: &Value) -> bool {
+        self.expectation.method == method && self.expectation.params.matches(params)
+    }
+
+    fn is_exhausted(&self) -> bool {
+        matches!(self.expectation.times, Some(times) if self.calls >= times)
+    }
+
+    fn is_satisfied(&self) -> bool {
+        match self.expectation.times {
+            Some(times) => self.calls == times,
+            None => self.calls > 0,
+        }
+    }
+}
+
+impl Matcher {
+    /// Creates a new matcher from a predicate.
+    pub fn predicate(predicate: impl Fn(&Value) -> bool + Send + Sync + 'static) -> Self {
+        Matcher::Predicate(Arc::new(predicate))
+    }
+
+    /// Returns `true` if the parameters match.
+    pub fn matches(&self, params: &Value) -> bool {
+        match self {
+            Matcher::Any => true,
+            Matcher::Exact(expected) => params == expected,
+            Matcher::Partial(expected) => contains(params, expected),
+            Matcher::Predicate(predicate) => predicate(params),
+        }
+    }
+}
+
+impl Debug for Matcher {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Matcher::Any => f.write_str("Any"),
+            Matcher::Exact(value) => f.debug_tuple("Exact").field(value).finish(),
+            Matcher::Partial(value) => f.debug_tuple("Partial").field(value).finish(),
+            Matcher::Predicate(_) => f.write_str("Predicate(..)"),
+        }
+    }
+}
+
+impl From<Value> for Matcher {
+    fn from(value: Value) -> Self {
+        Matcher::Exact(value)
+    }
+}
+
+/// Returns `true` if the value partially contains the expected value.
+fn contains(value: &Value, expected: &Value) -> bool {
+    match (value, expected) {
+        (Value::Object(value), Value::Object(expected)) => expected.iter().all(
+            |(key, expected)| matches!(value.get(key), Some(value) if contains(value, expected)),
+        ),
+        (Value::Array(value), Value::Array(expected)) => {
+            value.len() >= expected.len()
+                && value
+                    .iter()
+                    .zip(expected)
+                    .all(|(value, expected)| contains(value, expected))
+        }
+        (value, expected) => value == expected,
     }
 }
 
@@ -127,3 +409,168 @@ impl<T> Unpin for Ready<T> {}
 #[derive(Clone, Debug, Eq, Error, PartialEq)]
 #[error("{0}")]
 pub struct Error(String);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::jsonrpc::{Client, ClientError};
+    use futures::executor::block_on;
+
+    #[test]
+    fn params_matchers() {
+        let mut transport = MockTransport::default();
+        transport
+            .expect(Expectation::new("test_any").with_result(json!(1)))
+            .expect(
+                Expectation::new("test_partial")
+                    .with_params(Matcher::Partial(json!([{ "to": "0x01" }])))
+                    .with_result(json!(2)),
+            )
+            .expect(
+                Expectation::new("test_predicate")
+                    .with_params(Matcher::predicate(|params| params[0].as_u64() > Some(1)))
+                    .with_result(json!(3)),
+            );
+        let client = Client::new(transport);
+
+        block_on(async {
+            assert_eq!(client.call::<_, u64>("test_any", ["x"]).await.unwrap(), 1);
+            assert_eq!(
+                client
+                    .call::<_, u64>(
+                        "test_partial",
+                        json!([{ "to": "0x01", "data": "0x" }, "latest"])
+                    )
+                    .await
+                    .unwrap(),
+                2,
+            );
+            assert!(client.call::<_, u64>("test_predicate", [1]).await.is_err());
+            assert_eq!(
+                client.call::<_, u64>("test_predicate", [2]).await.unwrap(),
+                3
+            );
+        });
+    }
+
+    #[test]
+    fn unordered_and_repeated_expectations() {
+        let mut transport = MockTransport::default();
+        transport
+            .expect_call("test_first", json!([]), Ok(json!(1)))
+            .expect_call("test_second", json!([]), Ok(json!(2)))
+            .expect(
+                Expectation::new("eth_chainId")
+                    .with_result(json!("0x1"))
+                    .repeatedly(),
+            )
+            .expect(
+                Expectation::new("test_unordered")
+                    .with_result(json!(3))
+                    .times(2)
+                    .unordered(),
+            );
+        let client = Client::new(transport);
+
+        block_on(async {
+            client.call::<_, String>("eth_chainId", ()).await.unwrap();
+            client.call::<_, u64>("test_unordered", ()).await.unwrap();
+            assert!(client.call::<_, u64>("test_second", ()).await.is_err());
+            assert_eq!(
+                client.call::<_, u64>("test_first", [(); 0]).await.unwrap(),
+                1
+            );
+            client.call::<_, u64>("test_unordered", ()).await.unwrap();
+            client.call::<_, String>("eth_chainId", ()).await.unwrap();
+            assert_eq!(
+                client.call::<_, u64>("test_second", [(); 0]).await.unwrap(),
+                2
+            );
+            assert!(client.call::<_, u64>("test_unordered", ()).await.is_err());
+        });
+    }
+
+    #[test]
+    fn custom_errors() {
+        let mut transport = MockTransport::default();
+        transport.expect(
+            Expectation::new("eth_call")
+                .with_error(RpcError::new(3, "execution reverted").with_data(json!("0x"))),
+        );
+        let client = Client::new(transport);
+
+        match block_on(client.call::<_, String>("eth_call", ())) {
+            Err(ClientError::Rpc(err)) => {
+                assert_eq!(err.code, ErrorCode::Other(3));
+                assert_eq!(err.message, "execution reverted");
+                assert_eq!(err.data, Some(json!("0x")));
+            }
+            result => panic!("unexpected result {:?}", result),
+        }
+    }
+
+    #[test]
+    fn batch_requests() {
+        let mut transport = MockTransport::default();
+        transport
+            .expect_call("test_first", json!([1]), Ok(json!(1)))
+            .expect_call("test_second", json!([2]), Err("oops".to_owned()));
+        let client = Client::new(transport);
+
+        block_on(async {
+            let batch = client.batch();
+            let first = batch.call::<_, u64>("test_first", [1]);
+            let second = batch.call::<_, u64>("test_second", [2]);
+            batch.send().await.unwrap();
+
+            assert_eq!(first.await.unwrap(), 1);
+            assert!(matches!(second.await, Err(ClientError::Rpc(_))));
+        });
+    }
+
+    #[test]
+    fn failed_batches_do_not_count() {
+        let mut transport = MockTransport::default();
+        transport.expect_call("test_first", json!([1]), Ok(json!(1)));
+        let client = Client::new(transport);
+
+        block_on(async {
+            let batch = client.batch();
+            let first = batch.call::<_, u64>("test_first", [1]);
+            let unexpected = batch.call::<_, u64>("test_unexpected", [2]);
+            assert!(batch.send().await.is_err());
+            assert!(first.await.is_err());
+            assert!(unexpected.await.is_err());
+
+            assert_eq!(client.call::<_, u64>("test_first", [1]).await.unwrap(), 1);
+        });
+    }
+
+    #[test]
+    #[should_panic(expected = "unmet mock transport expectations: test_missing")]
+    fn verifies_on_drop() {
+        let mut transport = MockTransport::default();
+        transport
+            .expect_call("test_called", json!([]), Ok(json!(null)))
+            .expect_call("test_missing", json!([]), Ok(json!(null)));
+
+        let client = Client::new(transport.shared());
+        block_on(client.call::<_, ()>("test_called", [(); 0])).unwrap();
+        drop(client);
+    }
+
+    #[test]
+    fn clones_copy_expectations() {
+        let mut transport = MockTransport::default();
+        transport.expect_call("test_called", json!([]), Ok(json!(null)));
+
+        let copy = Client::new(transport.clone());
+        let shared = Client::new(transport.shared());
+        block_on(async {
+            copy.call::<_, ()>("test_called", [(); 0]).await.unwrap();
+            shared.call::<_, ()>("test_called", [(); 0]).await.unwrap();
+            assert!(shared.call::<_, ()>("test_called", [(); 0]).await.is_err());
+        });
+        transport.verify();
+    }
+}
@@ -0,0 +1,777 @@
+//! This module contains a transport that wraps multiple underlying transports
+//! to different endpoints for redundancy.
+//!
+//! In failover mode, calls are sent to one endpoint at a time in priority
+//! order, moving on to the next endpoint when a call fails or the endpoint
+//! responds with a rate limiting error. Endpoints that fail are considered
+//! unhealthy for a cooldown period, during which they are only tried once all
+//! healthy endpoints have failed.
+//!
+//! In quorum mode, calls are sent to a number of endpoints at once, in the
+//! same order as for failover, and the response that a required number of
+//! endpoints agree on is returned. Endpoints that fail or are rate limited are
+//! replaced by the next endpoint, if any. When the endpoints disagree so that
+//! the pending calls can no longer reach a quorum, calls are also sent to the
+//! remaining endpoints until a quorum is reached or there are none left.
+//! Responses are compared by their JSON value, so differences in formatting or
+//! in the order of batch responses do not matter. Responses that are not valid
+//! JSON don't agree with any other response.
+
+use crate::jsonrpc::data::{ErrorEnvelope, Messages};
+use crate::transport::{TransientError, Transport};
+use serde_json::Value;
+use std::error::Error;
+use std::fmt::{self, Display, Formatter};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Mutex, MutexGuard};
+use std::task::{ready, Context, Poll};
+use std::time::Duration;
+use thiserror::Error;
+use web_time::Instant;
+
+/// Error codes that nodes and RPC providers use for rate limiting: `-32005` is
+/// the "limit exceeded" error code from EIP-1474, and some providers use the
+/// `429 Too Many Requests` HTTP status as the error code instead.
+const RATE_LIMIT_ERROR_CODES: &[i32] = &[-32005, 429];
+
+/// A transport that sends calls to multiple endpoints, either for failover or
+/// for reaching a quorum on the response.
+#[derive(Debug)]
+pub struct Multi<T> {
+    endpoints: Vec<Endpoint<T>>,
+    mode: Mode,
+    cooldown: Duration,
+}
+
+/// The mode that a [`Multi`] transport operates in.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Mode {
+    /// Endpoints are tried one at a time in priority order.
+    Failover,
+    /// Calls are sent to `size` endpoints and the response that at least
+    /// `required` of them agree on is returned.
+    Quorum {
+        /// The number of endpoints that calls are sent to.
+        size: usize,
+        /// The number of endpoints that need to agree on the response.
+        required: usize,
+    },
+}
+
+/// An endpoint with its health.
+#[derive(Debug)]
+struct Endpoint<T> {
+    transport: T,
+    health: Mutex<Health>,
+}
+
+/// The health of an endpoint.
+#[derive(Debug, Default)]
+struct Health {
+    /// The time until which the endpoint is considered unhealthy after a
+    /// failed call.
+    unhealthy_until: Option<Instant>,
+}
+
+impl<T> Multi<T> {
+    /// Creates a new transport in failover mode. Endpoints are tried in the
+    /// order of the specified transports, so the first transport has the
+    /// highest priority.
+    ///
+    /// # Panics
+    ///
+    /// This method panics if no transports are specified.
+    pub fn failover(transports: impl IntoIterator<Item = T>) -> Self {
+        Self::new(transports, Mode::Failover)
+    }
+
+    /// Creates a new transport in quorum mode, where calls are sent to `size`
+    /// endpoints and at least `required` of them need to agree on a response.
+    ///
+    /// # Panics
+    ///
+    /// This method panics if `required` is zero or greater than `size`, or if
+    /// `size` is greater than the number of transports.
+    pub fn quorum(transports: impl IntoIterator<Item = T>, size: usize, required: usize) -> Self {
+        Self::new(transports, Mode::Quorum { size, required })
+    }
+
+    fn new(transports: impl IntoIterator<Item = T>, mode: Mode) -> Self {
+        let endpoints = transports
+            .into_iter()
+            .map(|transport| Endpoint {
+                transport,
+                health: Default::default(),
+            })
+            .collect::<Vec<_>>();
+
+        assert!(!endpoints.is_empty(), "no transports");
+        if let Mode::Quorum { size, required } = mode {
+            assert!(
+                (1..=size).contains(&required) && size <= endpoints.len(),
+                "invalid quorum of {} out of {} for {} transports",
+                required,
+                size,
+                endpoints.len(),
+            );
+        }
+
+        Multi {
+            endpoints,
+            mode,
+            cooldown: Duration::from_secs(30),
+        }
+    }
+
+    /// Sets the period during which an endpoint is considered unhealthy after
+    /// a failed call. Defaults to 30 seconds.
+    pub fn with_cooldown(mut self, cooldown: Duration) -> Self {
+        self.cooldown = cooldown;
+        self
+    }
+
+    /// Returns the mode of the transport.
+    pub fn mode(&self) -> Mode {
+        self.mode
+    }
+
+    /// Returns an iterator over the underlying transports in priority order.
+    pub fn transports(&self) -> impl Iterator<Item = &T> {
+        self.endpoints.iter().map(|endpoint| &endpoint.transport)
+    }
+
+    /// Returns `true` if the endpoint at the specified index is currently
+    /// considered healthy.
+    ///
+    /// # Panics
+    ///
+    /// This method panics if the index is out of bounds.
+    pub fn is_healthy(&self, index: usize) -> bool {
+        self.endpoints[index].is_healthy(Instant::now())
+    }
+
+    /// Returns the indices of the endpoints in the order they should be tried
+    /// for failover, with healthy endpoints first.
+    fn failover_order(&self) -> Vec<usize> {
+        let now = Instant::now();
+        let (mut healthy, unhealthy) = (0..self.endpoints.len())
+            .partition::<Vec<_>, _>(|&index| self.endpoints[index].is_healthy(now));
+        healthy.extend(unhealthy);
+        healthy
+    }
+}
+
+impl<T> Endpoint<T> {
+    fn health(&self) -> MutexGuard<'_, Health> {
+        self.health.lock().expect("poisoned endpoint health")
+    }
+
+    fn is_healthy(&self, now: Instant) -> bool {
+        match self.health().unhealthy_until {
+            Some(until) => now >= until,
+            None => true,
+        }
+    }
+
+    fn record(&self, healthy: bool, cooldown: Duration) {
+        self.health().unhealthy_until = match healthy {
+            true => None,
+            false => Some(Instant::now() + cooldown),
+        };
+    }
+}
+
+/// Returns `true` if the response is a rate limiting error response, or a
+/// batch response containing one.
+fn is_rate_limited(response: &[u8]) -> bool {
+    match serde_json::from_slice::<Messages<ErrorEnvelope>>(response) {
+        Ok(responses) => responses.iter().any(|response| match &response.error {
+            Some(error) => RATE_LIMIT_ERROR_CODES.contains(&error.code),
+            None => false,
+        }),
+        Err(_) => false,
+    }
+}
+
+impl<T> Transport for Multi<T>
+where
+    T: Transport,
+{
+    type Error = MultiError<T::Error>;
+    type Call<'a>
+        = Call<'a, T>
+    where
+        T: 'a;
+
+    fn call<'a>(&'a self, request: &'a [u8]) -> Self::Call<'a> {
+        let mut order = self.failover_order().into_iter();
+        let inner = match self.mode {
+            Mode::Failover => Inner::Failover { call: None },
+            Mode::Quorum { size, required } => Inner::Quorum {
+                required,
+                calls: order
+                    .by_ref()
+                    .take(size)
+                    .map(|index| {
+                        (
+                            index,
+                            Box::pin(self.endpoints[index].transport.call(request)),
+                        )
+                    })
+                    .collect(),
+                responses: Vec::new(),
+            },
+        };
+
+        Call {
+            multi: self,
+            request,
+            order,
+            inner,
+            errors: Vec::new(),
+            limited: None,
+        }
+    }
+}
+
+/// Future returned by [`Multi`] calls.
+pub struct Call<'a, T>
+where
+    T: Transport + 'a,
+{
+    multi: &'a Multi<T>,
+    request: &'a [u8],
+    /// The endpoints that were not yet tried, in failover order.
+    order: std::vec::IntoIter<usize>,
+    inner: Inner<'a, T>,
+    /// The transport errors, boxed so that the call is `Unpin` regardless of
+    /// the transport error type.
+    errors: Vec<Box<T::Error>>,
+    /// The last rate limiting error response, returned if no endpoint
+    /// succeeds.
+    limited: Option<Vec<u8>>,
+}
+
+/// The mode specific state of a call.
+enum Inner<'a, T>
+where
+    T: Transport + 'a,
+{
+    Failover {
+        call: Option<(usize, Pin<Box<T::Call<'a>>>)>,
+    },
+    Quorum {
+        required: usize,
+        calls: Vec<(usize, Pin<Box<T::Call<'a>>>)>,
+        responses: Vec<Agreement>,
+    },
+}
+
+/// A response along with the number of endpoints that agree on it. Responses
+/// that are not valid JSON have no value and never agree with any other
+/// response.
+struct Agreement {
+    value: Option<Value>,
+    response: Vec<u8>,
+    count: usize,
+}
+
+impl<T> Future for Call<'_, T>
+where
+    T: Transport,
+{
+    type Output = Result<Vec<u8>, MultiError<T::Error>>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        let Call {
+            multi,
+            request,
+            order,
+            inner,
+            errors,
+            limited,
+        } = &mut *self;
+
+        match inner {
+            Inner::Failover { call } => loop {
+                let (index, pending) = match call {
+                    Some(call) => call,
+                    None => match order.next() {
+                        Some(index) => {
+                            let pending = multi.endpoints[index].transport.call(request);
+                            call.insert((index, Box::pin(pending)))
+                        }
+                        None => return Poll::Ready(failed(errors, limited)),
+                    },
+                };
+
+                let result = ready!(pending.as_mut().poll(cx));
+                let endpoint = &multi.endpoints[*index];
+                *call = None;
+                match result {
+                    Ok(response) if is_rate_limited(&response) => {
+                        endpoint.record(false, multi.cooldown);
+                        *limited = Some(response);
+                    }
+                    Ok(response) => {
+                        endpoint.record(true, multi.cooldown);
+                        return Poll::Ready(Ok(response));
+                    }
+                    Err(err) => {
+                        endpoint.record(false, multi.cooldown);
+                        errors.push(Box::new(err));
+                    }
+                }
+            },
+            Inner::Quorum {
+                required,
+                calls,
+                responses,
+            } => loop {
+                // NOTE: Replacement calls are pushed to the end of the list, so
+                // they get polled in the same pass.
+                let mut i = 0;
+                while i < calls.len() {
+                    let result = match calls[i].1.as_mut().poll(cx) {
+                        Poll::Ready(result) => result,
+                        Poll::Pending => {
+                            i += 1;
+                            continue;
+                        }
+                    };
+                    let (index, _) = calls.swap_remove(i);
+                    let endpoint = &multi.endpoints[index];
+
+                    let response = match result {
+                        Ok(response) if is_rate_limited(&response) => {
+                            *limited = Some(response);
+                            None
+                        }
+                        Ok(response) => Some(response),
+                        Err(err) => {
+                            errors.push(Box::new(err));
+                            None
+                        }
+                    };
+                    let response = match response {
+                        Some(response) => response,
+                        None => {
+                            endpoint.record(false, multi.cooldown);
+                            if let Some(index) = order.next() {
+                                let pending = multi.endpoints[index].transport.call(request);
+                                calls.push((index, Box::pin(pending)));
+                            }
+                            continue;
+                        }
+                    };
+
+                    let value = serde_json::from_slice(&response).ok().map(normalize);
+                    endpoint.record(value.is_some(), multi.cooldown);
+                    match responses
+                        .iter_mut()
+                        .find(|agreement| agreement.value.is_some() && agreement.value == value)
+                    {
+                        Some(agreement) => agreement.count += 1,
+                        None => responses.push(Agreement {
+                            value,
+                            response,
+                            count: 1,
+                        }),
+                    }
+                }
+
+                let best = responses
+                    .iter()
+                    .filter(|agreement| agreement.value.is_some())
+                    .map(|agreement| agreement.count)
+                    .max()
+                    .unwrap_or(0);
+                if best >= *required {
+                    let index = responses
+                        .iter()
+                        .position(|agreement| agreement.value.is_some() && agreement.count == best)
+                        .expect("missing best response");
+                    return Poll::Ready(Ok(responses.swap_remove(index).response));
+                }
+                if best + calls.len() >= *required {
+                    return Poll::Pending;
+                }
+                if best + calls.len() + order.len() < *required {
+                    return match responses.is_empty() {
+                        true => Poll::Ready(failed(errors, limited)),
+                        false => Poll::Ready(Err(MultiError::NoQuorum)),
+                    };
+                }
+
+                // NOTE: The endpoints disagreed, so escalate to just enough of
+                // the remaining endpoints for a quorum to still be reachable,
+                // and poll the new calls.
+                let missing = *required - best - calls.len();
+                calls.extend(order.by_ref().take(missing).map(|index| {
+                    let pending = multi.endpoints[index].transport.call(request);
+                    (index, Box::pin(pending))
+                }));
+            },
+        }
+    }
+}
+
+/// Returns the result of a call for which no endpoint succeeded. This is the
+/// last rate limiting error response if there was one, so that it can be
+/// handled like any other RPC error, and the transport errors otherwise.
+fn failed<E>(
+    errors: &mut Vec<Box<E>>,
+    limited: &mut Option<Vec<u8>>,
+) -> Result<Vec<u8>, MultiError<E>>
+where
+    E: Error,
+{
+    match limited.take() {
+        Some(response) => Ok(response),
+        None => Err(MultiError::Failed(
+            errors.drain(..).map(|error| *error).collect(),
+        )),
+    }
+}
+
+/// Normalizes a response value for comparison. Nodes may respond to batches
+/// in any order, so batch responses are sorted by their ID.
+fn normalize(value: Value) -> Value {
+    match value {
+        Value::Array(mut responses) => {
+            responses.sort_by_key(|response| response["id"].to_string());
+            Value::Array(responses)
+        }
+        value => value,
+    }
+}
+
+/// An error performing a call with a [`Multi`] transport.
+#[derive(Debug, Error)]
+pub enum MultiError<E>
+where
+    E: Error,
+{
+    /// All endpoints failed, with the error for each endpoint that was tried.
+    #[error("all endpoints failed: {}", Errors(.0))]
+    Failed(Vec<E>),
+    /// Not enough endpoints agreed on the response for a quorum.
+    #[error("endpoints did not agree on a response")]
+    NoQuorum,
+}
+
+impl<E> TransientError for MultiError<E>
+where
+    E: TransientError,
+{
+    /// Calls are considered transient if any of the endpoints failed with a
+    /// transient error or if the endpoints did not agree on a response, as
+    /// they may have been out of sync.
+    fn is_transient(&self) -> bool {
+        match self {
+            MultiError::Failed(errors) => errors.iter().any(E::is_transient),
+            MultiError::NoQuorum => true,
+        }
+    }
+}
+
+/// Helper for displaying a list of errors.
+struct Errors<'a, E>(&'a [E]);
+
+impl<E> Display for Errors<'_, E>
+where
+    E: Display,
+{
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        for (i, error) in self.0.iter().enumerate() {
+            if i > 0 {
+                f.write_str("; ")?;
+            }
+            Display::fmt(error, f)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::executor::block_on;
+    use serde_json::json;
+    use std::collections::VecDeque;
+    use std::future::Ready;
+
+    #[derive(Debug, Error)]
+    #[error("endpoint down")]
+    struct Down;
+
+    /// A transport that returns scripted results and counts the calls.
+    struct Script(Mutex<ScriptState>);
+
+    struct ScriptState {
+        results: VecDeque<Result<Value, Down>>,
+        calls: usize,
+    }
+
+    impl Script {
+        fn new(results: impl IntoIterator<Item = Result<Value, Down>>) -> Self {
+            Script(Mutex::new(ScriptState {
+                results: results.into_iter().collect(),
+                calls: 0,
+            }))
+        }
+
+        fn calls(&self) -> usize {
+            self.0.lock().unwrap().calls
+        }
+    }
+
+    impl Transport for Script {
+        type Error = Down;
+        type Call<'a> = Ready<Result<Vec<u8>, Down>>;
+
+        fn call<'a>(&'a self, _: &'a [u8]) -> Self::Call<'a> {
+            let mut script = self.0.lock().unwrap();
+            script.calls += 1;
+            let result = script.results.pop_front().unwrap();
+            std::future::ready(result.map(|value| value.to_string().into_bytes()))
+        }
+    }
+
+    fn calls(multi: &Multi<Script>) -> Vec<usize> {
+        multi.transports().map(Script::calls).collect()
+    }
+
+    fn parse(response: Vec<u8>) -> Value {
+        serde_json::from_slice(&response).unwrap()
+    }
+
+    #[test]
+    fn fails_over_in_priority_order() {
+        let multi = Multi::failover(vec![
+            Script::new(vec![Err(Down)]),
+            Script::new(vec![Ok(json!(1)), Ok(json!(2))]),
+            Script::new(vec![]),
+        ]);
+
+        assert_eq!(parse(block_on(multi.call(b"")).unwrap()), json!(1));
+        assert_eq!(calls(&multi), [1, 1, 0]);
+        assert!(!multi.is_healthy(0));
+        assert!(multi.is_healthy(1));
+
+        // NOTE: The first endpoint is unhealthy, so it gets skipped.
+        assert_eq!(parse(block_on(multi.call(b"")).unwrap()), json!(2));
+        assert_eq!(calls(&multi), [1, 2, 0]);
+    }
+
+    #[test]
+    fn retries_unhealthy_endpoints() {
+        let multi = Multi::failover(vec![
+            Script::new(vec![Err(Down), Ok(json!(1))]),
+            Script::new(vec![Err(Down), Ok(json!(2))]),
+        ]);
+        assert!(matches!(
+            block_on(multi.call(b"")),
+            Err(MultiError::Failed(errors)) if errors.len() == 2,
+        ));
+
+        // NOTE: All endpoints are unhealthy, so they are tried in order.
+        assert_eq!(parse(block_on(multi.call(b"")).unwrap()), json!(1));
+        assert_eq!(calls(&multi), [2, 1]);
+
+        let multi = Multi::failover(vec![
+            Script::new(vec![Err(Down), Ok(json!(1))]),
+            Script::new(vec![Ok(json!(2))]),
+        ])
+        .with_cooldown(Duration::from_secs(0));
+        assert_eq!(parse(block_on(multi.call(b"")).unwrap()), json!(2));
+        assert!(multi.is_healthy(0));
+        assert_eq!(parse(block_on(multi.call(b"")).unwrap()), json!(1));
+    }
+
+    #[test]
+    fn reaches_quorum() {
+        let multi = Multi::quorum(
+            vec![
+                Script::new(vec![Ok(json!({ "id": 0, "result": "0x1" }))]),
+                Script::new(vec![Ok(json!({ "id": 0, "result": "0x2" }))]),
+                Script::new(vec![Ok(json!({ "result": "0x2", "id": 0 }))]),
+            ],
+            3,
+            2,
+        );
+
+        assert_eq!(
+            parse(block_on(multi.call(b"")).unwrap()),
+            json!({ "id": 0, "result": "0x2" }),
+        );
+        assert_eq!(calls(&multi), [1, 1, 1]);
+    }
+
+    #[test]
+    fn compares_batches_regardless_of_order() {
+        let multi = Multi::quorum(
+            vec![
+                Script::new(vec![Ok(json!([{ "id": 0 }, { "id": 1 }]))]),
+                Script::new(vec![Ok(json!([{ "id": 1 }, { "id": 0 }]))]),
+            ],
+            2,
+            2,
+        );
+
+        assert!(block_on(multi.call(b"")).is_ok());
+    }
+
+    #[test]
+    fn fails_without_quorum() {
+        let multi = Multi::quorum(
+            vec![
+                Script::new(vec![Ok(json!(1))]),
+                Script::new(vec![Ok(json!(2))]),
+                Script::new(vec![Err(Down)]),
+            ],
+            3,
+            2,
+        );
+        assert!(matches!(
+            block_on(multi.call(b"")),
+            Err(MultiError::NoQuorum)
+        ));
+
+        let multi = Multi::quorum(
+            vec![Script::new(vec![Err(Down)]), Script::new(vec![Err(Down)])],
+            2,
+            1,
+        );
+        assert!(matches!(
+            block_on(multi.call(b"")),
+            Err(MultiError::Failed(errors)) if errors.len() == 2,
+        ));
+    }
+
+    #[test]
+    fn sends_to_quorum_size_endpoints() {
+        let multi = Multi::quorum(
+            vec![
+                Script::new(vec![Ok(json!(1))]),
+                Script::new(vec![Err(Down)]),
+                Script::new(vec![Ok(json!(1))]),
+                Script::new(vec![]),
+            ],
+            2,
+            2,
+        );
+
+        // NOTE: The failed endpoint gets replaced by the next one.
+        assert_eq!(parse(block_on(multi.call(b"")).unwrap()), json!(1));
+        assert_eq!(calls(&multi), [1, 1, 1, 0]);
+        assert!(!multi.is_healthy(1));
+    }
+
+    #[test]
+    fn escalates_to_remaining_endpoints() {
+        let multi = Multi::quorum(
+            vec![
+                Script::new(vec![Ok(json!(1))]),
+                Script::new(vec![Ok(json!(2))]),
+                Script::new(vec![Ok(json!(2))]),
+                Script::new(vec![]),
+            ],
+            2,
+            2,
+        );
+
+        // NOTE: Only one more endpoint is needed to reach the quorum.
+        assert_eq!(parse(block_on(multi.call(b"")).unwrap()), json!(2));
+        assert_eq!(calls(&multi), [1, 1, 1, 0]);
+
+        let multi = Multi::quorum(
+            vec![
+                Script::new(vec![Ok(json!(1))]),
+                Script::new(vec![Ok(json!(2))]),
+                Script::new(vec![Ok(json!(3))]),
+            ],
+            2,
+            2,
+        );
+        assert!(matches!(
+            block_on(multi.call(b"")),
+            Err(MultiError::NoQuorum)
+        ));
+        assert_eq!(calls(&multi), [1, 1, 1]);
+    }
+
+    #[test]
+    fn invalid_responses_disagree() {
+        /// A transport that responds with invalid JSON.
+        struct Invalid;
+
+        impl Transport for Invalid {
+            type Error = Down;
+            type Call<'a> = Ready<Result<Vec<u8>, Down>>;
+
+            fn call<'a>(&'a self, _: &'a [u8]) -> Self::Call<'a> {
+                std::future::ready(Ok(b"invalid".to_vec()))
+            }
+        }
+
+        let multi = Multi::quorum(vec![Invalid, Invalid], 2, 1);
+        assert!(matches!(
+            block_on(multi.call(b"")),
+            Err(MultiError::NoQuorum)
+        ));
+        assert!(!multi.is_healthy(0));
+        assert!(!multi.is_healthy(1));
+    }
+
+    #[test]
+    fn fails_over_rate_limited_endpoints() {
+        let limited = || Ok(json!({ "id": 0, "error": { "code": -32005, "message": "limit" } }));
+
+        let multi = Multi::failover(vec![
+            Script::new(vec![limited()]),
+            Script::new(vec![Ok(json!({ "id": 0, "result": "0x1" }))]),
+        ]);
+        assert_eq!(
+            parse(block_on(multi.call(b"")).unwrap()),
+            json!({ "id": 0, "result": "0x1" }),
+        );
+        assert!(!multi.is_healthy(0));
+
+        // NOTE: When all endpoints are rate limited, the rate limiting error
+        // response is returned.
+        let multi = Multi::failover(vec![
+            Script::new(vec![limited()]),
+            Script::new(vec![Err(Down)]),
+        ]);
+        assert_eq!(
+            parse(block_on(multi.call(b"")).unwrap()),
+            limited().unwrap()
+        );
+
+        let multi = Multi::quorum(
+            vec![
+                Script::new(vec![Ok(json!(1))]),
+                Script::new(vec![limited()]),
+                Script::new(vec![Ok(json!(1))]),
+            ],
+            2,
+            2,
+        );
+        assert_eq!(parse(block_on(multi.call(b"")).unwrap()), json!(1));
+        assert_eq!(calls(&multi), [1, 1, 1]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn invalid_quorum() {
+        Multi::quorum(vec![Script::new(vec![])], 1, 2);
+    }
+
+    #[test]
+    #[should_panic]
+    fn invalid_quorum_size() {
+        Multi::quorum(vec![Script::new(vec![])], 2, 1);
+    }
+}
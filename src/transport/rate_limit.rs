@@ -0,0 +1,421 @@
+//! This module contains a transport wrapper that shapes the calls sent to the
+//! underlying transport, limiting both the rate of requests and the number of
+//! requests that are in flight at the same time.
+//!
+//! The request rate is limited with a token bucket, where each call consumes
+//! tokens according to the weights of the methods it calls. This allows
+//! matching the "compute units" that hosted node providers use for billing and
+//! throttling, where expensive methods such as `eth_getLogs` count for more
+//! than cheap ones such as `eth_chainId`.
+
+use crate::jsonrpc::data::{Messages, MethodEnvelope};
+use crate::transport::Transport;
+use futures_timer::Delay;
+use std::collections::HashMap;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Mutex, MutexGuard};
+use std::task::{ready, Context, Poll, Waker};
+use std::time::Duration;
+use web_time::Instant;
+
+/// A transport wrapper that limits the rate of requests and the number of
+/// requests in flight.
+#[derive(Debug)]
+pub struct RateLimit<T> {
+    transport: T,
+    bucket: Option<Bucket>,
+    max_in_flight: Option<usize>,
+    weights: HashMap<String, u32>,
+    usage: Mutex<Usage>,
+}
+
+/// Token bucket parameters.
+#[derive(Clone, Copy, Debug)]
+struct Bucket {
+    rate: f64,
+    capacity: f64,
+}
+
+/// The current usage of the limits.
+#[derive(Debug)]
+struct Usage {
+    /// The available tokens in the bucket. This can be negative when calls
+    /// have reserved tokens ahead of time and are waiting for them.
+    tokens: f64,
+    /// The time the tokens were last updated.
+    updated: Instant,
+    /// The number of calls currently in flight.
+    in_flight: usize,
+    /// Tasks waiting for a call to complete so that they can be sent.
+    waiters: Vec<Waker>,
+}
+
+impl<T> RateLimit<T> {
+    /// Creates a new transport wrapper without any limits.
+    pub fn new(transport: T) -> Self {
+        RateLimit {
+            transport,
+            bucket: None,
+            max_in_flight: None,
+            weights: HashMap::new(),
+            usage: Mutex::new(Usage {
+                tokens: 0.0,
+                updated: Instant::now(),
+                in_flight: 0,
+                waiters: Vec::new(),
+            }),
+        }
+    }
+
+    /// Limits the request rate to `rate` tokens per second, allowing bursts of
+    /// up to `burst` tokens. Calls consume one token per request by default,
+    /// see [`RateLimit::with_method_weight`].
+    ///
+    /// # Panics
+    ///
+    /// This method panics if the rate or burst are not positive.
+    pub fn with_rate(mut self, rate: f64, burst: u32) -> Self {
+        assert!(rate > 0.0 && burst > 0, "invalid rate limit");
+        let capacity = f64::from(burst);
+        self.bucket = Some(Bucket { rate, capacity });
+
+        let usage = self.usage.get_mut().expect("poisoned rate limit usage");
+        usage.tokens = capacity;
+        usage.updated = Instant::now();
+        self
+    }
+
+    /// Limits the number of calls in flight at the same time.
+    ///
+    /// # Panics
+    ///
+    /// This method panics if the maximum is zero.
+    pub fn with_max_in_flight(mut self, max_in_flight: usize) -> Self {
+        assert!(max_in_flight > 0, "invalid maximum calls in flight");
+        self.max_in_flight = Some(max_in_flight);
+        self
+    }
+
+    /// Sets the number of tokens that requests for the specified method
+    /// consume. Batch requests consume the sum of the weights of their
+    /// requests.
+    pub fn with_method_weight(mut self, method: impl Into<String>, weight: u32) -> Self {
+        self.weights.insert(method.into(), weight);
+        self
+    }
+
+    /// Returns a reference to the underlying transport.
+    pub fn transport(&self) -> &T {
+        &self.transport
+    }
+
+    /// Returns the weight of a request. Requests that can't be inspected have a
+    /// weight of 1.
+    fn weight(&self, request: &[u8]) -> u32 {
+        match serde_json::from_slice::<Messages<MethodEnvelope>>(request) {
+            Ok(requests) => requests
+                .iter()
+                .map(|request| self.weights.get(&request.method).copied().unwrap_or(1))
+                .fold(0, u32::saturating_add),
+            Err(_) => 1,
+        }
+    }
+
+    /// Reserves tokens for a call, returning how long the call needs to wait
+    /// for the reserved tokens to become available.
+    ///
+    /// Since tokens are reserved ahead of time, calls are sent in the order
+    /// they reserved their tokens. Calls that weigh more than the capacity of
+    /// the bucket wait until the bucket would be full.
+    fn reserve(&self, weight: u32, now: Instant) -> Option<Duration> {
+        let bucket = self.bucket?;
+        let mut usage = self.usage();
+
+        let elapsed = now.duration_since(usage.updated).as_secs_f64();
+        usage.tokens = (usage.tokens + elapsed * bucket.rate).min(bucket.capacity);
+        usage.updated = now;
+
+        usage.tokens -= f64::from(weight).min(bucket.capacity);
+        if usage.tokens >= 0.0 {
+            None
+        } else {
+            Some(Duration::from_secs_f64(-usage.tokens / bucket.rate))
+        }
+    }
+
+    /// Returns the tokens reserved by a call that was cancelled before it was
+    /// sent back to the bucket.
+    fn refund(&self, weight: u32) {
+        if let Some(bucket) = self.bucket {
+            let mut usage = self.usage();
+            usage.tokens =
+                (usage.tokens + f64::from(weight).min(bucket.capacity)).min(bucket.capacity);
+        }
+    }
+
+    /// Attempts to acquire a permit for sending a call.
+    fn poll_acquire(&self, cx: &mut Context) -> Poll<Permit<'_, T>> {
+        let max_in_flight = match self.max_in_flight {
+            Some(max_in_flight) => max_in_flight,
+            None => return Poll::Ready(Permit(None)),
+        };
+
+        let mut usage = self.usage();
+        if usage.in_flight < max_in_flight {
+            usage.in_flight += 1;
+            Poll::Ready(Permit(Some(self)))
+        } else {
+            if !usage
+                .waiters
+                .iter()
+                .any(|waker| waker.will_wake(cx.waker()))
+            {
+                usage.waiters.push(cx.waker().clone());
+            }
+            Poll::Pending
+        }
+    }
+
+    fn usage(&self) -> MutexGuard<'_, Usage> {
+        self.usage.lock().expect("poisoned rate limit usage")
+    }
+}
+
+impl<T> Transport for RateLimit<T>
+where
+    T: Transport,
+{
+    type Error = T::Error;
+    type Call<'a>
+        = Call<'a, T>
+    where
+        T: 'a;
+
+    fn call<'a>(&'a self, request: &'a [u8]) -> Self::Call<'a> {
+        Call {
+            limit: self,
+            request,
+            state: State::Start,
+        }
+    }
+}
+
+/// A permit for a call that is in flight. Dropping the permit lets the next
+/// waiting call be sent.
+struct Permit<'a, T>(Option<&'a RateLimit<T>>);
+
+impl<T> Drop for Permit<'_, T> {
+    fn drop(&mut self) {
+        if let Some(limit) = self.0 {
+            let mut usage = limit.usage();
+            usage.in_flight -= 1;
+            for waker in usage.waiters.drain(..) {
+                waker.wake();
+            }
+        }
+    }
+}
+
+/// Future returned by [`RateLimit`] calls.
+pub struct Call<'a, T>
+where
+    T: Transport + 'a,
+{
+    limit: &'a RateLimit<T>,
+    request: &'a [u8],
+    state: State<'a, T>,
+}
+
+/// The state of a rate limited call.
+enum State<'a, T>
+where
+    T: Transport + 'a,
+{
+    /// The call has not reserved its tokens yet.
+    Start,
+    /// The call is waiting for its reserved tokens to become available.
+    Throttled { delay: Delay, weight: u32 },
+    /// The call is waiting for other calls to complete.
+    Queued,
+    /// The call is in flight, holding its permit until it completes.
+    Calling {
+        _permit: Permit<'a, T>,
+        call: Pin<Box<T::Call<'a>>>,
+    },
+}
+
+impl<T> Future for Call<'_, T>
+where
+    T: Transport,
+{
+    type Output = Result<Vec<u8>, T::Error>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        let this = &mut *self;
+        loop {
+            match &mut this.state {
+                State::Start => {
+                    let weight = this.limit.weight(this.request);
+                    this.state = match this.limit.reserve(weight, Instant::now()) {
+                        Some(wait) => State::Throttled {
+                            delay: Delay::new(wait),
+                            weight,
+                        },
+                        None => State::Queued,
+                    };
+                }
+                State::Throttled { delay, .. } => {
+                    ready!(Pin::new(delay).poll(cx));
+                    this.state = State::Queued;
+                }
+                State::Queued => {
+                    let permit = ready!(this.limit.poll_acquire(cx));
+                    let call = this.limit.transport.call(this.request);
+                    this.state = State::Calling {
+                        _permit: permit,
+                        call: Box::pin(call),
+                    };
+                }
+                State::Calling { call, .. } => return call.as_mut().poll(cx),
+            }
+        }
+    }
+}
+
+impl<T> Drop for Call<'_, T>
+where
+    T: Transport,
+{
+    fn drop(&mut self) {
+        // NOTE: Calls that are cancelled while throttled never use their
+        // reserved tokens, so give them back to the calls that come after.
+        if let State::Throttled { weight, .. } = self.state {
+            self.limit.refund(weight);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::executor::block_on;
+    use futures::future;
+    use futures::FutureExt as _;
+    use std::convert::Infallible;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// A transport with calls that yield once before completing, recording
+    /// the maximum number of concurrent calls.
+    #[derive(Default)]
+    struct Concurrency {
+        current: AtomicUsize,
+        max: AtomicUsize,
+    }
+
+    impl Transport for Concurrency {
+        type Error = Infallible;
+        type Call<'a> = Pin<Box<dyn Future<Output = Result<Vec<u8>, Infallible>> + 'a>>;
+
+        fn call<'a>(&'a self, _: &'a [u8]) -> Self::Call<'a> {
+            Box::pin(async move {
+                let current = self.current.fetch_add(1, Ordering::SeqCst) + 1;
+                self.max.fetch_max(current, Ordering::SeqCst);
+
+                let mut yielded = false;
+                future::poll_fn(|cx| {
+                    if yielded {
+                        Poll::Ready(())
+                    } else {
+                        yielded = true;
+                        cx.waker().wake_by_ref();
+                        Poll::Pending
+                    }
+                })
+                .await;
+
+                self.current.fetch_sub(1, Ordering::SeqCst);
+                Ok(Vec::new())
+            })
+        }
+    }
+
+    fn max_concurrency(limit: RateLimit<Concurrency>) -> usize {
+        block_on(future::join_all((0..4).map(|_| limit.call(b""))));
+        limit.transport().max.load(Ordering::SeqCst)
+    }
+
+    #[test]
+    fn limits_calls_in_flight() {
+        assert_eq!(max_concurrency(RateLimit::new(Concurrency::default())), 4);
+        assert_eq!(
+            max_concurrency(RateLimit::new(Concurrency::default()).with_max_in_flight(2)),
+            2,
+        );
+    }
+
+    #[test]
+    fn method_weights() {
+        let limit = RateLimit::new(()).with_method_weight("eth_getLogs", 10);
+
+        assert_eq!(limit.weight(br#"{"method":"eth_chainId"}"#), 1);
+        assert_eq!(limit.weight(br#"{"method":"eth_getLogs"}"#), 10);
+        assert_eq!(
+            limit.weight(br#"[{"method":"eth_chainId"},{"method":"eth_getLogs"}]"#),
+            11,
+        );
+        assert_eq!(limit.weight(b"invalid"), 1);
+    }
+
+    #[test]
+    fn reserves_tokens() {
+        let limit = RateLimit::new(()).with_rate(1.0, 10);
+        let start = limit.usage().updated;
+
+        assert_eq!(limit.reserve(4, start), None);
+        assert_eq!(limit.reserve(6, start), None);
+        assert_eq!(limit.reserve(2, start), Some(Duration::from_secs(2)));
+
+        // NOTE: Calls that weigh more than the bucket capacity wait for the
+        // bucket to be full, after the previously reserved tokens.
+        assert_eq!(limit.reserve(100, start), Some(Duration::from_secs(12)));
+
+        // Tokens are refilled at the configured rate.
+        let later = start + Duration::from_secs(5);
+        assert_eq!(limit.reserve(1, later), Some(Duration::from_secs(8)));
+    }
+
+    #[test]
+    fn refunds_cancelled_reservations() {
+        let limit = RateLimit::new(()).with_rate(1.0, 10);
+        let start = limit.usage().updated;
+
+        assert_eq!(limit.reserve(10, start), None);
+        assert_eq!(limit.reserve(4, start), Some(Duration::from_secs(4)));
+        limit.refund(4);
+        assert_eq!(limit.reserve(2, start), Some(Duration::from_secs(2)));
+    }
+
+    #[test]
+    fn throttles_calls() {
+        // NOTE: The rate is slow enough that throttled calls can never become
+        // ready while the test runs.
+        let limit = RateLimit::new(Concurrency::default()).with_rate(0.01, 1);
+
+        let mut first = limit.call(b"");
+        assert!(block_on(&mut first).is_ok());
+
+        let mut second = limit.call(b"");
+        assert!((&mut second).now_or_never().is_none());
+        assert!(matches!(second.state, State::Throttled { weight: 1, .. }));
+        assert!(limit.usage().tokens < 0.0);
+
+        // Dropping a throttled call returns its tokens, so the next call takes
+        // its place instead of waiting behind it.
+        drop(second);
+        assert!(limit.usage().tokens >= 0.0);
+        let mut third = limit.call(b"");
+        assert!((&mut third).now_or_never().is_none());
+        assert!(limit.usage().tokens > -1.5);
+    }
+}
@@ -0,0 +1,406 @@
+//! This module contains transports for recording calls made over a real
+//! transport to a fixture file, and for replaying them in tests without any
+//! network access.
+//!
+//! Fixtures are JSON lines files, with one `{"request":...,"response":...}`
+//! object per call. Calls are replayed by matching the methods and parameters
+//! of requests, ignoring their IDs, so that fixtures can be replayed with
+//! clients that generate different request IDs than the ones recorded.
+
+use crate::transport::{TransientError, Transport};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::{HashMap, VecDeque};
+use std::error::Error;
+use std::fs::{File, OpenOptions};
+use std::future::{self, Future, Ready};
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+use std::pin::Pin;
+use std::sync::{Mutex, MutexGuard};
+use std::task::{ready, Context, Poll};
+use thiserror::Error;
+
+/// A recorded call.
+#[derive(Debug, Deserialize, Serialize)]
+struct Entry {
+    request: Value,
+    /// The response, or `null` for calls without a response, such as
+    /// notifications.
+    response: Value,
+}
+
+/// A transport wrapper that records every call made over the underlying
+/// transport to a fixture file.
+///
+/// Only calls that received a response are recorded, including responses with
+/// RPC errors. Calls that failed with a transport error have no response to
+/// replay, so they are not recorded and replaying them fails.
+///
+/// Recorded calls are buffered in memory and written to the fixture file when
+/// the buffer is full, when the transport is dropped, or when calling
+/// [`Record::flush`].
+#[derive(Debug)]
+pub struct Record<T> {
+    transport: T,
+    fixture: Mutex<BufWriter<File>>,
+}
+
+impl<T> Record<T> {
+    /// Creates a new recording transport, writing to the fixture file at the
+    /// specified path. An existing fixture file is overwritten.
+    pub fn create(transport: T, path: impl AsRef<Path>) -> io::Result<Self> {
+        Ok(Record {
+            transport,
+            fixture: Mutex::new(BufWriter::new(File::create(path)?)),
+        })
+    }
+
+    /// Creates a new recording transport, appending to the fixture file at the
+    /// specified path.
+    pub fn append(transport: T, path: impl AsRef<Path>) -> io::Result<Self> {
+        let fixture = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(Record {
+            transport,
+            fixture: Mutex::new(BufWriter::new(fixture)),
+        })
+    }
+
+    /// Returns a reference to the underlying transport.
+    pub fn transport(&self) -> &T {
+        &self.transport
+    }
+
+    /// Writes the buffered calls to the fixture file.
+    pub fn flush(&self) -> io::Result<()> {
+        self.fixture().flush()
+    }
+
+    /// Writes a call to the fixture file buffer.
+    fn write(&self, request: &[u8], response: &[u8]) -> io::Result<()> {
+        let entry = Entry {
+            request: serde_json::from_slice(request)?,
+            response: match response {
+                [] => Value::Null,
+                response => serde_json::from_slice(response)?,
+            },
+        };
+
+        // NOTE: Buffer complete lines with a single write, so that only whole
+        // lines are written and the fixture stays valid even when the process
+        // exits while recording.
+        let mut line = serde_json::to_vec(&entry)?;
+        line.push(b'\n');
+        self.fixture().write_all(&line)
+    }
+
+    fn fixture(&self) -> MutexGuard<'_, BufWriter<File>> {
+        self.fixture.lock().expect("poisoned fixture file")
+    }
+}
+
+impl<T> Transport for Record<T>
+where
+    T: Transport,
+{
+    type Error = RecordError<T::Error>;
+    type Call<'a>
+        = RecordCall<'a, T>
+    where
+        T: 'a;
+
+    fn call<'a>(&'a self, request: &'a [u8]) -> Self::Call<'a> {
+        RecordCall {
+            record: self,
+            request,
+            call: Box::pin(self.transport.call(request)),
+        }
+    }
+}
+
+/// Future returned by [`Record`] calls.
+pub struct RecordCall<'a, T>
+where
+    T: Transport + 'a,
+{
+    record: &'a Record<T>,
+    request: &'a [u8],
+    call: Pin<Box<T::Call<'a>>>,
+}
+
+impl<T> Future for RecordCall<'_, T>
+where
+    T: Transport,
+{
+    type Output = Result<Vec<u8>, RecordError<T::Error>>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        let response = ready!(self.call.as_mut().poll(cx)).map_err(RecordError::Transport)?;
+        self.record.write(self.request, &response)?;
+        Poll::Ready(Ok(response))
+    }
+}
+
+/// An error performing a call with a [`Record`] transport.
+#[derive(Debug, Error)]
+pub enum RecordError<E>
+where
+    E: Error,
+{
+    /// An error from the underlying transport.
+    #[error(transparent)]
+    Transport(E),
+    /// An error writing the call to the fixture file.
+    #[error("error recording call: {0}")]
+    Io(#[from] io::Error),
+}
+
+impl<E> TransientError for RecordError<E>
+where
+    E: TransientError,
+{
+    fn is_transient(&self) -> bool {
+        match self {
+            RecordError::Transport(err) => err.is_transient(),
+            RecordError::Io(_) => false,
+        }
+    }
+}
+
+/// A transport that replays calls from a fixture file.
+///
+/// Calls with the same method and parameters are replayed in the order they
+/// were recorded. Each recorded response is replayed once, and calls fail once
+/// all of their recorded responses were replayed.
+#[derive(Debug)]
+pub struct Replay {
+    calls: Mutex<HashMap<String, VecDeque<Entry>>>,
+}
+
+impl Replay {
+    /// Loads the recorded calls from the fixture file at the specified path.
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        Self::from_reader(BufReader::new(File::open(path)?))
+    }
+
+    /// Loads the recorded calls from a reader of a fixture.
+    pub fn from_reader(reader: impl BufRead) -> io::Result<Self> {
+        let mut calls = HashMap::<_, VecDeque<_>>::new();
+        for line in reader.lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let entry = serde_json::from_str::<Entry>(&line)?;
+            let key = key(&entry.request).ok_or_else(|| {
+                io::Error::new(io::ErrorKind::InvalidData, "invalid recorded request")
+            })?;
+            calls.entry(key).or_default().push_back(entry);
+        }
+
+        Ok(Replay {
+            calls: Mutex::new(calls),
+        })
+    }
+
+    fn replay(&self, request: &[u8]) -> Result<Vec<u8>, ReplayError> {
+        let request = serde_json::from_slice::<Value>(request)
+            .map_err(|err| ReplayError(format!("invalid request JSON: {}", err)))?;
+        let key = key(&request).ok_or_else(|| ReplayError("invalid request".to_owned()))?;
+
+        let mut calls = self.calls.lock().expect("poisoned recorded calls");
+        let entry = calls
+            .get_mut(&key)
+            .ok_or_else(|| ReplayError(format!("no recorded call for {}", key)))?
+            .pop_front()
+            .ok_or_else(|| ReplayError(format!("recorded calls for {} are exhausted", key)))?;
+
+        // NOTE: Calls are matched without their IDs, so update the IDs of the
+        // recorded responses to the ones of the replayed request.
+        let response = match (&entry.response, &entry.request, &request) {
+            (Value::Null, _, _) => return Ok(Vec::new()),
+            (Value::Array(responses), Value::Array(recorded), Value::Array(requests)) => {
+                Value::Array(
+                    responses
+                        .iter()
+                        .map(|response| {
+                            let id = recorded
+                                .iter()
+                                .position(|recorded| recorded["id"] == response["id"])
+                                .map_or(Value::Null, |i| requests[i]["id"].clone());
+                            with_id(response, id)
+                        })
+                        .collect(),
+                )
+            }
+            (response, _, request) => with_id(response, request["id"].clone()),
+        };
+        Ok(serde_json::to_vec(&response).expect("JSON value serialization is infallible"))
+    }
+}
+
+impl Transport for Replay {
+    type Error = ReplayError;
+    type Call<'a> = Ready<Result<Vec<u8>, ReplayError>>;
+
+    fn call<'a>(&'a self, request: &'a [u8]) -> Self::Call<'a> {
+        future::ready(self.replay(request))
+    }
+}
+
+/// An error replaying a call that was not recorded, or whose recorded calls
+/// were all replayed.
+#[derive(Debug, Error)]
+#[error("replay error: {0}")]
+pub struct ReplayError(String);
+
+impl TransientError for ReplayError {
+    fn is_transient(&self) -> bool {
+        false
+    }
+}
+
+/// Returns the key for matching a request, made of the method and parameters
+/// of the request (or of each request in a batch).
+fn key(request: &Value) -> Option<String> {
+    fn call(request: &Value) -> Option<Value> {
+        let method = request.get("method")?.as_str()?;
+        let params = request.get("params").unwrap_or(&Value::Null);
+        Some(json!([method, params]))
+    }
+
+    let key = match request {
+        Value::Array(requests) => Value::Array(requests.iter().map(call).collect::<Option<_>>()?),
+        request => call(request)?,
+    };
+    Some(key.to_string())
+}
+
+/// Returns a copy of a response object with the specified ID.
+fn with_id(response: &Value, id: Value) -> Value {
+    let mut response = response.clone();
+    if let Some(response) = response.as_object_mut() {
+        response.insert("id".to_owned(), id);
+    }
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::jsonrpc::server::Server;
+    use crate::jsonrpc::{Client, ClientError, StringIds};
+    use futures::executor::block_on;
+    use std::path::PathBuf;
+    use std::sync::atomic::{AtomicU64, Ordering};
+    use std::sync::Arc;
+
+    /// A fixture file in the temporary directory that is removed on drop.
+    struct Fixture(PathBuf);
+
+    impl Fixture {
+        fn new(name: &str) -> Self {
+            Fixture(std::env::temp_dir().join(format!(
+                "ethrs-replay-{}-{}.jsonl",
+                name,
+                std::process::id(),
+            )))
+        }
+    }
+
+    impl Drop for Fixture {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_file(&self.0);
+        }
+    }
+
+    /// A server with a block number that increases with every call.
+    fn node() -> Server {
+        let block = Arc::new(AtomicU64::new(1));
+        Server::new()
+            .with_method("eth_blockNumber", move |(): ()| {
+                let block = block.fetch_add(1, Ordering::SeqCst);
+                async move { Ok(format!("{:#x}", block)) }
+            })
+            .with_method("test_echo", |params: Value| async { Ok(params) })
+    }
+
+    #[test]
+    fn records_and_replays_calls() {
+        let fixture = Fixture::new("calls");
+
+        let client = Client::new(Record::create(node(), &fixture.0).unwrap());
+        block_on(async {
+            for _ in 0..2 {
+                client
+                    .call::<_, String>("eth_blockNumber", ())
+                    .await
+                    .unwrap();
+            }
+            client.call::<_, Value>("test_echo", [1]).await.unwrap();
+            client.notify("test_echo", [2]).await.unwrap();
+        });
+        client.transport().flush().unwrap();
+
+        let client = Client::new(Replay::open(&fixture.0).unwrap())
+            .with_id_generator(StringIds::new("replay-"));
+        block_on(async {
+            assert_eq!(
+                client.call::<_, Value>("test_echo", [1]).await.unwrap(),
+                json!([1]),
+            );
+            client.notify("test_echo", [2]).await.unwrap();
+            for block in ["0x1", "0x2"] {
+                assert_eq!(
+                    client
+                        .call::<_, String>("eth_blockNumber", ())
+                        .await
+                        .unwrap(),
+                    block,
+                );
+            }
+            match client.call::<_, String>("eth_blockNumber", ()).await {
+                Err(ClientError::Transport(err)) => {
+                    assert!(err.to_string().contains("exhausted"));
+                }
+                result => panic!("unexpected result {:?}", result),
+            }
+            assert!(matches!(
+                client.call::<_, Value>("test_echo", [3]).await,
+                Err(ClientError::Transport(_)),
+            ));
+        });
+    }
+
+    #[test]
+    fn replays_batches() {
+        let fixture = Fixture::new("batches");
+
+        let client = Client::new(Record::create(node(), &fixture.0).unwrap());
+        block_on(async {
+            let batch = client.batch();
+            let (first, second) = (
+                batch.call::<_, Value>("test_echo", [1]),
+                batch.call::<_, Value>("test_echo", [2]),
+            );
+            batch.send().await.unwrap();
+            first.await.unwrap();
+            second.await.unwrap();
+        });
+        drop(client);
+
+        let client = Client::new(Replay::open(&fixture.0).unwrap())
+            .with_id_generator(StringIds::new("replay-"));
+        block_on(async {
+            let batch = client.batch();
+            let (first, second) = (
+                batch.call::<_, Value>("test_echo", [1]),
+                batch.call::<_, Value>("test_echo", [2]),
+            );
+            batch.send().await.unwrap();
+            assert_eq!(first.await.unwrap(), json!([1]));
+            assert_eq!(second.await.unwrap(), json!([2]));
+        });
+    }
+}
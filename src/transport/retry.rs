@@ -0,0 +1,421 @@
+//! This module contains a transport wrapper that retries failed calls with
+//! exponential backoff.
+//!
+//! Whether or not a call is retried is driven by the type of error: transient
+//! transport errors (see [`TransientError`]) and responses with selected JSON
+//! RPC server error codes are retried, while everything else is returned as
+//! is. Calls to non-idempotent methods, such as `eth_sendRawTransaction`, are
+//! never retried since it is not possible to know whether or not the node
+//! already processed the failed request.
+//!
+//! Retrying a batch request resends all of its calls, including the ones that
+//! already succeeded, so batches are only retried if all of their calls are
+//! idempotent.
+
+use crate::jsonrpc::data::{ErrorCode, ErrorEnvelope, Messages, MethodEnvelope};
+use crate::transport::{TransientError, Transport};
+use crate::util;
+use futures_timer::Delay;
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{ready, Context, Poll};
+use std::time::Duration;
+
+/// Methods that are never retried by default.
+const NON_IDEMPOTENT_METHODS: &[&str] = &[
+    "eth_sendRawTransaction",
+    "eth_sendTransaction",
+    "personal_sendTransaction",
+];
+
+/// Server error codes that are retried by default. `-32005` is the "limit
+/// exceeded" error code from EIP-1474 that is used by nodes and RPC providers
+/// for rate limiting.
+const SERVER_ERROR_CODES: &[i32] = &[-32005];
+
+/// A transport wrapper that retries failed calls with exponential backoff and
+/// jitter.
+#[derive(Clone, Debug)]
+pub struct Retry<T> {
+    transport: T,
+    max_retries: u32,
+    initial_backoff: Duration,
+    max_backoff: Duration,
+    jitter: f64,
+    server_error_codes: Vec<i32>,
+    non_idempotent_methods: Vec<String>,
+}
+
+impl<T> Retry<T> {
+    /// Creates a new retrying transport wrapper with the default settings.
+    ///
+    /// By default, calls are retried up to 3 times with a backoff starting at
+    /// 100 milliseconds, up to 10 seconds, and a jitter of 0.5.
+    pub fn new(transport: T) -> Self {
+        Retry {
+            transport,
+            max_retries: 3,
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(10),
+            jitter: 0.5,
+            server_error_codes: SERVER_ERROR_CODES.to_vec(),
+            non_idempotent_methods: NON_IDEMPOTENT_METHODS
+                .iter()
+                .map(|&method| method.to_owned())
+                .collect(),
+        }
+    }
+
+    /// Sets the maximum number of times a call is retried.
+    pub fn with_max_retries(mut self, max_retries: u32) -> Self {
+        self.max_retries = max_retries;
+        self
+    }
+
+    /// Sets the backoff before the first retry. The backoff doubles for each
+    /// subsequent retry up to the specified maximum.
+    pub fn with_backoff(mut self, initial: Duration, max: Duration) -> Self {
+        self.initial_backoff = initial;
+        self.max_backoff = max;
+        self
+    }
+
+    /// Sets the jitter, which is the maximum fraction of the backoff that is
+    /// randomly subtracted from it so that clients don't retry in lockstep.
+    ///
+    /// # Panics
+    ///
+    /// This method panics if the jitter is not in the range from 0 to 1.
+    pub fn with_jitter(mut self, jitter: f64) -> Self {
+        assert!((0.0..=1.0).contains(&jitter), "jitter out of range");
+        self.jitter = jitter;
+        self
+    }
+
+    /// Sets the JSON RPC server error codes for which calls are retried.
+    ///
+    /// # Panics
+    ///
+    /// This method panics if any of the codes are not in the server error
+    /// range from -32099 to -32000.
+    pub fn with_server_error_codes(mut self, codes: impl IntoIterator<Item = i32>) -> Self {
+        self.server_error_codes = codes
+            .into_iter()
+            .inspect(|&code| {
+                assert!(
+                    matches!(ErrorCode::from(code), ErrorCode::ServerError(_)),
+                    "{} is not a server error code",
+                    code,
+                )
+            })
+            .collect();
+        self
+    }
+
+    /// Adds a method that is never retried, in addition to the default
+    /// non-idempotent methods.
+    pub fn with_non_idempotent_method(mut self, method: impl Into<String>) -> Self {
+        self.non_idempotent_methods.push(method.into());
+        self
+    }
+
+    /// Returns a reference to the underlying transport.
+    pub fn transport(&self) -> &T {
+        &self.transport
+    }
+
+    /// Returns `true` if the request only calls idempotent methods. Requests
+    /// that can't be inspected are conservatively considered non-idempotent.
+    fn is_idempotent(&self, request: &[u8]) -> bool {
+        match serde_json::from_slice::<Messages<MethodEnvelope>>(request) {
+            Ok(requests) => requests
+                .iter()
+                .all(|request| !self.non_idempotent_methods.contains(&request.method)),
+            Err(_) => false,
+        }
+    }
+
+    /// Returns `true` if the response contains an error with one of the
+    /// retried server error codes.
+    fn is_retried_response(&self, response: &[u8]) -> bool {
+        match serde_json::from_slice::<Messages<ErrorEnvelope>>(response) {
+            Ok(responses) => responses.iter().any(|response| match &response.error {
+                Some(error) => self.server_error_codes.contains(&error.code),
+                None => false,
+            }),
+            Err(_) => false,
+        }
+    }
+
+    /// Computes the backoff before the specified retry.
+    fn backoff(&self, retry: u32) -> Duration {
+        let backoff = self
+            .initial_backoff
+            .saturating_mul(2_u32.saturating_pow(retry))
+            .min(self.max_backoff);
+        backoff.mul_f64(1.0 - self.jitter * util::random_f64())
+    }
+}
+
+impl<T> Transport for Retry<T>
+where
+    T: Transport,
+    T::Error: TransientError,
+{
+    type Error = T::Error;
+    type Call<'a>
+        = Call<'a, T>
+    where
+        T: 'a;
+
+    fn call<'a>(&'a self, request: &'a [u8]) -> Self::Call<'a> {
+        let max_retries = if self.is_idempotent(request) {
+            self.max_retries
+        } else {
+            0
+        };
+
+        Call {
+            retry: self,
+            request,
+            retries: 0,
+            max_retries,
+            state: State::Calling(Box::pin(self.transport.call(request))),
+        }
+    }
+}
+
+/// Future returned by [`Retry`] calls.
+pub struct Call<'a, T>
+where
+    T: Transport + 'a,
+{
+    retry: &'a Retry<T>,
+    request: &'a [u8],
+    retries: u32,
+    max_retries: u32,
+    state: State<'a, T>,
+}
+
+/// The state of a retried call.
+enum State<'a, T>
+where
+    T: Transport + 'a,
+{
+    /// The call is being performed.
+    Calling(Pin<Box<T::Call<'a>>>),
+    /// The call failed and is waiting to be retried.
+    Waiting(Delay),
+}
+
+impl<T> Future for Call<'_, T>
+where
+    T: Transport,
+    T::Error: TransientError,
+{
+    type Output = Result<Vec<u8>, T::Error>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        let this = &mut *self;
+        loop {
+            match &mut this.state {
+                State::Calling(call) => {
+                    let result = ready!(call.as_mut().poll(cx));
+                    let retried = match &result {
+                        Ok(response) => this.retry.is_retried_response(response),
+                        Err(err) => err.is_transient(),
+                    };
+                    if !retried || this.retries >= this.max_retries {
+                        return Poll::Ready(result);
+                    }
+
+                    let backoff = this.retry.backoff(this.retries);
+                    this.retries += 1;
+                    this.state = State::Waiting(Delay::new(backoff));
+                }
+                State::Waiting(delay) => {
+                    ready!(Pin::new(delay).poll(cx));
+                    let call = this.retry.transport.call(this.request);
+                    this.state = State::Calling(Box::pin(call));
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::executor::block_on;
+    use serde_json::json;
+    use std::collections::VecDeque;
+    use std::fmt::{self, Display, Formatter};
+    use std::future::Ready;
+    use std::sync::Mutex;
+
+    #[derive(Debug)]
+    struct Error(bool);
+
+    impl Display for Error {
+        fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+            write!(f, "transient: {}", self.0)
+        }
+    }
+
+    impl std::error::Error for Error {}
+
+    impl TransientError for Error {
+        fn is_transient(&self) -> bool {
+            self.0
+        }
+    }
+
+    /// A transport that returns scripted results and counts the calls.
+    struct Script(Mutex<ScriptState>);
+
+    struct ScriptState {
+        results: VecDeque<Result<Vec<u8>, Error>>,
+        calls: usize,
+    }
+
+    impl Script {
+        fn new(results: impl IntoIterator<Item = Result<Vec<u8>, Error>>) -> Retry<Self> {
+            let script = Script(Mutex::new(ScriptState {
+                results: results.into_iter().collect(),
+                calls: 0,
+            }));
+            Retry::new(script).with_backoff(Duration::from_millis(1), Duration::from_millis(2))
+        }
+
+        fn calls(&self) -> usize {
+            self.0.lock().unwrap().calls
+        }
+    }
+
+    impl Transport for Script {
+        type Error = Error;
+        type Call<'a> = Ready<Result<Vec<u8>, Error>>;
+
+        fn call<'a>(&'a self, _: &'a [u8]) -> Self::Call<'a> {
+            let mut script = self.0.lock().unwrap();
+            script.calls += 1;
+            std::future::ready(script.results.pop_front().unwrap())
+        }
+    }
+
+    const REQUEST: &[u8] = br#"{"jsonrpc":"2.0","method":"eth_chainId","params":[],"id":0}"#;
+
+    fn response(code: Option<i32>) -> Result<Vec<u8>, Error> {
+        let response = match code {
+            Some(code) => json!({
+                "jsonrpc": "2.0",
+                "error": { "code": code, "message": "error" },
+                "id": 0,
+            }),
+            None => json!({ "jsonrpc": "2.0", "result": "0x1", "id": 0 }),
+        };
+        Ok(response.to_string().into_bytes())
+    }
+
+    #[test]
+    fn retries_transient_errors() {
+        let retry = Script::new(vec![Err(Error(true)), Err(Error(true)), response(None)]);
+
+        assert_eq!(
+            block_on(retry.call(REQUEST)).unwrap(),
+            response(None).unwrap()
+        );
+        assert_eq!(retry.transport().calls(), 3);
+    }
+
+    #[test]
+    fn gives_up_after_max_retries() {
+        let retry = Script::new(vec![Err(Error(true)), Err(Error(true))]).with_max_retries(1);
+
+        assert!(block_on(retry.call(REQUEST)).is_err());
+        assert_eq!(retry.transport().calls(), 2);
+    }
+
+    #[test]
+    fn does_not_retry_permanent_errors() {
+        let retry = Script::new(vec![Err(Error(false))]);
+
+        assert!(block_on(retry.call(REQUEST)).is_err());
+        assert_eq!(retry.transport().calls(), 1);
+    }
+
+    #[test]
+    fn retries_selected_server_errors() {
+        let retry = Script::new(vec![response(Some(-32005)), response(None)]);
+        assert_eq!(
+            block_on(retry.call(REQUEST)).unwrap(),
+            response(None).unwrap()
+        );
+        assert_eq!(retry.transport().calls(), 2);
+
+        let retry = Script::new(vec![response(Some(-32000))]);
+        assert_eq!(
+            block_on(retry.call(REQUEST)).unwrap(),
+            response(Some(-32000)).unwrap(),
+        );
+        assert_eq!(retry.transport().calls(), 1);
+
+        let retry = Script::new(vec![response(Some(-32000)), response(None)])
+            .with_server_error_codes(vec![-32000]);
+        assert_eq!(
+            block_on(retry.call(REQUEST)).unwrap(),
+            response(None).unwrap()
+        );
+        assert_eq!(retry.transport().calls(), 2);
+    }
+
+    #[test]
+    fn never_retries_non_idempotent_methods() {
+        let retry = Script::new(vec![Err(Error(true))]);
+        let request =
+            br#"{"jsonrpc":"2.0","method":"eth_sendRawTransaction","params":["0x"],"id":0}"#;
+        assert!(block_on(retry.call(request)).is_err());
+        assert_eq!(retry.transport().calls(), 1);
+
+        let retry = Script::new(vec![Err(Error(true))]);
+        let batch = format!(
+            "[{},{}]",
+            String::from_utf8_lossy(REQUEST),
+            String::from_utf8_lossy(request),
+        );
+        assert!(block_on(retry.call(batch.as_bytes())).is_err());
+        assert_eq!(retry.transport().calls(), 1);
+
+        let retry = Script::new(vec![Ok(format!(
+            "[{},{}]",
+            String::from_utf8_lossy(&response(Some(-32005)).unwrap()),
+            String::from_utf8_lossy(&response(None).unwrap()),
+        )
+        .into_bytes())]);
+        assert!(block_on(retry.call(batch.as_bytes())).is_ok());
+        assert_eq!(retry.transport().calls(), 1);
+
+        let retry = Script::new(vec![Err(Error(true))]).with_non_idempotent_method("eth_chainId");
+        assert!(block_on(retry.call(REQUEST)).is_err());
+        assert_eq!(retry.transport().calls(), 1);
+    }
+
+    #[test]
+    fn exponential_backoff() {
+        let retry = Retry::new(())
+            .with_backoff(Duration::from_secs(1), Duration::from_secs(5))
+            .with_jitter(0.0);
+
+        assert_eq!(retry.backoff(0), Duration::from_secs(1));
+        assert_eq!(retry.backoff(1), Duration::from_secs(2));
+        assert_eq!(retry.backoff(2), Duration::from_secs(4));
+        assert_eq!(retry.backoff(3), Duration::from_secs(5));
+        assert_eq!(retry.backoff(100), Duration::from_secs(5));
+
+        let retry = retry.with_jitter(0.5);
+        for _ in 0..100 {
+            let backoff = retry.backoff(1);
+            assert!(backoff > Duration::from_secs(1) && backoff <= Duration::from_secs(2));
+        }
+    }
+}
@@ -0,0 +1,18 @@
+//! Module containing crate-private utilities.
+
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+
+/// Returns random bits.
+///
+/// This uses the randomly seeded hasher from the standard library, which is
+/// good enough for jitter and unique IDs without needing a random number
+/// generator dependency.
+pub fn random() -> u64 {
+    RandomState::new().build_hasher().finish()
+}
+
+/// Returns a random number in the range from 0 to 1.
+pub fn random_f64() -> f64 {
+    (random() >> 11) as f64 / (1_u64 << 53) as f64
+}
@@ -0,0 +1,303 @@
+//! This module contains a native HTTP POST implementation using `libcurl`.
+//! Since `curl` transfers are blocking, asynchronous calls are performed on a
+//! bounded pool of background threads so that they do not block the executor.
+
+use curl::easy::{Easy, List};
+use ethrs_transport::{TransientError, Transport};
+use futures_channel::oneshot;
+use std::{
+    fmt::{self, Display, Formatter},
+    future::Future,
+    pin::Pin,
+    sync::{mpsc, Arc, Mutex},
+    thread,
+};
+
+/// The default maximum number of background threads performing transfers.
+const DEFAULT_MAX_THREADS: usize = 16;
+
+/// A HTTP transport implementation that works on native targets using
+/// `libcurl`.
+///
+/// Transfers are performed on a pool of background threads that are started
+/// as needed, up to a maximum number of threads. Calls are queued while all of
+/// them are busy. Idle `curl` handles are pooled and reused between calls so
+/// that open connections to the node can be kept alive.
+#[derive(Clone, Debug)]
+pub struct Http {
+    client: Arc<Client>,
+    workers: Arc<Workers>,
+}
+
+/// The state shared by the worker threads for performing transfers.
+#[derive(Debug)]
+struct Client {
+    url: String,
+    handles: Mutex<Vec<Easy>>,
+}
+
+/// A queued HTTP POST, with the channel for sending back its result.
+type Job = (Vec<u8>, oneshot::Sender<Result<Vec<u8>, Error>>);
+
+/// The pool of worker threads performing transfers. The threads exit once the
+/// pool is dropped.
+#[derive(Debug)]
+struct Workers {
+    jobs: Mutex<mpsc::Sender<Job>>,
+    queue: Arc<Mutex<mpsc::Receiver<Job>>>,
+    state: Arc<Mutex<WorkerState>>,
+    max: usize,
+}
+
+#[derive(Debug, Default)]
+struct WorkerState {
+    /// The number of started worker threads.
+    started: usize,
+    /// The number of worker threads that are not performing a transfer and
+    /// were not already reserved for a queued job.
+    idle: usize,
+}
+
+impl Http {
+    /// Creates a new `curl` HTTP transport.
+    pub fn new(url: impl AsRef<str>) -> Self {
+        Self::with_max_threads(url, DEFAULT_MAX_THREADS)
+    }
+
+    /// Creates a new `curl` HTTP transport that performs at most `max_threads`
+    /// concurrent transfers, each on its own background thread.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if `max_threads` is 0.
+    pub fn with_max_threads(url: impl AsRef<str>, max_threads: usize) -> Self {
+        assert!(
+            max_threads > 0,
+            "HTTP transport requires at least one thread"
+        );
+
+        let (jobs, queue) = mpsc::channel();
+        Self {
+            client: Arc::new(Client {
+                url: url.as_ref().to_owned(),
+                handles: Default::default(),
+            }),
+            workers: Arc::new(Workers {
+                jobs: Mutex::new(jobs),
+                queue: Arc::new(Mutex::new(queue)),
+                state: Default::default(),
+                max: max_threads,
+            }),
+        }
+    }
+}
+
+impl Client {
+    /// Performs a blocking HTTP POST with a pooled `curl` handle.
+    fn post(&self, request: &[u8]) -> Result<Vec<u8>, Error> {
+        let mut handle = self
+            .handles
+            .lock()
+            .expect("poisoned handle pool")
+            .pop()
+            .unwrap_or_else(Easy::new);
+        let response = post(&mut handle, &self.url, request)?;
+
+        // NOTE: Only return handles to the pool on success, as we can't be
+        // sure what state the connection is in after an error.
+        self.handles
+            .lock()
+            .expect("poisoned handle pool")
+            .push(handle);
+
+        Ok(response)
+    }
+}
+
+impl Workers {
+    /// Queues a job, starting a new worker thread for it if all started
+    /// threads are busy and the maximum number of threads was not reached.
+    fn submit(&self, client: &Arc<Client>, job: Job) {
+        let mut state = self.state.lock().expect("poisoned worker state");
+        if state.idle > 0 {
+            state.idle -= 1;
+        } else if state.started < self.max {
+            state.started += 1;
+
+            let client = client.clone();
+            let queue = self.queue.clone();
+            let state = self.state.clone();
+            thread::spawn(move || work(&client, &queue, &state));
+        }
+        drop(state);
+
+        // NOTE: Sending only fails if all workers exited, which only happens
+        // when they panicked. The call then fails when the result channel is
+        // dropped.
+        let _ = self.jobs.lock().expect("poisoned job queue").send(job);
+    }
+}
+
+/// Performs queued jobs until the worker pool is dropped.
+fn work(client: &Client, queue: &Mutex<mpsc::Receiver<Job>>, state: &Mutex<WorkerState>) {
+    loop {
+        let job = queue.lock().expect("poisoned job queue").recv();
+        let (request, result) = match job {
+            Ok(job) => job,
+            Err(_) => break,
+        };
+
+        // NOTE: Skip transfers for calls that were dropped while queued, and
+        // ignore send errors, they just mean that the call future was dropped
+        // before the transfer completed.
+        if !result.is_canceled() {
+            let _ = result.send(client.post(&request));
+        }
+
+        state.lock().expect("poisoned worker state").idle += 1;
+    }
+}
+
+impl Transport for Http {
+    type Error = Error;
+    type Call<'a> = Pin<Box<dyn Future<Output = Result<Vec<u8>, Self::Error>> + Send + 'a>>;
+
+    fn call<'a>(&'a self, request: &'a [u8]) -> Self::Call<'a> {
+        let (sender, receiver) = oneshot::channel();
+        self.workers
+            .submit(&self.client, (request.to_owned(), sender));
+
+        Box::pin(async move {
+            receiver
+                .await
+                .map_err(|_| Error::from("HTTP request thread panicked"))?
+        })
+    }
+}
+
+/// Performs a JSON HTTP POST `request` with the specified `curl` handle.
+fn post(handle: &mut Easy, url: &str, request: &[u8]) -> Result<Vec<u8>, Error> {
+    let mut headers = List::new();
+    headers.append("Content-Type: application/json")?;
+
+    handle.url(url)?;
+    handle.post(true)?;
+    handle.post_fields_copy(request)?;
+    handle.http_headers(headers)?;
+
+    let mut response = Vec::new();
+    {
+        let mut transfer = handle.transfer();
+        transfer.write_function(|data| {
+            response.extend_from_slice(data);
+            Ok(data.len())
+        })?;
+        transfer.perform()?;
+    }
+
+    let status = handle.response_code()?;
+    if !(200..300).contains(&status) {
+        return Err(Error {
+            status: Some(status),
+            ..Error::new(format!(
+                "HTTP status {}: {}",
+                status,
+                String::from_utf8_lossy(&response),
+            ))
+        });
+    }
+
+    Ok(response)
+}
+
+/// An HTTP transport error.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Error {
+    message: String,
+    status: Option<u32>,
+    curl: Option<curl::Error>,
+}
+
+impl Error {
+    /// Creates a new error with the specified message.
+    pub fn new(message: impl Into<String>) -> Self {
+        Self {
+            message: message.into(),
+            status: None,
+            curl: None,
+        }
+    }
+
+    /// Returns the error message.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// Returns the HTTP status code if the node responded with a non-success
+    /// status.
+    pub fn status(&self) -> Option<u32> {
+        self.status
+    }
+
+    /// Returns the underlying `curl` error if the transfer failed.
+    ///
+    /// This method is specific to the `curl` backend and is only available on
+    /// non-Wasm targets.
+    pub fn curl_error(&self) -> Option<&curl::Error> {
+        self.curl.as_ref()
+    }
+}
+
+// NOTE: `curl::Error` only implements `PartialEq` even though its equality is
+// total, as it compares error codes and messages.
+impl Eq for Error {}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        Display::fmt(&self.message, f)
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl TransientError for Error {
+    /// Connection, timeout, send and receive errors as well as `429 Too Many
+    /// Requests`, `502 Bad Gateway`, `503 Service Unavailable` and `504 Gateway
+    /// Timeout` responses are considered transient. Other errors, such as
+    /// malformed URLs, TLS certificate errors or other HTTP statuses, are not.
+    fn is_transient(&self) -> bool {
+        if let Some(err) = &self.curl {
+            return err.is_couldnt_resolve_host()
+                || err.is_couldnt_connect()
+                || err.is_operation_timedout()
+                || err.is_send_error()
+                || err.is_recv_error()
+                || err.is_got_nothing()
+                || err.is_partial_file();
+        }
+        self.status
+            .is_some_and(|status| crate::TRANSIENT_STATUSES.contains(&status))
+    }
+}
+
+impl From<curl::Error> for Error {
+    fn from(err: curl::Error) -> Self {
+        Self {
+            message: err.to_string(),
+            status: None,
+            curl: Some(err),
+        }
+    }
+}
+
+impl From<&str> for Error {
+    fn from(message: &str) -> Self {
+        Self::new(message)
+    }
+}
+
+impl From<String> for Error {
+    fn from(message: String) -> Self {
+        Self::new(message)
+    }
+}
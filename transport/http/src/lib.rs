@@ -1,5 +1,14 @@
 //! Simple HTTP transport implementation. Uses `curl` for standard targets and
 //! `fetch` with `web-sys` for Wasm target.
+//!
+//! Both backends provide the same `Http` and `Error` API. Errors are created
+//! with `Error::new` (or converted from strings) and expose their details with
+//! the `Error::message` and `Error::status` accessors. The only backend
+//! specific extra is `Error::curl_error` on non-Wasm targets.
+//!
+//! Note that the `Error` fields are private: code that constructed or matched
+//! the former `Error(String)` tuple struct should use `Error::new` and
+//! `Error::message` instead.
 
 #[cfg(not(target_arch = "wasm32"))]
 #[path = "curl.rs"]
@@ -9,3 +18,10 @@ mod platform;
 mod platform;
 
 pub use platform::*;
+
+/// HTTP statuses for which failed requests are considered transient: `429 Too
+/// Many Requests` and `503 Service Unavailable`, as well as `502 Bad Gateway`
+/// and `504 Gateway Timeout` which are returned by proxies in front of nodes
+/// that are temporarily unavailable. Other server errors, such as `500
+/// Internal Server Error`, usually aren't fixed by retrying.
+const TRANSIENT_STATUSES: [u32; 4] = [429, 502, 503, 504];
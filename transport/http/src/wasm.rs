@@ -1,8 +1,8 @@
 //! This module contains a Wasm compatible HTTP POST implementation using the
 //! `fetch` API. In order to work with NodeJS, a `fetch` polyfill is needed.
 
-use ethrs_transport::Transport2;
-use js_sys::{Function, JsString, Promise, Uint8Array};
+use ethrs_transport::{TransientError, Transport};
+use js_sys::{Array, Function, JsString, Promise, Uint8Array};
 use std::{
     fmt::{self, Display, Formatter},
     future::Future,
@@ -26,21 +26,36 @@ impl Http {
     }
 }
 
-impl<'a> Transport2<'a> for Http {
+impl Transport for Http {
     type Error = Error;
-    type Call = Pin<Box<dyn Future<Output = Result<Vec<u8>, Self::Error>> + 'a>>;
+    type Call<'a> = Pin<Box<dyn Future<Output = Result<Vec<u8>, Self::Error>> + 'a>>;
 
-    fn call(&'a self, request: &'a [u8]) -> Self::Call {
+    fn call<'a>(&'a self, request: &'a [u8]) -> Self::Call<'a> {
         Box::pin(async move {
             let request = Uint8Array::from(request);
-            let text = JsFuture::from(
+            let response = JsFuture::from(
                 post()
                     .call2(&JsValue::UNDEFINED, &self.url, &request)?
                     .dyn_into::<Promise>()?,
             )
-            .await?
-            .as_string()
-            .ok_or("fetch returned non-string value")?;
+            .await
+            .map_err(Error::connection)?
+            .dyn_into::<Array>()?;
+
+            let status = response
+                .get(0)
+                .as_f64()
+                .ok_or("fetch returned non-numeric status")? as u32;
+            let text = response
+                .get(1)
+                .as_string()
+                .ok_or("fetch returned non-string value")?;
+            if !(200..300).contains(&status) {
+                return Err(Error {
+                    status: Some(status),
+                    ..Error::new(format!("HTTP status {}: {}", status, text))
+                });
+            }
 
             Ok(text.into_bytes())
         })
@@ -68,37 +83,87 @@ fn post() -> &'static Function {
 
 /// An HTTP transport error.
 #[derive(Clone, Debug, Eq, PartialEq)]
-pub struct Error(pub String);
+pub struct Error {
+    message: String,
+    status: Option<u32>,
+    connection: bool,
+}
 
 impl Error {
+    /// Creates a new error with the specified message.
+    pub fn new(message: impl Into<String>) -> Self {
+        Self {
+            message: message.into(),
+            status: None,
+            connection: false,
+        }
+    }
+
     /// Creates a new unknown error when inspecting the JavaScript error type
     /// fails.
-    #[doc(hidden)]
-    pub fn unknown() -> Self {
-        Self("unknown error".to_owned())
+    fn unknown() -> Self {
+        Self::new("unknown error")
+    }
+
+    /// Creates a new error for a failed request, meaning that no response was
+    /// received from the node.
+    fn connection(value: JsValue) -> Self {
+        Self {
+            connection: true,
+            ..Self::from(value)
+        }
+    }
+
+    /// Returns the error message.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// Returns the HTTP status code if the node responded with a non-success
+    /// status.
+    pub fn status(&self) -> Option<u32> {
+        self.status
     }
 }
 
 impl Display for Error {
     fn fmt(&self, f: &mut Formatter) -> fmt::Result {
-        Display::fmt(&self.0, f)
+        Display::fmt(&self.message, f)
     }
 }
 
 impl std::error::Error for Error {}
 
+impl TransientError for Error {
+    /// Failed requests, where no response was received, as well as `429 Too
+    /// Many Requests`, `502 Bad Gateway`, `503 Service Unavailable` and `504
+    /// Gateway Timeout` responses are considered transient.
+    fn is_transient(&self) -> bool {
+        self.connection
+            || self
+                .status
+                .is_some_and(|status| crate::TRANSIENT_STATUSES.contains(&status))
+    }
+}
+
 impl From<JsValue> for Error {
     fn from(value: JsValue) -> Self {
         js_sys::Error::from(value)
             .message()
             .as_string()
-            .map(Error)
+            .map(Error::new)
             .unwrap_or_else(Error::unknown)
     }
 }
 
-impl<'a> From<&'a str> for Error {
+impl From<&str> for Error {
     fn from(message: &str) -> Self {
-        Self(message.to_owned())
+        Self::new(message)
+    }
+}
+
+impl From<String> for Error {
+    fn from(message: String) -> Self {
+        Self::new(message)
     }
 }
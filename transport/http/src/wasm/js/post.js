@@ -7,7 +7,7 @@
                 headers: { "Content-Type": "application/json" },
                 body: data,
             })
-            .then(response => response.text());
+            .then(response => response.text().then(body => [response.status, body]));
     } else if (typeof require !== "undefined") {
         return new Promise((resolve, reject) => {
             const scheme = url.startsWith("https") ? "https" : "http";
@@ -24,7 +24,7 @@
                     let body = "";
                     response.setEncoding('utf8');
                     response.on('data', chunk => body = `${body}${chunk}`);
-                    response.on('end', () => resolve(body));
+                    response.on('end', () => resolve([response.statusCode, body]));
                 },
             );
             request.on('error', err => reject(err));
@@ -1,5 +1,5 @@
-use ethrs_transport::Transport2;
-use ethrs_transport_http::{Error, Http};
+use ethrs_transport::Transport;
+use ethrs_transport_http::Http;
 use wasm_bindgen_test::*;
 
 #[wasm_bindgen_test]
@@ -21,5 +21,6 @@ async fn http_error() {
     let transport = Http::new("invalid://url");
     let error = transport.call(b"").await.unwrap_err();
 
-    assert_ne!(error, Error::unknown());
+    assert_ne!(error.message(), "unknown error");
+    assert_eq!(error.status(), None);
 }
@@ -0,0 +1,171 @@
+#![cfg(not(target_arch = "wasm32"))]
+
+use ethrs_transport::{TransientError, Transport};
+use ethrs_transport_http::Http;
+use futures::executor::block_on;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+/// Starts a local HTTP server that responds to requests with the specified
+/// status and the request body echoed back. Returns the URL of the server.
+fn echo_server(status: &'static str) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            let stream = stream.unwrap();
+            thread::spawn(move || echo(stream, status));
+        }
+    });
+
+    url
+}
+
+/// Handles a single HTTP request, echoing back its body.
+fn echo(stream: TcpStream, status: &str) {
+    let mut reader = BufReader::new(stream);
+
+    let mut content_length = 0;
+    loop {
+        let mut line = String::new();
+        reader.read_line(&mut line).unwrap();
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        let header = line.to_ascii_lowercase();
+        if let Some(value) = header.strip_prefix("content-length:") {
+            content_length = value.trim().parse().unwrap();
+        }
+    }
+
+    let mut body = vec![0; content_length];
+    reader.read_exact(&mut body).unwrap();
+
+    let mut stream = reader.into_inner();
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        status,
+        body.len(),
+    )
+    .unwrap();
+    stream.write_all(&body).unwrap();
+}
+
+const REQUEST: &[u8] = br#"{"jsonrpc":"2.0","id":42,"method":"eth_chainId","params":[]}"#;
+
+#[test]
+fn call() {
+    let transport = Http::new(echo_server("200 OK"));
+    let answer = block_on(transport.call(REQUEST)).unwrap();
+
+    assert_eq!(answer, REQUEST);
+}
+
+#[test]
+fn concurrent_calls() {
+    let transport = Http::new(echo_server("200 OK"));
+    let (first, second) = block_on(futures::future::join(
+        transport.call(b"[1]"),
+        transport.call(b"[2]"),
+    ));
+
+    assert_eq!(first.unwrap(), b"[1]");
+    assert_eq!(second.unwrap(), b"[2]");
+}
+
+#[test]
+fn bounded_concurrent_calls() {
+    #[derive(Default)]
+    struct Connections {
+        active: AtomicUsize,
+        max: AtomicUsize,
+    }
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    let connections = Arc::new(Connections::default());
+    thread::spawn({
+        let connections = connections.clone();
+        move || {
+            for stream in listener.incoming() {
+                let stream = stream.unwrap();
+                let connections = connections.clone();
+                thread::spawn(move || {
+                    let active = connections.active.fetch_add(1, Ordering::SeqCst) + 1;
+                    connections.max.fetch_max(active, Ordering::SeqCst);
+                    thread::sleep(Duration::from_millis(10));
+                    connections.active.fetch_sub(1, Ordering::SeqCst);
+                    echo(stream, "200 OK");
+                });
+            }
+        }
+    });
+
+    let transport = Http::with_max_threads(url, 2);
+    let requests = (0..10).map(|i| format!("[{}]", i)).collect::<Vec<_>>();
+    let responses = block_on(futures::future::join_all(
+        requests
+            .iter()
+            .map(|request| transport.call(request.as_bytes())),
+    ));
+
+    for (request, response) in requests.iter().zip(responses) {
+        assert_eq!(response.unwrap(), request.as_bytes());
+    }
+    assert!(connections.max.load(Ordering::SeqCst) <= 2);
+}
+
+#[test]
+fn http_status_error() {
+    let transport = Http::new(echo_server("503 Service Unavailable"));
+    let error = block_on(transport.call(b"overloaded")).unwrap_err();
+
+    assert_eq!(error.message(), "HTTP status 503: overloaded");
+    assert_eq!(error.status(), Some(503));
+    assert!(error.is_transient());
+}
+
+#[test]
+fn http_permanent_status_errors() {
+    for (status, code) in [
+        ("400 Bad Request", 400),
+        ("500 Internal Server Error", 500),
+        ("501 Not Implemented", 501),
+    ] {
+        let transport = Http::new(echo_server(status));
+        let error = block_on(transport.call(b"invalid")).unwrap_err();
+
+        assert_eq!(error.status(), Some(code));
+        assert!(!error.is_transient());
+    }
+}
+
+#[test]
+fn http_error() {
+    let transport = Http::new("invalid://url");
+    let error = block_on(transport.call(b"")).unwrap_err();
+
+    assert!(!error.message().is_empty());
+    assert_eq!(error.status(), None);
+    assert!(error.curl_error().unwrap().is_unsupported_protocol());
+    assert!(!error.is_transient());
+}
+
+#[test]
+fn connection_error() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    drop(listener);
+
+    let error = block_on(Http::new(url).call(b"")).unwrap_err();
+
+    assert!(error.curl_error().unwrap().is_couldnt_connect());
+    assert!(error.is_transient());
+}
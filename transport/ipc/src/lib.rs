@@ -0,0 +1,142 @@
+//! IPC transport implementation over Unix domain sockets, for connecting to
+//! nodes running on the same machine (such as `geth.ipc` or `reth.ipc`).
+
+#![cfg(unix)]
+
+mod split;
+
+use self::split::Splitter;
+use async_net::unix::UnixStream;
+use ethrs_transport::{DuplexTransport, TransientError};
+use futures_util::{
+    io::{AsyncRead, AsyncWriteExt},
+    lock::Mutex,
+};
+use std::{
+    fmt::{self, Display, Formatter},
+    future::Future,
+    io,
+    path::Path,
+    pin::Pin,
+    sync,
+    task::{Context, Poll},
+};
+
+/// Size of the chunks read from the socket.
+const BUFFER_SIZE: usize = 4096;
+
+/// An IPC transport implementation over a Unix domain socket.
+pub struct Ipc {
+    writer: Mutex<UnixStream>,
+    reader: sync::Mutex<Reader>,
+}
+
+/// The receiving half of the IPC socket.
+struct Reader {
+    stream: UnixStream,
+    splitter: Splitter,
+    error: Option<Error>,
+}
+
+impl Ipc {
+    /// Connects to the node IPC socket at the specified path.
+    pub async fn connect(path: impl AsRef<Path>) -> Result<Self, Error> {
+        let stream = UnixStream::connect(path).await?;
+
+        Ok(Self {
+            writer: Mutex::new(stream.clone()),
+            reader: sync::Mutex::new(Reader {
+                stream,
+                splitter: Splitter::default(),
+                error: None,
+            }),
+        })
+    }
+}
+
+impl DuplexTransport for Ipc {
+    type Error = Error;
+    type Send<'a> = Pin<Box<dyn Future<Output = Result<(), Self::Error>> + Send + 'a>>;
+
+    fn send(&self, message: Vec<u8>) -> Self::Send<'_> {
+        Box::pin(async move {
+            self.writer.lock().await.write_all(&message).await?;
+            Ok(())
+        })
+    }
+
+    fn poll_receive(&self, cx: &mut Context) -> Poll<Result<Vec<u8>, Self::Error>> {
+        let mut reader = self.reader.lock().expect("poisoned IPC reader");
+        let result = reader.poll_receive(cx);
+        if let Poll::Ready(Err(err)) = &result {
+            reader.error = Some(err.clone());
+        }
+        result
+    }
+}
+
+impl Reader {
+    fn poll_receive(&mut self, cx: &mut Context) -> Poll<Result<Vec<u8>, Error>> {
+        if let Some(err) = &self.error {
+            return Poll::Ready(Err(err.clone()));
+        }
+
+        let mut buffer = [0; BUFFER_SIZE];
+        loop {
+            if let Some(message) = self.splitter.next()? {
+                return Poll::Ready(Ok(message));
+            }
+
+            match Pin::new(&mut self.stream).poll_read(cx, &mut buffer) {
+                Poll::Ready(Ok(0)) if self.splitter.is_empty() => {
+                    return Poll::Ready(Err(Error::closed()))
+                }
+                Poll::Ready(Ok(0)) => {
+                    return Poll::Ready(Err("IPC connection closed mid-message".into()))
+                }
+                Poll::Ready(Ok(len)) => self.splitter.extend(&buffer[..len]),
+                Poll::Ready(Err(err)) => return Poll::Ready(Err(err.into())),
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+    }
+}
+
+/// An IPC transport error.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Error(pub String);
+
+impl Error {
+    /// Creates a new error for when the IPC connection was closed.
+    pub fn closed() -> Self {
+        Self("IPC connection closed".to_owned())
+    }
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        Display::fmt(&self.0, f)
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl TransientError for Error {
+    /// Errors are never transient, as the IPC connection can't be used again
+    /// once an error occurred.
+    fn is_transient(&self) -> bool {
+        false
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Self {
+        Self(err.to_string())
+    }
+}
+
+impl From<&str> for Error {
+    fn from(message: &str) -> Self {
+        Self(message.to_owned())
+    }
+}
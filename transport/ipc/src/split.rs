@@ -0,0 +1,136 @@
+//! Module containing a splitter for reading individual JSON RPC messages from
+//! a byte stream.
+//!
+//! Nodes write JSON values back-to-back over IPC sockets without any delimiter
+//! or length prefix, so messages are split by tracking the nesting depth of
+//! the JSON being read while skipping over string contents.
+
+use crate::Error;
+
+/// An incremental splitter for a stream of concatenated JSON objects or
+/// arrays.
+#[derive(Debug, Default)]
+pub struct Splitter {
+    buffer: Vec<u8>,
+    scanned: usize,
+    depth: usize,
+    string: bool,
+    escape: bool,
+}
+
+impl Splitter {
+    /// Appends data read from the stream to the splitter.
+    pub fn extend(&mut self, data: &[u8]) {
+        self.buffer.extend_from_slice(data);
+    }
+
+    /// Returns `true` if the splitter does not contain any partially read
+    /// message data.
+    pub fn is_empty(&self) -> bool {
+        self.buffer.iter().all(u8::is_ascii_whitespace)
+    }
+
+    /// Returns the next complete message, or `None` if more data is needed.
+    pub fn next(&mut self) -> Result<Option<Vec<u8>>, Error> {
+        if self.depth == 0 {
+            let start = self
+                .buffer
+                .iter()
+                .position(|byte| !byte.is_ascii_whitespace())
+                .unwrap_or(self.buffer.len());
+            self.buffer.drain(..start);
+            self.scanned = 0;
+        }
+
+        while self.scanned < self.buffer.len() {
+            let byte = self.buffer[self.scanned];
+            self.scanned += 1;
+
+            if self.string {
+                match byte {
+                    _ if self.escape => self.escape = false,
+                    b'\\' => self.escape = true,
+                    b'"' => self.string = false,
+                    _ => {}
+                }
+                continue;
+            }
+
+            match byte {
+                b'{' | b'[' => self.depth += 1,
+                b'}' | b']' if self.depth > 0 => {
+                    self.depth -= 1;
+                    if self.depth == 0 {
+                        let message = self.buffer.drain(..self.scanned).collect();
+                        self.scanned = 0;
+                        return Ok(Some(message));
+                    }
+                }
+                _ if self.depth == 0 => {
+                    return Err(Error::from("message is not a JSON object or array"))
+                }
+                b'"' => self.string = true,
+                _ => {}
+            }
+        }
+
+        Ok(None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn split(chunks: &[&[u8]]) -> Result<Vec<String>, Error> {
+        let mut splitter = Splitter::default();
+        let mut messages = Vec::new();
+        for chunk in chunks {
+            splitter.extend(chunk);
+            while let Some(message) = splitter.next()? {
+                messages.push(String::from_utf8(message).unwrap());
+            }
+        }
+        Ok(messages)
+    }
+
+    #[test]
+    fn back_to_back_messages() {
+        assert_eq!(
+            split(&[br#"{"id":1}[{"id":2},{"id":3}]"#, b"\n{}"]).unwrap(),
+            [r#"{"id":1}"#, r#"[{"id":2},{"id":3}]"#, "{}"],
+        );
+    }
+
+    #[test]
+    fn messages_across_chunks() {
+        assert_eq!(
+            split(&[b"  {\"a\":", b"[1,", b"2]}", b"{", b"}"]).unwrap(),
+            [r#"{"a":[1,2]}"#, "{}"],
+        );
+    }
+
+    #[test]
+    fn brackets_and_escapes_in_strings() {
+        assert_eq!(
+            split(&[br#"{"a":"}]\"{","#, br#""b":"\\"}{"c":"\\\""}"#]).unwrap(),
+            [r#"{"a":"}]\"{","b":"\\"}"#, r#"{"c":"\\\""}"#],
+        );
+    }
+
+    #[test]
+    fn partial_message() {
+        let mut splitter = Splitter::default();
+        splitter.extend(br#"{"id":1}{"id""#);
+
+        assert_eq!(splitter.next().unwrap().unwrap(), br#"{"id":1}"#);
+        assert_eq!(splitter.next().unwrap(), None);
+        assert!(!splitter.is_empty());
+    }
+
+    #[test]
+    fn invalid_message() {
+        assert!(split(&[b"{}42"]).is_err());
+        assert!(split(&[b"}"]).is_err());
+    }
+}
@@ -0,0 +1,86 @@
+#![cfg(unix)]
+
+use ethrs_transport::DuplexTransport;
+use ethrs_transport_ipc::{Error, Ipc};
+use futures::executor::block_on;
+use futures::future::poll_fn;
+use std::io::{Read, Write};
+use std::os::unix::net::UnixListener;
+use std::path::PathBuf;
+use std::{env, fs, process, thread};
+
+/// Starts a local IPC socket stand-in that reads a single request and then
+/// writes the specified chunks back before closing the connection. Returns the
+/// path to the socket.
+fn node(name: &str, chunks: &'static [&'static [u8]]) -> PathBuf {
+    let path = env::temp_dir().join(format!("ethrs-{}-{}.ipc", process::id(), name));
+    let _ = fs::remove_file(&path);
+    let listener = UnixListener::bind(&path).unwrap();
+
+    thread::spawn(move || {
+        let (mut stream, _) = listener.accept().unwrap();
+        let mut request = [0; 64];
+        let _ = stream.read(&mut request).unwrap();
+        for chunk in chunks {
+            stream.write_all(chunk).unwrap();
+            stream.flush().unwrap();
+        }
+    });
+
+    path
+}
+
+async fn receive(ipc: &Ipc) -> Result<Vec<u8>, Error> {
+    poll_fn(|cx| ipc.poll_receive(cx)).await
+}
+
+#[test]
+fn back_to_back_messages() {
+    let path = node(
+        "back-to-back",
+        &[
+            br#"{"jsonrpc":"2.0","id":2,"result":"0x1"}{"jsonrpc":"2.0","#,
+            br#""id":1,"result":"}{"}"#,
+        ],
+    );
+
+    block_on(async {
+        let ipc = Ipc::connect(&path).await.unwrap();
+        ipc.send(b"[]".to_vec()).await.unwrap();
+
+        assert_eq!(
+            receive(&ipc).await.unwrap(),
+            br#"{"jsonrpc":"2.0","id":2,"result":"0x1"}"#,
+        );
+        assert_eq!(
+            receive(&ipc).await.unwrap(),
+            br#"{"jsonrpc":"2.0","id":1,"result":"}{"}"#,
+        );
+        assert_eq!(receive(&ipc).await.unwrap_err(), Error::closed());
+        assert_eq!(receive(&ipc).await.unwrap_err(), Error::closed());
+    });
+
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn closed_mid_message() {
+    let path = node("mid-message", &[br#"{"jsonrpc":"2.0","#]);
+
+    block_on(async {
+        let ipc = Ipc::connect(&path).await.unwrap();
+        ipc.send(b"[]".to_vec()).await.unwrap();
+
+        assert_ne!(receive(&ipc).await.unwrap_err(), Error::closed());
+    });
+
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn connection_error() {
+    let path = env::temp_dir().join(format!("ethrs-{}-missing.ipc", process::id()));
+    let error = block_on(Ipc::connect(path)).err().unwrap();
+
+    assert!(!error.0.is_empty());
+}
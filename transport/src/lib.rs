@@ -1,35 +1,69 @@
 //! This module contains trait and type definitions needed for implementing
 //! `ethrs` transports.
 
-use std::{error::Error, fmt::Display, future::Future};
+use std::{
+    error::Error,
+    future::Future,
+    task::{Context, Poll},
+};
 
 /// A trait to represent a simplex transport that can be used perform JSON RPC
 /// calls where the transport layer garantees that the requests and responses
 /// are matched together (JSON RPC over HTTP(S) for example).
 ///
+/// Calls only require a shared reference to the transport, so multiple calls
+/// can be in flight at the same time.
+///
 /// This trait is not suitable for duplex transports where the sent requests may
-/// come back out of order (JSON RPC over WebSockets for example).
+/// come back out of order (JSON RPC over WebSockets for example), see
+/// [`DuplexTransport`] instead.
 pub trait Transport {
     /// Error type that this transport produces.
     type Error: Error;
 
     /// Future returned by the `call` method.
-    type Call: Future<Output = Result<(), Self::Error>>;
+    type Call<'a>: Future<Output = Result<Vec<u8>, Self::Error>> + 'a
+    where
+        Self: 'a;
 
     /// Perform a JSON RPC call over this transport by sending the serialized
-    /// `request` bytes and receive the result into `response` buffer.
-    fn call(&mut self, request: &[u8], response: &mut Vec<u8>) -> Self::Call;
+    /// `request` bytes and receiving the serialized response bytes.
+    fn call<'a>(&'a self, request: &'a [u8]) -> Self::Call<'a>;
+}
+
+/// A trait for transport errors that can tell whether they are transient,
+/// meaning that sending the same request again may succeed.
+pub trait TransientError: Error {
+    /// Returns `true` if the error is transient.
+    fn is_transient(&self) -> bool;
 }
 
-/// In development.
-pub trait Transport2<'a> {
+/// A trait to represent a duplex transport that sends and receives framed JSON
+/// RPC messages independently of each other (JSON RPC over WebSockets for
+/// example). Responses may arrive out of order and the remote may send
+/// messages that were never requested, such as subscription notifications.
+///
+/// Note that the transport does not interpret the messages in any way,
+/// matching responses to their requests is done by the JSON RPC client.
+pub trait DuplexTransport {
     /// Error type that this transport produces.
-    type Error: Display;
+    type Error: Error;
 
-    /// Future returned by the `call` method.
-    type Call: Future<Output = Result<Vec<u8>, Self::Error>>;
+    /// Future returned by the `send` method.
+    type Send<'a>: Future<Output = Result<(), Self::Error>> + 'a
+    where
+        Self: 'a;
 
-    /// Perform a JSON RPC call over this transport by sending the serialized
-    /// `request` bytes and receive the result into `response` buffer.
-    fn call(&'a self, request: &'a [u8]) -> Self::Call;
+    /// Send a single serialized JSON RPC `message` over this transport.
+    ///
+    /// The message is taken by value so that sending it does not borrow from
+    /// the caller, allowing messages to be queued and sent later on.
+    fn send(&self, message: Vec<u8>) -> Self::Send<'_>;
+
+    /// Attempt to receive the next message from this transport.
+    ///
+    /// Only the waker from the most recent call is scheduled to be woken when
+    /// a new message arrives. Once the underlying connection is closed, the
+    /// transport should keep returning errors from this method.
+    fn poll_receive(&self, cx: &mut Context) -> Poll<Result<Vec<u8>, Self::Error>>;
 }
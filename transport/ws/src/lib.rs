@@ -0,0 +1,11 @@
+//! WebSocket transport implementation. Uses `tungstenite` for standard targets
+//! and the `WebSocket` API with `web-sys` for Wasm target.
+
+#[cfg(not(target_arch = "wasm32"))]
+#[path = "tungstenite.rs"]
+mod platform;
+#[cfg(target_arch = "wasm32")]
+#[path = "wasm.rs"]
+mod platform;
+
+pub use platform::*;
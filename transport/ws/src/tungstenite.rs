@@ -0,0 +1,141 @@
+//! This module contains a native WebSocket implementation using
+//! `async-tungstenite` over `async-net` sockets. Neither of these depend on a
+//! specific async runtime, so the transport can be used with any executor.
+
+use async_net::TcpStream;
+use async_tungstenite::{
+    async_tls::{self, ClientStream},
+    tungstenite::{self, client::IntoClientRequest, Message},
+    WebSocketReceiver, WebSocketSender,
+};
+use ethrs_transport::{DuplexTransport, TransientError};
+use futures_util::{lock::Mutex, stream::StreamExt};
+use std::{
+    fmt::{self, Display, Formatter},
+    future::Future,
+    io,
+    pin::Pin,
+    sync,
+    task::{Context, Poll},
+};
+
+/// A WebSocket transport implementation that works on native targets.
+///
+/// The connection is split into independent sending and receiving halves so
+/// that messages can be sent while another task is waiting to receive.
+pub struct WebSocket {
+    sender: Mutex<WebSocketSender<ClientStream<TcpStream>>>,
+    receiver: sync::Mutex<WebSocketReceiver<ClientStream<TcpStream>>>,
+}
+
+impl WebSocket {
+    /// Connects to a WebSocket node at the specified `ws://` or `wss://` URL.
+    pub async fn connect(url: impl AsRef<str>) -> Result<Self, Error> {
+        let request = url.as_ref().into_client_request()?;
+
+        let uri = request.uri();
+        let host = uri
+            .host()
+            .ok_or("missing WebSocket URL host")?
+            .trim_start_matches('[')
+            .trim_end_matches(']')
+            .to_owned();
+        let port = match (uri.port_u16(), uri.scheme_str()) {
+            (Some(port), _) => port,
+            (None, Some("wss")) => 443,
+            (None, _) => 80,
+        };
+
+        let socket = TcpStream::connect((&*host, port)).await?;
+        let (stream, _) = async_tls::client_async_tls(request, socket).await?;
+        let (sender, receiver) = stream.split();
+
+        Ok(Self {
+            sender: Mutex::new(sender),
+            receiver: sync::Mutex::new(receiver),
+        })
+    }
+}
+
+impl DuplexTransport for WebSocket {
+    type Error = Error;
+    type Send<'a> = Pin<Box<dyn Future<Output = Result<(), Self::Error>> + Send + 'a>>;
+
+    fn send(&self, message: Vec<u8>) -> Self::Send<'_> {
+        Box::pin(async move {
+            let text = String::from_utf8(message)
+                .map_err(|_| Error::from("message is not valid UTF-8"))?;
+            self.sender.lock().await.send(Message::text(text)).await?;
+            Ok(())
+        })
+    }
+
+    fn poll_receive(&self, cx: &mut Context) -> Poll<Result<Vec<u8>, Self::Error>> {
+        let mut receiver = self.receiver.lock().expect("poisoned WebSocket receiver");
+        loop {
+            let message = match receiver.poll_next_unpin(cx) {
+                Poll::Ready(Some(message)) => message?,
+                Poll::Ready(None) => return Poll::Ready(Err(Error::closed())),
+                Poll::Pending => return Poll::Pending,
+            };
+
+            match message {
+                Message::Text(text) => return Poll::Ready(Ok(text.as_bytes().to_vec())),
+                Message::Binary(data) => return Poll::Ready(Ok(data.to_vec())),
+                Message::Close(_) => return Poll::Ready(Err(Error::closed())),
+                // NOTE: Ping replies are handled by `tungstenite` internally.
+                Message::Ping(_) | Message::Pong(_) | Message::Frame(_) => {}
+            }
+        }
+    }
+}
+
+/// A WebSocket transport error.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Error(pub String);
+
+impl Error {
+    /// Creates a new error for when the WebSocket connection was closed.
+    pub fn closed() -> Self {
+        Self("WebSocket connection closed".to_owned())
+    }
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        Display::fmt(&self.0, f)
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl TransientError for Error {
+    /// Errors are never transient, as the WebSocket connection can't be used again
+    /// once an error occurred.
+    fn is_transient(&self) -> bool {
+        false
+    }
+}
+
+impl From<tungstenite::Error> for Error {
+    fn from(err: tungstenite::Error) -> Self {
+        match err {
+            tungstenite::Error::ConnectionClosed | tungstenite::Error::AlreadyClosed => {
+                Self::closed()
+            }
+            err => Self(err.to_string()),
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Self {
+        Self(err.to_string())
+    }
+}
+
+impl From<&str> for Error {
+    fn from(message: &str) -> Self {
+        Self(message.to_owned())
+    }
+}
@@ -0,0 +1,182 @@
+//! This module contains a Wasm compatible WebSocket implementation using the
+//! `WebSocket` API. In order to work with NodeJS, a runtime with a global
+//! `WebSocket` implementation is needed.
+
+use ethrs_transport::{DuplexTransport, TransientError};
+use js_sys::{ArrayBuffer, Promise, Uint8Array};
+use std::{
+    cell::RefCell,
+    collections::VecDeque,
+    fmt::{self, Display, Formatter},
+    future::{self, Ready},
+    rc::Rc,
+    task::{Context, Poll, Waker},
+};
+use wasm_bindgen::{prelude::*, JsCast};
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{BinaryType, CloseEvent, MessageEvent};
+
+/// A WebSocket transport implementation that works on wasm32 targets using the
+/// native JavaScript `WebSocket` API.
+///
+/// Received messages are buffered by the JavaScript event handlers until they
+/// are polled from the transport.
+pub struct WebSocket {
+    socket: web_sys::WebSocket,
+    inbox: Rc<RefCell<Inbox>>,
+    _onmessage: Closure<dyn FnMut(MessageEvent)>,
+    _onclose: Closure<dyn FnMut(CloseEvent)>,
+}
+
+/// Buffered messages received from the JavaScript event handlers.
+#[derive(Default)]
+struct Inbox {
+    messages: VecDeque<Result<Vec<u8>, Error>>,
+    closed: bool,
+    waker: Option<Waker>,
+}
+
+impl Inbox {
+    fn push(&mut self, message: Result<Vec<u8>, Error>) {
+        self.messages.push_back(message);
+        if let Some(waker) = self.waker.take() {
+            waker.wake();
+        }
+    }
+}
+
+impl WebSocket {
+    /// Connects to a WebSocket node at the specified `ws://` or `wss://` URL.
+    pub async fn connect(url: impl AsRef<str>) -> Result<Self, Error> {
+        let socket = web_sys::WebSocket::new(url.as_ref())?;
+        socket.set_binary_type(BinaryType::Arraybuffer);
+
+        let opened = Promise::new(&mut |resolve, reject| {
+            socket.set_onopen(Some(&resolve));
+            socket.set_onerror(Some(&reject));
+        });
+        let result = JsFuture::from(opened).await;
+        socket.set_onopen(None);
+        socket.set_onerror(None);
+        result.map_err(|_| Error::from("failed to open WebSocket connection"))?;
+
+        let inbox = Rc::new(RefCell::new(Inbox::default()));
+        let onmessage = Closure::wrap(Box::new({
+            let inbox = inbox.clone();
+            move |event: MessageEvent| inbox.borrow_mut().push(message_data(event.data()))
+        }) as Box<dyn FnMut(MessageEvent)>);
+        let onclose = Closure::wrap(Box::new({
+            let inbox = inbox.clone();
+            move |_: CloseEvent| {
+                let mut inbox = inbox.borrow_mut();
+                inbox.closed = true;
+                if let Some(waker) = inbox.waker.take() {
+                    waker.wake();
+                }
+            }
+        }) as Box<dyn FnMut(CloseEvent)>);
+        socket.set_onmessage(Some(onmessage.as_ref().unchecked_ref()));
+        socket.set_onclose(Some(onclose.as_ref().unchecked_ref()));
+
+        Ok(Self {
+            socket,
+            inbox,
+            _onmessage: onmessage,
+            _onclose: onclose,
+        })
+    }
+}
+
+impl DuplexTransport for WebSocket {
+    type Error = Error;
+    type Send<'a> = Ready<Result<(), Self::Error>>;
+
+    fn send(&self, message: Vec<u8>) -> Self::Send<'_> {
+        future::ready(match String::from_utf8(message) {
+            Ok(text) => self.socket.send_with_str(&text).map_err(Error::from),
+            Err(_) => Err("message is not valid UTF-8".into()),
+        })
+    }
+
+    fn poll_receive(&self, cx: &mut Context) -> Poll<Result<Vec<u8>, Self::Error>> {
+        let mut inbox = self.inbox.borrow_mut();
+        if let Some(message) = inbox.messages.pop_front() {
+            return Poll::Ready(message);
+        }
+        if inbox.closed {
+            return Poll::Ready(Err(Error::closed()));
+        }
+
+        inbox.waker = Some(cx.waker().clone());
+        Poll::Pending
+    }
+}
+
+impl Drop for WebSocket {
+    fn drop(&mut self) {
+        self.socket.set_onmessage(None);
+        self.socket.set_onclose(None);
+        let _ = self.socket.close();
+    }
+}
+
+/// Reads the data from a WebSocket message event.
+fn message_data(data: JsValue) -> Result<Vec<u8>, Error> {
+    if let Some(text) = data.as_string() {
+        Ok(text.into_bytes())
+    } else if let Some(buffer) = data.dyn_ref::<ArrayBuffer>() {
+        Ok(Uint8Array::new(buffer).to_vec())
+    } else {
+        Err("unsupported WebSocket message data".into())
+    }
+}
+
+/// A WebSocket transport error.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Error(pub String);
+
+impl Error {
+    /// Creates a new error for when the WebSocket connection was closed.
+    pub fn closed() -> Self {
+        Self("WebSocket connection closed".to_owned())
+    }
+
+    /// Creates a new unknown error when inspecting the JavaScript error type
+    /// fails.
+    #[doc(hidden)]
+    pub fn unknown() -> Self {
+        Self("unknown error".to_owned())
+    }
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        Display::fmt(&self.0, f)
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl TransientError for Error {
+    /// Errors are never transient, as the WebSocket connection can't be used again
+    /// once an error occurred.
+    fn is_transient(&self) -> bool {
+        false
+    }
+}
+
+impl From<JsValue> for Error {
+    fn from(value: JsValue) -> Self {
+        js_sys::Error::from(value)
+            .message()
+            .as_string()
+            .map(Error)
+            .unwrap_or_else(Error::unknown)
+    }
+}
+
+impl From<&str> for Error {
+    fn from(message: &str) -> Self {
+        Self(message.to_owned())
+    }
+}
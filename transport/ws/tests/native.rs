@@ -0,0 +1,93 @@
+#![cfg(not(target_arch = "wasm32"))]
+
+use ethrs_transport::DuplexTransport;
+use ethrs_transport_ws::{Error, WebSocket};
+use futures::executor::block_on;
+use futures::future::{self, poll_fn};
+use std::net::TcpListener;
+use std::thread;
+use tungstenite::Message;
+
+/// Starts a local WebSocket server that accepts a single connection, waits for
+/// `count` messages and sends them back in reverse order before closing the
+/// connection. Returns the URL of the server.
+fn reverse_server(count: usize) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("ws://{}", listener.local_addr().unwrap());
+
+    thread::spawn(move || {
+        let (stream, _) = listener.accept().unwrap();
+        let mut socket = tungstenite::accept(stream).unwrap();
+
+        let mut messages = Vec::new();
+        while messages.len() < count {
+            match socket.read().unwrap() {
+                message @ Message::Text(_) => messages.push(message),
+                _ => continue,
+            }
+        }
+        for message in messages.into_iter().rev() {
+            socket.send(message).unwrap();
+        }
+        socket.close(None).unwrap();
+        while socket.read().is_ok() {}
+    });
+
+    url
+}
+
+#[test]
+fn send_and_receive() {
+    block_on(async {
+        let socket = WebSocket::connect(reverse_server(1)).await.unwrap();
+        socket.send(br#"{"id":1}"#.to_vec()).await.unwrap();
+        let message = poll_fn(|cx| socket.poll_receive(cx)).await.unwrap();
+
+        assert_eq!(message, br#"{"id":1}"#);
+    });
+}
+
+#[test]
+fn concurrent_messages() {
+    block_on(async {
+        let socket = WebSocket::connect(reverse_server(3)).await.unwrap();
+        let sent = future::join3(
+            socket.send(b"1".to_vec()),
+            socket.send(b"2".to_vec()),
+            socket.send(b"3".to_vec()),
+        )
+        .await;
+        assert!(matches!(sent, (Ok(()), Ok(()), Ok(()))));
+
+        let mut received = Vec::new();
+        for _ in 0..3 {
+            received.push(poll_fn(|cx| socket.poll_receive(cx)).await.unwrap());
+        }
+        received.sort();
+
+        assert_eq!(received, [b"1", b"2", b"3"]);
+    });
+}
+
+#[test]
+fn closed_connection() {
+    block_on(async {
+        let socket = WebSocket::connect(reverse_server(0)).await.unwrap();
+        let error = poll_fn(|cx| socket.poll_receive(cx)).await.unwrap_err();
+
+        assert_eq!(error, Error::closed());
+        assert_eq!(
+            poll_fn(|cx| socket.poll_receive(cx)).await.unwrap_err(),
+            Error::closed(),
+        );
+    });
+}
+
+#[test]
+fn connection_error() {
+    let error = block_on(WebSocket::connect("ws://127.0.0.1:1"))
+        .err()
+        .unwrap();
+
+    assert!(!error.0.is_empty());
+}